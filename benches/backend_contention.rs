@@ -0,0 +1,76 @@
+//! Compare `MemoryBackend` and `ConcurrentMemoryBackend` under contention
+//!
+//! Run with `cargo bench --features dashmap-backend`.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use std::collections::HashMap;
+use threatflux_cache::{CacheEntry, ConcurrentMemoryBackend, MemoryBackend, StorageBackend};
+
+const KEYS: usize = 1_000;
+const OPS_PER_TASK: usize = 1_000;
+
+/// Fill `backend` with `KEYS` single-entry keys
+async fn populate<B>(backend: &B)
+where
+    B: StorageBackend<Key = String, Value = String, Metadata = ()>,
+{
+    let entries: HashMap<_, _> = (0..KEYS)
+        .map(|i| {
+            let key = format!("key-{i}");
+            (key.clone(), vec![CacheEntry::new(key, i.to_string())])
+        })
+        .collect();
+    backend.save(&entries).await.unwrap();
+}
+
+/// Run `tasks` tasks that each mix lookups with the occasional removal
+async fn hammer<B>(backend: &B, tasks: usize)
+where
+    B: StorageBackend<Key = String, Value = String, Metadata = ()> + Clone + 'static,
+{
+    let handles: Vec<_> = (0..tasks)
+        .map(|task| {
+            let backend = backend.clone();
+            tokio::spawn(async move {
+                for op in 0..OPS_PER_TASK {
+                    let key = format!("key-{}", (task * OPS_PER_TASK + op) % KEYS);
+                    if op % 10 == 0 {
+                        backend.remove(&key).await.unwrap();
+                    } else {
+                        backend.load_key(&key).await.unwrap();
+                    }
+                }
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.await.unwrap();
+    }
+}
+
+fn bench_contention(c: &mut Criterion) {
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    let mut group = c.benchmark_group("backend_contention");
+
+    for tasks in [1, 4, 16] {
+        let memory = MemoryBackend::<String, String>::new();
+        runtime.block_on(populate(&memory));
+        group.bench_with_input(BenchmarkId::new("memory", tasks), &tasks, |b, &tasks| {
+            b.to_async(&runtime).iter(|| hammer(&memory, tasks));
+        });
+
+        let concurrent = ConcurrentMemoryBackend::<String, String>::new();
+        runtime.block_on(populate(&concurrent));
+        group.bench_with_input(BenchmarkId::new("dashmap", tasks), &tasks, |b, &tasks| {
+            b.to_async(&runtime).iter(|| hammer(&concurrent, tasks));
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_contention);
+criterion_main!(benches);
@@ -112,7 +112,7 @@ async fn show_entries(cache: &DocCache) -> Result<()> {
             println!(
                 "Entry: {} - Access count: {}, Age: {:?}",
                 entry.value.title,
-                entry.access_count,
+                entry.access_count(),
                 entry.age()
             );
         }
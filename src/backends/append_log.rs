@@ -0,0 +1,500 @@
+//! Append-only log storage backend
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tokio::fs::{self, OpenOptions};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+
+use crate::backends::{fnv1a, namespace_segment, StorageKey, StorageMeta, StorageValue};
+use crate::{storage::EntryMap, CacheEntry, CacheError, Result, StorageBackend};
+
+/// Type alias for complex phantom data type
+type PhantomTypes<K, V, M> = std::marker::PhantomData<(K, V, M)>;
+
+/// File the log is appended to
+const LOG_FILE: &str = "log.jsonl";
+
+/// File `compact` folds the log into
+const SNAPSHOT_FILE: &str = "snapshot.jsonl";
+
+/// One line of the log or the snapshot
+///
+/// Written with borrowed fields and read back with owned ones.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+enum LogRecord<K, E> {
+    /// The key's entries became `entries`
+    Put {
+        at: DateTime<Utc>,
+        key: K,
+        entries: E,
+    },
+    /// The key was removed
+    Remove { at: DateTime<Utc>, key: K },
+    /// Every key was removed
+    Clear { at: DateTime<Utc> },
+}
+
+/// Storage backend that records every change as a line appended to a log
+///
+/// Where [`FilesystemBackend`](crate::FilesystemBackend) overwrites one file
+/// per key, this backend never rewrites what it has written: `save` appends
+/// a `put` record for each key whose entries changed and a `remove` record
+/// for each key that is gone, `remove` and `clear` append tombstones, and
+/// `load` replays the records in order. The log, `log.jsonl` in the base
+/// directory, is JSON with one timestamped record per line, so it doubles as
+/// an audit trail of writes.
+///
+/// The log only grows until [`compact`](StorageBackend::compact) folds it into
+/// `snapshot.jsonl` and truncates it, discarding the history up to that
+/// point. Changes to access counts alone are not logged, since they come
+/// from reads.
+///
+/// One backend per directory is assumed: changes are tracked in memory to
+/// decide what a save appends, and nothing stops two processes from
+/// appending to the same log.
+#[allow(clippy::type_complexity)]
+pub struct AppendLogBackend<K, V, M = ()>
+where
+    K: StorageKey,
+    V: StorageValue,
+    M: StorageMeta,
+{
+    base_path: PathBuf,
+    /// Digest of the entries last logged for each live key; the lock also
+    /// keeps appends from interleaving
+    logged: Mutex<HashMap<K, u64>>,
+    _phantom: PhantomTypes<K, V, M>,
+}
+
+impl<K, V, M> AppendLogBackend<K, V, M>
+where
+    K: StorageKey,
+    V: StorageValue,
+    M: StorageMeta,
+{
+    /// Open the log in `base_path`, creating the directory if needed
+    ///
+    /// The existing log is replayed to learn which keys are stored. A record
+    /// left half-written by a crash is cut off the end of the log.
+    pub async fn new<P: AsRef<Path>>(base_path: P) -> Result<Self> {
+        let base_path = base_path.as_ref().to_path_buf();
+        fs::create_dir_all(&base_path).await?;
+
+        let mut backend = Self {
+            base_path,
+            logged: Mutex::default(),
+            _phantom: std::marker::PhantomData,
+        };
+        let (state, valid_len) = backend.replay().await?;
+        let log_path = backend.log_path();
+        if fs::metadata(&log_path).await.map(|m| m.len()).unwrap_or(0) > valid_len {
+            let log = OpenOptions::new().write(true).open(&log_path).await?;
+            log.set_len(valid_len).await?;
+        }
+        *backend.logged.get_mut() = digests(&state)?;
+        Ok(backend)
+    }
+
+    /// Path of the log file
+    pub fn log_path(&self) -> PathBuf {
+        self.base_path.join(LOG_FILE)
+    }
+
+    fn snapshot_path(&self) -> PathBuf {
+        self.base_path.join(SNAPSHOT_FILE)
+    }
+
+    /// Rebuild the stored entries from the snapshot and the log, also
+    /// returning the length of the log up to its last complete record
+    async fn replay(&self) -> Result<(EntryMap<K, V, M>, u64)> {
+        let mut state = EntryMap::new();
+        replay_file(&self.snapshot_path(), &mut state).await?;
+        let valid_len = replay_file(&self.log_path(), &mut state).await?;
+        Ok((state, valid_len))
+    }
+
+    /// Append `records`, which are complete lines, and flush them to disk
+    async fn append(&self, records: &[u8]) -> Result<()> {
+        if records.is_empty() {
+            return Ok(());
+        }
+        let mut log = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.log_path())
+            .await?;
+        log.write_all(records).await?;
+        log.sync_data().await?;
+        Ok(())
+    }
+}
+
+/// Apply the records in `path` to `state`, returning the length of the file
+/// up to the end of its last complete line
+///
+/// A final line without a newline is a torn append and is ignored; any other
+/// line that does not parse is an error.
+async fn replay_file<K, V, M>(path: &Path, state: &mut EntryMap<K, V, M>) -> Result<u64>
+where
+    K: StorageKey,
+    V: StorageValue,
+    M: StorageMeta,
+{
+    let data = match fs::read(path).await {
+        Ok(data) => data,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut valid_len = 0;
+    for (number, line) in data.split_inclusive(|&byte| byte == b'\n').enumerate() {
+        if !line.ends_with(b"\n") {
+            break;
+        }
+        valid_len += line.len();
+        if line.iter().all(u8::is_ascii_whitespace) {
+            continue;
+        }
+        let record: LogRecord<K, Vec<CacheEntry<K, V, M>>> =
+            serde_json::from_slice(line).map_err(|e| {
+                CacheError::Serialization(format!("{} line {}: {e}", path.display(), number + 1))
+            })?;
+        match record {
+            LogRecord::Put { key, entries, .. } => {
+                state.insert(key, entries);
+            }
+            LogRecord::Remove { key, .. } => {
+                state.remove(&key);
+            }
+            LogRecord::Clear { .. } => state.clear(),
+        }
+    }
+    Ok(valid_len as u64)
+}
+
+/// Serialize `record` as one line onto `buffer`
+fn push_record<T: Serialize>(buffer: &mut Vec<u8>, record: &T) -> Result<()> {
+    serde_json::to_writer(&mut *buffer, record)
+        .map_err(|e| CacheError::Serialization(e.to_string()))?;
+    buffer.push(b'\n');
+    Ok(())
+}
+
+/// Hash of what a save would log for `entries`, leaving out access counts
+/// and times so reads do not count as changes
+fn digest<K, V, M>(entries: &[CacheEntry<K, V, M>]) -> Result<u64>
+where
+    K: StorageKey,
+    V: StorageValue,
+    M: StorageMeta,
+{
+    let contents: Vec<_> = entries
+        .iter()
+        .map(|e| (&e.value, &e.metadata, e.timestamp, e.expiry))
+        .collect();
+    let json =
+        serde_json::to_vec(&contents).map_err(|e| CacheError::Serialization(e.to_string()))?;
+    Ok(fnv1a(&json))
+}
+
+fn digests<K, V, M>(state: &EntryMap<K, V, M>) -> Result<HashMap<K, u64>>
+where
+    K: StorageKey,
+    V: StorageValue,
+    M: StorageMeta,
+{
+    state
+        .iter()
+        .map(|(key, entries)| Ok((key.clone(), digest(entries)?)))
+        .collect()
+}
+
+#[async_trait]
+impl<K, V, M> StorageBackend for AppendLogBackend<K, V, M>
+where
+    K: StorageKey,
+    V: StorageValue,
+    M: StorageMeta,
+{
+    type Key = K;
+    type Value = V;
+    type Metadata = M;
+
+    async fn save(&self, entries: &EntryMap<K, V, M>) -> Result<()> {
+        let mut logged = self.logged.lock().await;
+        let at = Utc::now();
+        let mut records = Vec::new();
+        let mut saved = HashMap::with_capacity(entries.len());
+        for (key, bucket) in entries {
+            let digest = digest(bucket)?;
+            if logged.get(key) != Some(&digest) {
+                let record = LogRecord::Put {
+                    at,
+                    key,
+                    entries: bucket.as_slice(),
+                };
+                push_record(&mut records, &record)?;
+            }
+            saved.insert(key.clone(), digest);
+        }
+        for key in logged.keys().filter(|key| !entries.contains_key(*key)) {
+            push_record(&mut records, &LogRecord::<_, ()>::Remove { at, key })?;
+        }
+
+        self.append(&records).await?;
+        *logged = saved;
+        Ok(())
+    }
+
+    async fn load(&self) -> Result<EntryMap<K, V, M>> {
+        let mut logged = self.logged.lock().await;
+        let (state, _) = self.replay().await?;
+        *logged = digests(&state)?;
+        Ok(state)
+    }
+
+    async fn remove(&self, key: &K) -> Result<()> {
+        let mut logged = self.logged.lock().await;
+        let mut record = Vec::new();
+        push_record(
+            &mut record,
+            &LogRecord::<_, ()>::Remove {
+                at: Utc::now(),
+                key,
+            },
+        )?;
+        self.append(&record).await?;
+        logged.remove(key);
+        Ok(())
+    }
+
+    /// Append a `clear` record and delete the snapshot, which the record
+    /// makes irrelevant
+    async fn clear(&self) -> Result<()> {
+        let mut logged = self.logged.lock().await;
+        let mut record = Vec::new();
+        push_record(&mut record, &LogRecord::<K, ()>::Clear { at: Utc::now() })?;
+        self.append(&record).await?;
+        logged.clear();
+        match fs::remove_file(self.snapshot_path()).await {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    async fn size_bytes(&self) -> Result<u64> {
+        let mut total_size = 0;
+        for path in [self.snapshot_path(), self.log_path()] {
+            if let Ok(metadata) = fs::metadata(&path).await {
+                total_size += metadata.len();
+            }
+        }
+        Ok(total_size)
+    }
+
+    /// Fold the log into the snapshot, dropping expired entries, and
+    /// truncate the log
+    ///
+    /// The snapshot is replaced atomically before the log is truncated. A
+    /// crash in between leaves records that are already in the snapshot in
+    /// the log too, which replaying twice does not change.
+    async fn compact(&self) -> Result<()> {
+        let mut logged = self.logged.lock().await;
+        let (mut state, _) = self.replay().await?;
+        state.retain(|_, bucket| {
+            bucket.retain(|entry| !entry.is_expired());
+            !bucket.is_empty()
+        });
+
+        let at = Utc::now();
+        let mut snapshot = Vec::new();
+        for (key, entries) in &state {
+            let record = LogRecord::Put {
+                at,
+                key,
+                entries: entries.as_slice(),
+            };
+            push_record(&mut snapshot, &record)?;
+        }
+        let temp_path = self.base_path.join(format!("{SNAPSHOT_FILE}.tmp"));
+        let mut file = fs::File::create(&temp_path).await?;
+        file.write_all(&snapshot).await?;
+        file.sync_all().await?;
+        fs::rename(&temp_path, self.snapshot_path()).await?;
+
+        if let Ok(log) = OpenOptions::new().write(true).open(self.log_path()).await {
+            log.set_len(0).await?;
+        }
+        *logged = digests(&state)?;
+        Ok(())
+    }
+
+    /// Move the backend into a subdirectory of its base path named after
+    /// the namespace
+    ///
+    /// A namespace that is not a safe directory name as is gets a sanitized
+    /// name with a hash suffix.
+    async fn with_namespace(self, namespace: &str) -> Result<Self> {
+        let Some(directory) = namespace_segment(namespace) else {
+            return Err(CacheError::InvalidConfiguration(format!(
+                "namespace {namespace:?} is not a valid directory name"
+            )));
+        };
+        Self::new(self.base_path.join(directory)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    type Backend = AppendLogBackend<String, String>;
+
+    fn entries(items: &[(&str, &str)]) -> EntryMap<String, String, ()> {
+        items
+            .iter()
+            .map(|(key, value)| {
+                let entry = CacheEntry::new(key.to_string(), value.to_string());
+                (key.to_string(), vec![entry])
+            })
+            .collect()
+    }
+
+    async fn ops(backend: &Backend) -> Vec<String> {
+        let log = fs::read_to_string(backend.log_path()).await.unwrap();
+        log.lines()
+            .map(|line| {
+                let record: serde_json::Value = serde_json::from_str(line).unwrap();
+                let op = record["op"].as_str().unwrap();
+                match record["key"].as_str() {
+                    Some(key) => format!("{op} {key}"),
+                    None => op.to_string(),
+                }
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_saves_append_only_changes() {
+        let temp_dir = TempDir::new().unwrap();
+        let backend = Backend::new(temp_dir.path()).await.unwrap();
+
+        let first = entries(&[("a", "1"), ("b", "1")]);
+        backend.save(&first).await.unwrap();
+        backend.save(&first).await.unwrap();
+        let mut logged = ops(&backend).await;
+        logged.sort();
+        assert_eq!(logged, ["put a", "put b"]);
+
+        // Reads alone are not changes
+        first["a"][0].record_access();
+        backend.save(&first).await.unwrap();
+        assert_eq!(ops(&backend).await.len(), 2);
+
+        backend.save(&entries(&[("a", "2")])).await.unwrap();
+        backend.remove(&"a".to_string()).await.unwrap();
+        backend.save(&entries(&[("c", "1")])).await.unwrap();
+        backend.clear().await.unwrap();
+        assert_eq!(
+            ops(&backend).await[2..],
+            ["put a", "remove b", "remove a", "put c", "clear"]
+        );
+        assert!(backend.load().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_namespaces_that_sanitize_alike_stay_apart() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = Backend::new(temp_dir.path()).await.unwrap();
+        let nested = root.with_namespace("tenant/a").await.unwrap();
+        let plain = Backend::new(temp_dir.path())
+            .await
+            .unwrap()
+            .with_namespace("tenant_a")
+            .await
+            .unwrap();
+        assert_eq!(plain.base_path, temp_dir.path().join("tenant_a"));
+        assert_ne!(nested.base_path, plain.base_path);
+
+        nested.save(&entries(&[("key", "nested")])).await.unwrap();
+        assert!(plain.load().await.unwrap().is_empty());
+        assert!(Backend::new(temp_dir.path())
+            .await
+            .unwrap()
+            .with_namespace(" ")
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_log_replays_after_reopening() {
+        let temp_dir = TempDir::new().unwrap();
+        let backend = Backend::new(temp_dir.path()).await.unwrap();
+        backend
+            .save(&entries(&[("a", "1"), ("b", "1")]))
+            .await
+            .unwrap();
+        backend.save(&entries(&[("a", "2")])).await.unwrap();
+
+        let reopened = Backend::new(temp_dir.path()).await.unwrap();
+        let loaded = reopened.load().await.unwrap();
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded["a"][0].value, "2");
+
+        // Reopening remembers what is logged, so nothing is appended again
+        reopened.save(&loaded).await.unwrap();
+        assert_eq!(ops(&reopened).await.len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_compact_folds_log_into_snapshot() {
+        let temp_dir = TempDir::new().unwrap();
+        let backend = Backend::new(temp_dir.path()).await.unwrap();
+        let mut saved = entries(&[("a", "1"), ("b", "1")]);
+        saved.insert(
+            "expired".to_string(),
+            vec![CacheEntry::new("expired".to_string(), "v".to_string())
+                .with_ttl(chrono::Duration::milliseconds(-1))],
+        );
+        backend.save(&saved).await.unwrap();
+        backend.remove(&"b".to_string()).await.unwrap();
+
+        backend.compact().await.unwrap();
+        assert!(ops(&backend).await.is_empty());
+        let loaded = Backend::new(temp_dir.path())
+            .await
+            .unwrap()
+            .load()
+            .await
+            .unwrap();
+        assert_eq!(loaded.keys().collect::<Vec<_>>(), ["a"]);
+
+        // Later records apply on top of the snapshot
+        backend.save(&entries(&[("a", "2")])).await.unwrap();
+        assert_eq!(backend.load().await.unwrap()["a"][0].value, "2");
+    }
+
+    #[tokio::test]
+    async fn test_torn_record_is_cut_off() {
+        let temp_dir = TempDir::new().unwrap();
+        let backend = Backend::new(temp_dir.path()).await.unwrap();
+        backend.save(&entries(&[("a", "1")])).await.unwrap();
+        let mut log = OpenOptions::new()
+            .append(true)
+            .open(backend.log_path())
+            .await
+            .unwrap();
+        log.write_all(b"{\"op\":\"put\",\"at\"").await.unwrap();
+
+        let reopened = Backend::new(temp_dir.path()).await.unwrap();
+        reopened.save(&entries(&[("b", "1")])).await.unwrap();
+        assert_eq!(reopened.load().await.unwrap().len(), 1);
+        assert_eq!(ops(&reopened).await, ["put a", "put b", "remove a"]);
+    }
+}
@@ -0,0 +1,198 @@
+//! Concurrent in-memory storage backend built on `DashMap`
+
+use ::dashmap::DashMap;
+use async_trait::async_trait;
+use std::sync::Arc;
+
+use crate::backends::{StorageKey, StorageMeta, StorageValue};
+use crate::storage::EntryMap;
+use crate::{CacheEntry, Result, StorageBackend};
+
+/// Type alias for the entries of one namespace
+type NamespaceEntries<K, V, M> = Arc<DashMap<K, Vec<CacheEntry<K, V, M>>>>;
+
+/// In-memory storage backend for heavy concurrent use
+///
+/// Behaves like [`MemoryBackend`](crate::MemoryBackend), but entries live in
+/// a sharded `DashMap` instead of behind one lock, so calls on different keys
+/// rarely wait on each other. This pays off when many tasks call the backend
+/// directly; behind a [`Cache`](crate::Cache), which only saves in bulk,
+/// `MemoryBackend` is just as good.
+///
+/// Clones share the same storage, and each namespace gets its own map. A save
+/// replaces the namespace's keys one at a time, so a concurrent `load` may
+/// see some keys from before the save and some from after it.
+#[allow(clippy::type_complexity)]
+pub struct ConcurrentMemoryBackend<K, V, M = ()>
+where
+    K: StorageKey,
+    V: StorageValue,
+    M: StorageMeta,
+{
+    namespaces: Arc<DashMap<String, NamespaceEntries<K, V, M>>>,
+    entries: NamespaceEntries<K, V, M>,
+}
+
+impl<K, V, M> ConcurrentMemoryBackend<K, V, M>
+where
+    K: StorageKey,
+    V: StorageValue,
+    M: StorageMeta,
+{
+    /// Create a new concurrent memory backend
+    pub fn new() -> Self {
+        let entries = NamespaceEntries::default();
+        let namespaces = DashMap::new();
+        namespaces.insert(String::new(), Arc::clone(&entries));
+        Self {
+            namespaces: Arc::new(namespaces),
+            entries,
+        }
+    }
+}
+
+impl<K, V, M> Default for ConcurrentMemoryBackend<K, V, M>
+where
+    K: StorageKey,
+    V: StorageValue,
+    M: StorageMeta,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V, M> Clone for ConcurrentMemoryBackend<K, V, M>
+where
+    K: StorageKey,
+    V: StorageValue,
+    M: StorageMeta,
+{
+    fn clone(&self) -> Self {
+        Self {
+            namespaces: Arc::clone(&self.namespaces),
+            entries: Arc::clone(&self.entries),
+        }
+    }
+}
+
+#[async_trait]
+impl<K, V, M> StorageBackend for ConcurrentMemoryBackend<K, V, M>
+where
+    K: StorageKey,
+    V: StorageValue,
+    M: StorageMeta,
+{
+    type Key = K;
+    type Value = V;
+    type Metadata = M;
+
+    async fn save(&self, entries: &EntryMap<K, V, M>) -> Result<()> {
+        self.entries.retain(|key, _| entries.contains_key(key));
+        for (key, bucket) in entries {
+            self.entries.insert(key.clone(), bucket.clone());
+        }
+        Ok(())
+    }
+
+    async fn load(&self) -> Result<EntryMap<K, V, M>> {
+        Ok(self
+            .entries
+            .iter()
+            .map(|bucket| (bucket.key().clone(), bucket.value().clone()))
+            .collect())
+    }
+
+    async fn load_key(&self, key: &K) -> Result<Option<Vec<CacheEntry<K, V, M>>>> {
+        Ok(self.entries.get(key).map(|bucket| bucket.value().clone()))
+    }
+
+    async fn remove(&self, key: &K) -> Result<()> {
+        self.entries.remove(key);
+        Ok(())
+    }
+
+    async fn clear(&self) -> Result<()> {
+        self.entries.clear();
+        Ok(())
+    }
+
+    async fn contains(&self, key: &K) -> Result<bool> {
+        Ok(self.entries.contains_key(key))
+    }
+
+    async fn size_bytes(&self) -> Result<u64> {
+        // Estimate size based on number of entries
+        let total_entries: usize = self.entries.iter().map(|bucket| bucket.len()).sum();
+        let estimated_size = total_entries * std::mem::size_of::<CacheEntry<K, V, M>>();
+
+        Ok(estimated_size as u64)
+    }
+
+    async fn with_namespace(self, namespace: &str) -> Result<Self> {
+        let entries = Arc::clone(
+            self.namespaces
+                .entry(namespace.to_string())
+                .or_default()
+                .value(),
+        );
+        Ok(Self { entries, ..self })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries(keys: &[&str]) -> EntryMap<String, String, ()> {
+        keys.iter()
+            .map(|key| {
+                let entry = CacheEntry::new(key.to_string(), format!("{key}-value"));
+                (key.to_string(), vec![entry])
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_save_replaces_all_keys() {
+        let backend: ConcurrentMemoryBackend<String, String> = ConcurrentMemoryBackend::new();
+        let clone = backend.clone();
+
+        backend.save(&entries(&["a", "b"])).await.unwrap();
+        assert!(clone.contains(&"a".to_string()).await.unwrap());
+
+        backend.save(&entries(&["b", "c"])).await.unwrap();
+        let loaded = clone.load().await.unwrap();
+        let mut keys: Vec<_> = loaded.keys().cloned().collect();
+        keys.sort();
+        assert_eq!(keys, ["b", "c"]);
+        assert_eq!(
+            backend.load_key(&"c".to_string()).await.unwrap().unwrap()[0].value,
+            "c-value"
+        );
+        assert!(backend.size_bytes().await.unwrap() > 0);
+
+        backend.remove(&"b".to_string()).await.unwrap();
+        assert!(!clone.contains(&"b".to_string()).await.unwrap());
+        backend.clear().await.unwrap();
+        assert!(clone.load().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_namespaces_share_storage_but_not_entries() {
+        let shared: ConcurrentMemoryBackend<String, String> = ConcurrentMemoryBackend::new();
+        let tenant_a = shared.clone().with_namespace("a").await.unwrap();
+        let tenant_b = shared.clone().with_namespace("b").await.unwrap();
+        let tenant_a_again = shared.clone().with_namespace("a").await.unwrap();
+
+        tenant_a.save(&entries(&["key"])).await.unwrap();
+        assert!(tenant_a_again.contains(&"key".to_string()).await.unwrap());
+        assert!(!tenant_b.contains(&"key".to_string()).await.unwrap());
+        assert!(!shared.contains(&"key".to_string()).await.unwrap());
+
+        tenant_b.save(&entries(&["key"])).await.unwrap();
+        tenant_a.clear().await.unwrap();
+        assert_eq!(tenant_b.load().await.unwrap().len(), 1);
+        assert!(shared.load().await.unwrap().is_empty());
+    }
+}
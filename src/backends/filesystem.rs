@@ -1,34 +1,215 @@
 //! Filesystem storage backend
 
 use async_trait::async_trait;
+use fs2::FileExt;
+use futures::stream::{self, StreamExt, TryStreamExt};
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
-use std::collections::HashMap;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
 use tokio::fs::{self, File};
 use tokio::io::AsyncWriteExt;
 
-use crate::backends::{StorageKey, StorageMeta, StorageValue};
+use crate::backends::{
+    cache_file_stem, fnv1a, legacy_cache_file_stem, namespace_segment, StorageKey, StorageMeta,
+    StorageValue,
+};
+#[cfg(feature = "compression")]
+use crate::config::{CompressionAlgorithm, ValueCompression};
 use crate::{
-    storage::{EntryMap, SerializationFormat},
-    CacheEntry, EntryMetadata, Result, StorageBackend,
+    storage::{EntryMap, EntryStream, JsonStyle, SerializationFormat, DEFAULT_FORMAT},
+    CacheEntry, CacheError, EntryMetadata, Result, StorageBackend,
 };
 
 /// Type alias for complex phantom data type
 type PhantomTypes<K, V, M> = std::marker::PhantomData<(K, V, M)>;
 
+/// Renders a key as the name its file is derived from
+type KeyName<K> = fn(&K) -> Result<String>;
+
+/// Renders a key for the errors of a save
+type KeyLabel<K> = fn(&K) -> String;
+
+/// Name a key by a hash of its JSON form, for keys that are not `Display`
+///
+/// JSON is used whatever the backend's format, so the name of a key stays
+/// the same if the format is changed.
+fn hashed_key_name<K: Serialize>(key: &K) -> Result<String> {
+    let json = serde_json::to_vec(key).map_err(|e| CacheError::Serialization(e.to_string()))?;
+    Ok(format!("key-{:016x}", fnv1a(&json)))
+}
+
+/// Compression level used for cache files
+#[cfg(feature = "compression")]
+const COMPRESSION_LEVEL: u32 = 6;
+
+/// Magic bytes opening a cache file with an integrity header
+///
+/// The header is the magic followed by the little-endian CRC32 of the rest
+/// of the file. No supported format or compression starts with these bytes.
+const CHECKSUM_MAGIC: &[u8; 4] = b"TFC1";
+
+/// Length of the integrity header
+const CHECKSUM_HEADER_LEN: usize = CHECKSUM_MAGIC.len() + 4;
+
+/// Magic bytes opening the serialized entries of a file written with value
+/// compression, whose values are [`StoredValue`]s
+///
+/// It comes after any file compression and integrity header, in front of
+/// the format's own bytes.
+#[cfg(feature = "compression")]
+const VALUE_COMPRESSION_MAGIC: &[u8; 4] = b"TFV1";
+
+/// A value in a file written with value compression
+#[cfg(feature = "compression")]
+#[derive(Clone, Serialize, Deserialize)]
+enum StoredValue<T> {
+    /// A value no larger than the threshold, stored as is
+    Plain(T),
+    /// A value serialized in the file's format, then compressed
+    Compressed {
+        algorithm: CompressionAlgorithm,
+        #[serde(with = "compressed_bytes")]
+        data: Vec<u8>,
+    },
+}
+
+/// Serde for the bytes of a compressed value
+///
+/// Human-readable formats such as JSON get a base64 string rather than an
+/// array of numbers, which would take several times the space; arrays are
+/// still read, as files written before were. Other formats store the bytes
+/// as they always have.
+#[cfg(feature = "compression")]
+mod compressed_bytes {
+    use crate::compression::{decode_base64, encode_base64};
+    use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+    use std::fmt;
+
+    pub(super) fn serialize<S: Serializer>(data: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&encode_base64(data))
+        } else {
+            data.serialize(serializer)
+        }
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<u8>, D::Error> {
+        if !deserializer.is_human_readable() {
+            return Vec::deserialize(deserializer);
+        }
+
+        struct BytesVisitor;
+
+        impl<'de> de::Visitor<'de> for BytesVisitor {
+            type Value = Vec<u8>;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+                formatter.write_str("a base64 string or an array of bytes")
+            }
+
+            fn visit_str<E: de::Error>(self, encoded: &str) -> Result<Vec<u8>, E> {
+                decode_base64(encoded).ok_or_else(|| E::custom("invalid base64"))
+            }
+
+            fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> Result<Vec<u8>, A::Error> {
+                let mut data = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+                while let Some(byte) = seq.next_element()? {
+                    data.push(byte);
+                }
+                Ok(data)
+            }
+        }
+
+        deserializer.deserialize_any(BytesVisitor)
+    }
+}
+
+/// Copy of `entry` holding `value` in place of its own
+#[cfg(feature = "compression")]
+fn with_stored_value<K, V, W, M>(entry: &CacheEntry<K, V, M>, value: W) -> CacheEntry<K, W, M>
+where
+    K: Clone + std::hash::Hash + Eq,
+    V: Clone,
+    W: Clone,
+    M: Clone,
+{
+    let mut stored = CacheEntry::with_metadata(entry.key.clone(), value, entry.metadata.clone());
+    stored.timestamp = entry.timestamp;
+    stored.expiry = entry.expiry;
+    stored.set_access_count(entry.access_count());
+    stored.set_last_accessed(entry.last_accessed());
+    stored
+}
+
+/// What [`FilesystemBackend::save`] does when writing one key's file fails
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SaveErrorPolicy {
+    /// Return that key's error at once, leaving the keys after it unwritten
+    #[default]
+    Abort,
+    /// Write every other key too, then return a
+    /// [`CacheError::PartialSave`] listing each key that failed
+    Continue,
+}
+
 /// Filesystem storage backend
+///
+/// Each file is guarded by an advisory lock on a `.lock` sibling: exclusive
+/// while it is written, shared while it is read if the lock file exists.
+/// This keeps processes on one host from interleaving writes and makes reads
+/// wait for a write in flight; it does not protect caches on network
+/// filesystems, where advisory locks are often not honored. On Unix, lock
+/// files are deleted along with their key, and leftovers by `clear` and
+/// `compact`, each under the lock it removes; elsewhere they are kept.
+///
+/// Files are named after the key's `Display` form when created with
+/// [`FilesystemBackend::new`], or after a hash of the serialized key with
+/// [`FilesystemBackend::new_hashed`], which works for any key type.
 #[allow(clippy::type_complexity)]
 pub struct FilesystemBackend<K, V, M = ()>
 where
-    K: StorageKey + std::fmt::Display,
+    K: StorageKey,
     V: StorageValue,
     M: StorageMeta,
 {
     base_path: PathBuf,
+    key_name: KeyName<K>,
+    key_label: KeyLabel<K>,
     format: SerializationFormat,
+    json_style: JsonStyle,
+    format_autodetect: bool,
+    #[cfg(feature = "compression")]
+    compression: Option<CompressionAlgorithm>,
+    #[cfg(feature = "compression")]
+    value_compression: Option<ValueCompression>,
+    integrity_checks: bool,
+    integrity_failures: AtomicU64,
+    save_error_policy: SaveErrorPolicy,
     _phantom: PhantomTypes<K, V, M>,
 }
 
+/// Check that `file`, locked through `lock_path`, is still the file there
+///
+/// Lock files are deleted along with the files they guard, so a lock won
+/// after waiting may be on a file that is gone, and guards nothing.
+#[cfg(unix)]
+fn is_current_lock(file: &std::fs::File, lock_path: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+
+    match (file.metadata(), std::fs::metadata(lock_path)) {
+        (Ok(held), Ok(current)) => held.dev() == current.dev() && held.ino() == current.ino(),
+        _ => false,
+    }
+}
+
+/// Lock files are never deleted outside Unix, so every lock is current
+#[cfg(not(unix))]
+fn is_current_lock(_file: &std::fs::File, _lock_path: &Path) -> bool {
+    true
+}
+
 impl<K, V, M> FilesystemBackend<K, V, M>
 where
     K: StorageKey + std::fmt::Display,
@@ -37,58 +218,285 @@ where
 {
     /// Create a new filesystem backend with the given base path
     pub async fn new<P: AsRef<Path>>(base_path: P) -> Result<Self> {
+        Self::create(base_path, |key| Ok(key.to_string()), |key| key.to_string()).await
+    }
+}
+
+impl<K, V, M> FilesystemBackend<K, V, M>
+where
+    K: StorageKey + std::fmt::Debug,
+    V: StorageValue,
+    M: StorageMeta,
+{
+    /// Create a filesystem backend that names files after a hash of each
+    /// key, so keys need not implement `Display`
+    ///
+    /// Such names reveal nothing about the key, and differ from the ones
+    /// [`FilesystemBackend::new`] gives, so the two cannot read each other's
+    /// files. Save errors name keys by their `Debug` form.
+    pub async fn new_hashed<P: AsRef<Path>>(base_path: P) -> Result<Self> {
+        Self::create(base_path, hashed_key_name::<K>, |key| format!("{key:?}")).await
+    }
+}
+
+impl<K, V, M> FilesystemBackend<K, V, M>
+where
+    K: StorageKey,
+    V: StorageValue,
+    M: StorageMeta,
+{
+    async fn create<P: AsRef<Path>>(
+        base_path: P,
+        key_name: KeyName<K>,
+        key_label: KeyLabel<K>,
+    ) -> Result<Self> {
         let base_path = base_path.as_ref().to_path_buf();
         fs::create_dir_all(&base_path).await?;
 
         Ok(Self {
             base_path,
-            #[cfg(feature = "json-serialization")]
-            format: SerializationFormat::Json,
-            #[cfg(all(not(feature = "json-serialization"), feature = "bincode-serialization"))]
-            format: SerializationFormat::Bincode,
+            key_name,
+            key_label,
+            format: DEFAULT_FORMAT,
+            json_style: JsonStyle::Compact,
+            format_autodetect: false,
+            #[cfg(feature = "compression")]
+            compression: None,
+            #[cfg(feature = "compression")]
+            value_compression: None,
+            integrity_checks: false,
+            integrity_failures: AtomicU64::new(0),
+            save_error_policy: SaveErrorPolicy::Abort,
             _phantom: std::marker::PhantomData,
         })
     }
 
+    /// Choose whether a save stops at the first key it fails to write
+    ///
+    /// Stopping leaves the files of the keys after it as they were, so they
+    /// may lag behind memory until the next save. Continuing writes all it
+    /// can and reports every failure in one error.
+    pub fn with_save_error_policy(mut self, policy: SaveErrorPolicy) -> Self {
+        self.save_error_policy = policy;
+        self
+    }
+
     /// Set the serialization format
     pub fn with_format(mut self, format: SerializationFormat) -> Self {
         self.format = format;
         self
     }
 
-    /// Sanitize a filename by removing or replacing dangerous characters
-    fn sanitize_filename(filename: &str) -> String {
-        // Replace path separators and other dangerous characters with safe alternatives
-        let mut result = filename
-            .chars()
-            .map(|c| match c {
-                '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
-                c if c.is_control() => '_', // Replace control characters
-                c => c,
-            })
-            .collect::<String>();
+    /// Also read cache files written in any other enabled format
+    ///
+    /// Without this, switching `with_format` between runs leaves the files
+    /// written in the old format unread. With it, `load` picks each file's
+    /// format from its extension, and `remove` and `contains` see those
+    /// files too. New files are still written in the configured format, and
+    /// `compact` rewrites old-format files in it.
+    pub fn with_format_autodetect(mut self, enable: bool) -> Self {
+        self.format_autodetect = enable;
+        self
+    }
+
+    /// Write JSON cache files indented rather than compact
+    ///
+    /// Only affects the JSON format. Files in either style load either way.
+    pub fn with_pretty(mut self, pretty: bool) -> Self {
+        self.json_style = if pretty {
+            JsonStyle::Pretty
+        } else {
+            JsonStyle::Compact
+        };
+        self
+    }
+
+    /// Prefix each cache file with a CRC32 checksum verified on load
+    ///
+    /// Files whose checksum does not match are skipped and counted in
+    /// `integrity_failures`. With checks off, checksums are neither written
+    /// nor verified, but checksummed files still load. Files without a
+    /// checksum load either way.
+    pub fn with_integrity_checks(mut self, enable: bool) -> Self {
+        self.integrity_checks = enable;
+        self
+    }
+
+    /// Get the number of files skipped because their checksum did not match
+    pub fn integrity_failures(&self) -> u64 {
+        self.integrity_failures.load(Ordering::Relaxed)
+    }
+
+    /// Prepend the integrity header to `data`
+    fn add_checksum(data: Vec<u8>) -> Vec<u8> {
+        let mut file = Vec::with_capacity(CHECKSUM_HEADER_LEN + data.len());
+        file.extend_from_slice(CHECKSUM_MAGIC);
+        file.extend_from_slice(&crc32fast::hash(&data).to_le_bytes());
+        file.extend_from_slice(&data);
+        file
+    }
 
-        // Replace leading dots to prevent hidden files
-        if result.starts_with('.') {
-            result = result.replacen('.', "_", 1);
+    /// Strip the integrity header, if the file has one, verifying it when
+    /// checks are enabled
+    ///
+    /// Returns `None` when the checksum does not match.
+    fn verify_checksum(&self, data: Vec<u8>) -> Option<Vec<u8>> {
+        if !data.starts_with(CHECKSUM_MAGIC) || data.len() < CHECKSUM_HEADER_LEN {
+            return Some(data);
         }
+        let (header, payload) = data.split_at(CHECKSUM_HEADER_LEN);
+        let mut expected = [0u8; 4];
+        expected.copy_from_slice(&header[CHECKSUM_MAGIC.len()..]);
+        let intact =
+            !self.integrity_checks || crc32fast::hash(payload) == u32::from_le_bytes(expected);
+        intact.then(|| payload.to_vec())
+    }
+
+    /// Compress cache files with the given algorithm
+    ///
+    /// Compressed files carry the algorithm's extension after the format
+    /// extension (e.g. `key.json.gz`). Uncompressed files written earlier are
+    /// still recognized and loaded.
+    #[cfg(feature = "compression")]
+    pub fn with_compression(mut self, algorithm: CompressionAlgorithm) -> Self {
+        self.compression = Some(algorithm);
+        self
+    }
 
-        // Clean up trailing dots and whitespace
-        result.trim_matches('.').trim().to_string()
+    /// Get the extension used for newly written cache files
+    fn cache_file_extension(&self) -> String {
+        #[cfg(feature = "compression")]
+        if let Some(algorithm) = self.compression {
+            return format!("{}.{}", self.format.extension(), algorithm.extension());
+        }
+        self.format.extension().to_string()
     }
 
     /// Get the path for a cache file
     fn get_cache_file_path(&self, key: &str) -> PathBuf {
-        let sanitized_key = Self::sanitize_filename(key);
-        // Ensure the filename isn't empty after sanitization
-        let safe_key = if sanitized_key.is_empty() {
-            "cache_entry".to_string()
-        } else {
-            sanitized_key
-        };
+        self.base_path.join(format!(
+            "{}.{}",
+            cache_file_stem(key),
+            self.cache_file_extension()
+        ))
+    }
 
-        self.base_path
-            .join(format!("{}.{}", safe_key, self.format.extension()))
+    /// Get the formats cache files are read in, the configured one first
+    fn readable_formats(&self) -> Vec<SerializationFormat> {
+        let mut formats = vec![self.format];
+        if self.format_autodetect {
+            formats.extend(
+                SerializationFormat::all()
+                    .iter()
+                    .filter(|&&format| format != self.format),
+            );
+        }
+        formats
+    }
+
+    /// Get the format a cache file at `path` is read in, or `None` if its
+    /// extension is not one this backend reads
+    fn file_format(&self, path: &Path) -> Option<SerializationFormat> {
+        let name = path.file_name()?.to_str()?;
+        #[cfg(feature = "compression")]
+        let (name, _) = Self::split_compression(name);
+
+        let extension = Path::new(name).extension()?.to_str()?;
+        SerializationFormat::from_extension(extension)
+            .filter(|format| self.readable_formats().contains(format))
+    }
+
+    /// Get every path a key's entries may have been written to
+    ///
+    /// This includes files written with a different compression setting, or
+    /// in another format when autodetection is on, so `remove` and
+    /// `contains` keep working after either is changed.
+    fn candidate_file_paths(&self, key: &str) -> Vec<PathBuf> {
+        let mut paths = vec![self.get_cache_file_path(key)];
+
+        for format in self.readable_formats() {
+            let plain = format!("{}.{}", cache_file_stem(key), format.extension());
+            #[cfg(feature = "compression")]
+            let variants = std::iter::once(plain.clone()).chain(
+                CompressionAlgorithm::all()
+                    .iter()
+                    .map(|algorithm| format!("{plain}.{}", algorithm.extension())),
+            );
+            #[cfg(not(feature = "compression"))]
+            let variants = std::iter::once(plain);
+            for variant in variants {
+                let path = self.base_path.join(variant);
+                if !paths.contains(&path) {
+                    paths.push(path);
+                }
+            }
+        }
+
+        paths
+    }
+
+    /// Get the path an older version would have written the key's entries
+    /// to, if it differs from the current one
+    fn legacy_file_path(&self, key: &str) -> Option<PathBuf> {
+        legacy_cache_file_stem(key).map(|stem| {
+            self.base_path
+                .join(format!("{stem}.{}", self.format.extension()))
+        })
+    }
+
+    /// Get the legacy file of `key` if it exists and holds that key's entries
+    /// rather than those of a key it collided with
+    async fn owned_legacy_file(&self, key: &K) -> Option<(PathBuf, Vec<CacheEntry<K, V, M>>)>
+    where
+        K: Serialize + DeserializeOwned,
+        V: Serialize + DeserializeOwned,
+        M: Serialize + DeserializeOwned + EntryMetadata,
+    {
+        let path = self.legacy_file_path(&(self.key_name)(key).ok()?)?;
+        if !path.exists() {
+            return None;
+        }
+        match self.load_entry_from_path(&path).await {
+            Some((stored_key, entry_vec)) if stored_key == *key => Some((path, entry_vec)),
+            _ => None,
+        }
+    }
+
+    /// Write the file for one key, named `name`
+    async fn save_key(&self, key: &K, name: &str, entry_vec: &[CacheEntry<K, V, M>]) -> Result<()>
+    where
+        K: Serialize + DeserializeOwned,
+        V: Serialize + DeserializeOwned,
+        M: Serialize + DeserializeOwned + EntryMetadata,
+    {
+        let file_path = self.get_cache_file_path(name);
+        self.write_data(file_path, &self.encode(entry_vec)?).await?;
+        // Retire the file an older version wrote, so it cannot shadow this
+        // one on load
+        if let Some((legacy_path, _)) = self.owned_legacy_file(key).await {
+            fs::remove_file(&legacy_path).await?;
+        }
+        Ok(())
+    }
+
+    /// Delete every file that may hold the key's entries, named `name`
+    async fn remove_key_files(&self, key: &K, name: &str) -> Result<()>
+    where
+        K: Serialize + DeserializeOwned,
+        V: Serialize + DeserializeOwned,
+        M: Serialize + DeserializeOwned + EntryMetadata,
+    {
+        for file_path in self.candidate_file_paths(name) {
+            if file_path.exists() {
+                fs::remove_file(&file_path).await?;
+            }
+            Self::remove_lock(&file_path).await?;
+        }
+        if let Some((legacy_path, _)) = self.owned_legacy_file(key).await {
+            fs::remove_file(&legacy_path).await?;
+            Self::remove_lock(&legacy_path).await?;
+        }
+        Ok(())
     }
 
     /// Get the metadata file path
@@ -97,16 +505,315 @@ where
             .join(format!("metadata.{}", self.format.extension()))
     }
 
-    async fn write_data<P: AsRef<Path>>(&self, path: P, data: &[u8]) -> Result<()> {
-        let mut file = File::create(path).await?;
-        file.write_all(data).await?;
-        file.flush().await?;
+    /// Get the sibling path `path` is staged at while being written
+    ///
+    /// The process id keeps processes sharing a directory from writing to
+    /// the same temporary file. The `.tmp` extension is never mistaken for a
+    /// cache file, so leftovers from a crash are ignored on load.
+    fn temp_path(path: &Path) -> PathBuf {
+        let mut name = path.file_name().unwrap_or_default().to_os_string();
+        name.push(format!(".{}.tmp", std::process::id()));
+        path.with_file_name(name)
+    }
+
+    /// Get the `.lock` sibling guarding `path`
+    ///
+    /// The lock is held on a sibling rather than on `path` itself, because
+    /// writes replace `path` with a new file. Locks only coordinate
+    /// processes on the same host; network filesystems may ignore them.
+    fn lock_path(path: &Path) -> PathBuf {
+        let mut name = path.file_name().unwrap_or_default().to_os_string();
+        name.push(".lock");
+        path.with_file_name(name)
+    }
+
+    /// Take the exclusive lock guarding `path`, released when the returned
+    /// file is dropped
+    async fn lock_exclusive(path: &Path) -> Result<std::fs::File> {
+        let lock_path = Self::lock_path(path);
+        tokio::task::spawn_blocking(move || loop {
+            let file = std::fs::OpenOptions::new()
+                .create(true)
+                .truncate(false)
+                .write(true)
+                .open(&lock_path)?;
+            FileExt::lock_exclusive(&file)?;
+            if is_current_lock(&file, &lock_path) {
+                return Ok(file);
+            }
+        })
+        .await
+        .map_err(|e| crate::CacheError::StorageBackend(e.to_string()))?
+    }
+
+    /// Take a shared lock guarding `path` if its lock file exists, released
+    /// when the returned file is dropped
+    ///
+    /// Readers never create lock files, so a read-only directory still
+    /// loads. Reading without the lock is safe because writes land with an
+    /// atomic rename; the lock only makes a read wait for a write in flight.
+    async fn lock_shared(path: &Path) -> Option<std::fs::File> {
+        let lock_path = Self::lock_path(path);
+        tokio::task::spawn_blocking(move || loop {
+            let file = std::fs::File::open(&lock_path).ok()?;
+            FileExt::lock_shared(&file).ok()?;
+            if is_current_lock(&file, &lock_path) {
+                return Some(file);
+            }
+        })
+        .await
+        .ok()
+        .flatten()
+    }
+
+    /// Delete the lock file guarding `path`, if there is one
+    ///
+    /// The lock is taken first, so a writer holding it finishes before the
+    /// file goes, and writers waiting on it lock a new file afterwards. Only
+    /// Unix can delete a file others hold open, so lock files stay in place
+    /// elsewhere.
+    async fn remove_lock(path: &Path) -> Result<()> {
+        #[cfg(unix)]
+        {
+            let lock_path = Self::lock_path(path);
+            tokio::task::spawn_blocking(move || {
+                let file = match std::fs::File::open(&lock_path) {
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+                    file => file?,
+                };
+                FileExt::lock_exclusive(&file)?;
+                if is_current_lock(&file, &lock_path) {
+                    match std::fs::remove_file(&lock_path) {
+                        Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+                        _ => {}
+                    }
+                }
+                Ok(())
+            })
+            .await
+            .map_err(|e| crate::CacheError::StorageBackend(e.to_string()))?
+        }
+        #[cfg(not(unix))]
+        {
+            let _ = path;
+            Ok(())
+        }
+    }
+
+    /// Delete lock files whose guarded file no longer exists
+    async fn remove_orphaned_locks(&self) -> Result<()> {
+        let mut dir_entries = fs::read_dir(&self.base_path).await?;
+        while let Some(entry) = dir_entries.next_entry().await? {
+            let path = entry.path();
+            let Some(guarded) = path
+                .file_name()
+                .and_then(|s| s.to_str())
+                .and_then(|name| name.strip_suffix(".lock"))
+                .map(|name| path.with_file_name(name))
+            else {
+                continue;
+            };
+            if entry.file_type().await?.is_file() && !guarded.exists() {
+                Self::remove_lock(&guarded).await?;
+            }
+        }
         Ok(())
     }
 
+    /// Write `data` to `path` atomically
+    ///
+    /// The data goes to a temporary sibling that is renamed over `path` once
+    /// it is fully on disk, so a crash mid-write leaves the previous file
+    /// intact. Writers hold an exclusive lock on the path meanwhile.
+    async fn write_data<P: AsRef<Path>>(&self, path: P, data: &[u8]) -> Result<()> {
+        let path = path.as_ref();
+        let temp_path = Self::temp_path(path);
+        let _lock = Self::lock_exclusive(path).await?;
+
+        let written = async {
+            let mut file = File::create(&temp_path).await?;
+            file.write_all(data).await?;
+            file.sync_all().await?;
+            fs::rename(&temp_path, path).await
+        }
+        .await;
+        if written.is_err() {
+            let _ = fs::remove_file(&temp_path).await;
+        }
+        Ok(written?)
+    }
+
+    /// Record the number of keys saved in the metadata file
+    /// Read the metadata file, if there is a readable one
+    async fn read_metadata(&self) -> Option<CacheMetadata> {
+        let data = fs::read(self.get_metadata_path()).await.ok()?;
+        self.format.deserialize(&data).ok()
+    }
+
+    /// Count how many of `keys` have a file
+    async fn count_stored(&self, keys: &[K]) -> usize {
+        let mut stored = 0;
+        for key in keys {
+            if self.contains(key).await.unwrap_or(false) {
+                stored += 1;
+            }
+        }
+        stored
+    }
+
+    async fn write_metadata(&self, total_keys: usize) -> Result<()> {
+        let metadata = CacheMetadata {
+            total_keys,
+            last_updated: chrono::Utc::now(),
+        };
+        let data = self.format.serialize_with(&metadata, self.json_style)?;
+        self.write_data(self.get_metadata_path(), &data).await
+    }
+
+    /// Write the file of each of `keys`, or delete it for keys missing from
+    /// `entries`, as the save error policy allows
+    ///
+    /// Returns the failures the policy let the save continue past.
+    async fn write_keys<'a, I>(
+        &self,
+        entries: &EntryMap<K, V, M>,
+        keys: I,
+    ) -> Result<Vec<(String, CacheError)>>
+    where
+        I: IntoIterator<Item = &'a K>,
+        K: 'a,
+    {
+        let abort = self.save_error_policy == SaveErrorPolicy::Abort;
+        let mut failures = Vec::new();
+        for key in keys {
+            let written = match (self.key_name)(key) {
+                Ok(name) => match entries.get(key) {
+                    Some(entry_vec) => self.save_key(key, &name, entry_vec).await,
+                    None => self.remove_key_files(key, &name).await,
+                },
+                Err(e) => Err(e),
+            };
+            match written {
+                Ok(()) => {}
+                Err(e) if abort => return Err(e.with_key((self.key_label)(key))),
+                Err(e) => failures.push(((self.key_label)(key), e)),
+            }
+        }
+        Ok(failures)
+    }
+
+    /// Encode a key's entries as the contents of its cache file
+    fn encode(&self, entry_vec: &[CacheEntry<K, V, M>]) -> Result<Vec<u8>> {
+        #[cfg(feature = "compression")]
+        let data = match self.value_compression {
+            Some(compression) => self.encode_compressing_values(entry_vec, compression)?,
+            None => self.format.serialize_with(&entry_vec, self.json_style)?,
+        };
+        #[cfg(not(feature = "compression"))]
+        let data = self.format.serialize_with(&entry_vec, self.json_style)?;
+        #[cfg(feature = "compression")]
+        let data = match self.compression {
+            Some(algorithm) => crate::compression::compress(algorithm, COMPRESSION_LEVEL, &data)?,
+            None => data,
+        };
+        Ok(if self.integrity_checks {
+            Self::add_checksum(data)
+        } else {
+            data
+        })
+    }
+
+    /// Serialize entries with every value above the threshold compressed
+    #[cfg(feature = "compression")]
+    fn encode_compressing_values(
+        &self,
+        entry_vec: &[CacheEntry<K, V, M>],
+        compression: ValueCompression,
+    ) -> Result<Vec<u8>> {
+        let stored = entry_vec
+            .iter()
+            .map(|entry| {
+                let value = self.format.serialize(&entry.value)?;
+                let value = if value.len() > compression.threshold {
+                    StoredValue::Compressed {
+                        algorithm: compression.algorithm,
+                        data: crate::compression::compress(
+                            compression.algorithm,
+                            compression.level,
+                            &value,
+                        )?,
+                    }
+                } else {
+                    StoredValue::Plain(&entry.value)
+                };
+                Ok(with_stored_value(entry, value))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let mut data = VALUE_COMPRESSION_MAGIC.to_vec();
+        data.extend(self.format.serialize_with(&stored, self.json_style)?);
+        Ok(data)
+    }
+
+    /// Deserialize a file's entries, decompressing any compressed values
+    fn decode(format: SerializationFormat, data: &[u8]) -> Result<Vec<CacheEntry<K, V, M>>>
+    where
+        K: DeserializeOwned,
+        V: DeserializeOwned,
+        M: DeserializeOwned,
+    {
+        #[cfg(feature = "compression")]
+        if let Some(data) = data.strip_prefix(VALUE_COMPRESSION_MAGIC) {
+            let stored: Vec<CacheEntry<K, StoredValue<V>, M>> = format.deserialize(data)?;
+            return stored
+                .iter()
+                .map(|entry| {
+                    let value = match &entry.value {
+                        StoredValue::Plain(value) => value.clone(),
+                        StoredValue::Compressed { algorithm, data } => format
+                            .deserialize(&crate::compression::decompress(*algorithm, data)?)?,
+                    };
+                    Ok(with_stored_value(entry, value))
+                })
+                .collect();
+        }
+        format.deserialize(data)
+    }
+
+    /// Split a known compression extension off a file name
+    #[cfg(feature = "compression")]
+    fn split_compression(name: &str) -> (&str, Option<CompressionAlgorithm>) {
+        if let Some((rest, extension)) = name.rsplit_once('.') {
+            if let Some(algorithm) = CompressionAlgorithm::from_extension(extension) {
+                return (rest, Some(algorithm));
+            }
+        }
+        (name, None)
+    }
+
     fn is_cache_file_path(&self, path: &Path) -> bool {
-        path.extension().and_then(|s| s.to_str()) == Some(self.format.extension())
-            && path.file_stem().and_then(|s| s.to_str()) != Some("metadata")
+        let Some(name) = path.file_name().and_then(|s| s.to_str()) else {
+            return false;
+        };
+        #[cfg(feature = "compression")]
+        let (name, _) = Self::split_compression(name);
+
+        self.file_format(path).is_some()
+            && Path::new(name).file_stem().and_then(|s| s.to_str()) != Some("metadata")
+    }
+
+    /// Whether `path` is named the way this backend names `key`'s file, in
+    /// the current or the legacy layout
+    fn is_file_for_key(path: &Path, key: &str) -> bool {
+        let Some(name) = path.file_name().and_then(|s| s.to_str()) else {
+            return false;
+        };
+        #[cfg(feature = "compression")]
+        let (name, _) = Self::split_compression(name);
+
+        let Some(stem) = Path::new(name).file_stem().and_then(|s| s.to_str()) else {
+            return false;
+        };
+        stem == cache_file_stem(key) || legacy_cache_file_stem(key).is_some_and(|s| s == stem)
     }
 
     async fn cache_file_paths(&self) -> Result<Vec<PathBuf>> {
@@ -114,37 +821,82 @@ where
         let mut dir_entries = fs::read_dir(&self.base_path).await?;
         while let Some(entry) = dir_entries.next_entry().await? {
             let path = entry.path();
-            if self.is_cache_file_path(&path) {
+            // Namespace subdirectories belong to other caches
+            if entry.file_type().await?.is_file() && self.is_cache_file_path(&path) {
                 paths.push(path);
             }
         }
         Ok(paths)
     }
 
+    /// Get the cache file paths in the order they are loaded
+    ///
+    /// Files in the current layout come last so they win over stale files
+    /// left behind by a different compression setting or format.
+    async fn load_order_paths(&self) -> Result<Vec<PathBuf>> {
+        let mut paths = self.cache_file_paths().await?;
+        let current_extension = format!(".{}", self.cache_file_extension());
+        paths.sort_by_key(|path| path.to_string_lossy().ends_with(&current_extension));
+        Ok(paths)
+    }
+
     async fn load_entry_from_path(&self, path: &Path) -> Option<(K, Vec<CacheEntry<K, V, M>>)>
     where
-        K: Serialize + DeserializeOwned + std::fmt::Display,
+        K: Serialize + DeserializeOwned,
         V: Serialize + DeserializeOwned,
         M: Serialize + DeserializeOwned + EntryMetadata,
     {
-        let data = match fs::read(path).await {
+        let lock = Self::lock_shared(path).await;
+        let data = fs::read(path).await;
+        drop(lock);
+        let data = match data {
             Ok(d) => d,
             Err(e) => {
                 eprintln!("Failed to read cache file {path:?}: {e}");
                 return None;
             }
         };
-        let entry_vec: Vec<CacheEntry<K, V, M>> = match self.format.deserialize(&data) {
+        let Some(data) = self.verify_checksum(data) else {
+            eprintln!("Checksum mismatch in cache file {path:?}");
+            self.integrity_failures.fetch_add(1, Ordering::Relaxed);
+            return None;
+        };
+        #[cfg(feature = "compression")]
+        let data = match path
+            .file_name()
+            .and_then(|s| s.to_str())
+            .and_then(|name| Self::split_compression(name).1)
+        {
+            Some(algorithm) => match crate::compression::decompress(algorithm, &data) {
+                Ok(d) => d,
+                Err(e) => {
+                    eprintln!("Failed to decompress cache file {path:?}: {e}");
+                    return None;
+                }
+            },
+            None => data,
+        };
+        let format = self.file_format(path).unwrap_or(self.format);
+        let entry_vec = match Self::decode(format, &data) {
             Ok(v) => v,
             Err(e) => {
                 eprintln!("Failed to deserialize cache file {path:?}: {e}");
                 return None;
             }
         };
+        // The embedded key is authoritative; the file name is only derived
+        // from it
         let key = match entry_vec.first() {
             Some(first) => first.key.clone(),
             None => return None,
         };
+        if let Ok(name) = (self.key_name)(&key) {
+            if !Self::is_file_for_key(path, &name) {
+                eprintln!(
+                    "Cache file {path:?} holds entries for key {name}, whose file is named differently"
+                );
+            }
+        }
         Some((key, entry_vec))
     }
 }
@@ -152,7 +904,7 @@ where
 #[async_trait]
 impl<K, V, M> StorageBackend for FilesystemBackend<K, V, M>
 where
-    K: StorageKey + std::fmt::Display,
+    K: StorageKey,
     V: StorageValue,
     M: StorageMeta,
 {
@@ -162,49 +914,100 @@ where
     type Metadata = M;
 
     async fn save(&self, entries: &EntryMap<K, V, M>) -> Result<()> {
-        for (key, entry_vec) in entries {
-            let file_path = self.get_cache_file_path(&key.to_string());
-            let data = self.format.serialize(entry_vec)?;
-            self.write_data(file_path, &data).await?;
+        let failures = self.write_keys(entries, entries.keys()).await?;
+        self.write_metadata(entries.len()).await?;
+
+        if failures.is_empty() {
+            Ok(())
+        } else {
+            Err(CacheError::PartialSave { failures })
         }
+    }
 
-        let metadata = CacheMetadata {
-            total_keys: entries.len(),
-            last_updated: chrono::Utc::now(),
+    /// Write only the files of `keys`, deleting those of keys no longer in
+    /// `entries`
+    ///
+    /// The key count in the metadata file is moved by the keys whose files
+    /// appeared or disappeared, or recounted from the files if there is no
+    /// metadata to start from.
+    async fn save_keys(&self, entries: &EntryMap<K, V, M>, keys: &[K]) -> Result<()> {
+        let stored_before = self.count_stored(keys).await;
+        let failures = self.write_keys(entries, keys).await?;
+        let stored_after = self.count_stored(keys).await;
+        let total_keys = match self.read_metadata().await {
+            Some(metadata) => (metadata.total_keys + stored_after).saturating_sub(stored_before),
+            None => self.cache_file_paths().await?.len(),
         };
-        let data = self.format.serialize(&metadata)?;
-        self.write_data(self.get_metadata_path(), &data).await
+        self.write_metadata(total_keys).await?;
+        if failures.is_empty() {
+            Ok(())
+        } else {
+            Err(CacheError::PartialSave { failures })
+        }
+    }
+
+    fn supports_partial_save(&self) -> bool {
+        true
     }
 
     async fn load(&self) -> Result<EntryMap<K, V, M>> {
-        let mut entries: EntryMap<K, V, M> = HashMap::new();
-        for path in self.cache_file_paths().await? {
-            if let Some((key, entry_vec)) = self.load_entry_from_path(&path).await {
-                entries.insert(key, entry_vec);
+        self.load_stream().try_collect().await
+    }
+
+    /// Read one cache file at a time, skipping unreadable files as `load`
+    /// does
+    fn load_stream(&self) -> EntryStream<'_, K, V, M> {
+        stream::once(self.load_order_paths())
+            .map_ok(move |paths| {
+                stream::iter(paths).filter_map(move |path| async move {
+                    self.load_entry_from_path(&path).await.map(Ok)
+                })
+            })
+            .try_flatten()
+            .boxed()
+    }
+
+    /// Read only the key's own file, preferring the current layout
+    async fn load_key(&self, key: &K) -> Result<Option<Vec<CacheEntry<K, V, M>>>> {
+        for path in self.candidate_file_paths(&(self.key_name)(key)?) {
+            if !path.exists() {
+                continue;
+            }
+            if let Some((stored_key, entry_vec)) = self.load_entry_from_path(&path).await {
+                if stored_key == *key {
+                    return Ok(Some(entry_vec));
+                }
             }
         }
-        Ok(entries)
+        Ok(self
+            .owned_legacy_file(key)
+            .await
+            .map(|(_, entry_vec)| entry_vec))
     }
 
     async fn remove(&self, key: &K) -> Result<()> {
-        let file_path = self.get_cache_file_path(&key.to_string());
-        if file_path.exists() {
-            fs::remove_file(&file_path).await?;
-        }
-        Ok(())
+        let name = (self.key_name)(key)?;
+        self.remove_key_files(key, &name)
+            .await
+            .map_err(|e| e.with_key(&name))
     }
 
     async fn clear(&self) -> Result<()> {
         for path in self.cache_file_paths().await? {
             fs::remove_file(&path).await?;
         }
-
-        Ok(())
+        self.remove_orphaned_locks().await
     }
 
     async fn contains(&self, key: &K) -> Result<bool> {
-        let file_path = self.get_cache_file_path(&key.to_string());
-        Ok(file_path.exists())
+        if self
+            .candidate_file_paths(&(self.key_name)(key)?)
+            .iter()
+            .any(|path| path.exists())
+        {
+            return Ok(true);
+        }
+        Ok(self.owned_legacy_file(key).await.is_some())
     }
 
     async fn size_bytes(&self) -> Result<u64> {
@@ -213,21 +1016,122 @@ where
 
         while let Some(entry) = dir_entries.next_entry().await? {
             if let Ok(metadata) = entry.metadata().await {
-                total_size += metadata.len();
+                if metadata.is_file() {
+                    total_size += metadata.len();
+                }
             }
         }
 
         Ok(total_size)
     }
 
+    /// Drop expired entries and move files to the current layout
+    ///
+    /// Files losing every entry are deleted, and files losing some are
+    /// rewritten. Files written with a different compression setting, or in
+    /// another format when autodetection is on, are rewritten in the current
+    /// layout, or deleted if a file in the current layout already shadows
+    /// them. Unreadable files are left alone.
     async fn compact(&self) -> Result<()> {
-        // For filesystem backend, compaction could involve:
-        // - Removing expired entries
-        // - Consolidating small files
-        // - Rewriting files with compression
-        // For now, just a no-op
-        Ok(())
+        for path in self.load_order_paths().await? {
+            let Some((key, entry_vec)) = self.load_entry_from_path(&path).await else {
+                continue;
+            };
+            let Ok(name) = (self.key_name)(&key) else {
+                continue;
+            };
+            let target = self.get_cache_file_path(&name);
+            if path != target && target.exists() {
+                fs::remove_file(&path).await?;
+                continue;
+            }
+
+            let total = entry_vec.len();
+            let live: Vec<_> = entry_vec.into_iter().filter(|e| !e.is_expired()).collect();
+            if live.is_empty() {
+                fs::remove_file(&path).await?;
+            } else if live.len() != total || path != target {
+                self.write_data(&target, &self.encode(&live)?).await?;
+                if path != target {
+                    fs::remove_file(&path).await?;
+                }
+            }
+        }
+        self.remove_orphaned_locks().await
+    }
+
+    /// Move the backend into a subdirectory of its base path named after
+    /// the namespace
+    ///
+    /// A namespace that is not a safe directory name as is gets a sanitized
+    /// name with a hash suffix, like keys do.
+    async fn with_namespace(mut self, namespace: &str) -> Result<Self> {
+        let Some(directory) = namespace_segment(namespace) else {
+            return Err(crate::CacheError::InvalidConfiguration(format!(
+                "namespace {namespace:?} is not a valid directory name"
+            )));
+        };
+        self.base_path.push(directory);
+        fs::create_dir_all(&self.base_path).await?;
+        Ok(self)
+    }
+
+    /// Compress values above the threshold inside the cache files
+    ///
+    /// Each large value is serialized in the backend's format and
+    /// compressed on its own, while keys, metadata and small values stay
+    /// readable. Files written this way load whether or not the backend
+    /// reading them compresses values. With JSON the compressed bytes are
+    /// written as base64, which takes a third more space than a binary
+    /// format would.
+    #[cfg(feature = "compression")]
+    async fn with_value_compression(mut self, compression: ValueCompression) -> Result<Self> {
+        self.value_compression = Some(compression);
+        Ok(self)
+    }
+}
+
+/// Move every key stored by `from` into `to`, converting it to `to`'s
+/// format, compression and file naming, and return how many keys moved
+///
+/// All of `from`'s files are read and written out through `to` before any of
+/// them is deleted, so the two backends may share a directory, and a failed
+/// save leaves the source untouched. Files `from` cannot read are left in
+/// place, as are files `to` has just written under the same path.
+pub async fn migrate_format<K, V, M>(
+    from: &FilesystemBackend<K, V, M>,
+    to: &FilesystemBackend<K, V, M>,
+) -> Result<usize>
+where
+    K: StorageKey,
+    V: StorageValue,
+    M: StorageMeta,
+{
+    let mut entries = EntryMap::new();
+    let mut migrated_paths = Vec::new();
+    for path in from.load_order_paths().await? {
+        if let Some((key, entry_vec)) = from.load_entry_from_path(&path).await {
+            entries.insert(key, entry_vec);
+            migrated_paths.push(path);
+        }
+    }
+    to.save(&entries).await?;
+
+    let written: Vec<PathBuf> = entries
+        .keys()
+        .filter_map(|key| (to.key_name)(key).ok())
+        .map(|name| to.get_cache_file_path(&name))
+        .collect();
+    for path in migrated_paths {
+        if !written.contains(&path) && path.exists() {
+            fs::remove_file(&path).await?;
+        }
+    }
+    let metadata_path = from.get_metadata_path();
+    if metadata_path != to.get_metadata_path() && metadata_path.exists() {
+        fs::remove_file(&metadata_path).await?;
     }
+    Ok(entries.len())
 }
 
 /// Metadata about the cache stored on filesystem
@@ -240,6 +1144,8 @@ struct CacheMetadata {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::backends::sanitize_filename;
+    use std::collections::HashMap;
     use tempfile::TempDir;
 
     async fn new_backend() -> (TempDir, FilesystemBackend<String, String>) {
@@ -324,6 +1230,83 @@ mod tests {
         assert!(!loaded.contains_key("bad"));
     }
 
+    #[tokio::test]
+    async fn test_load_stream_yields_each_file() {
+        let (_temp_dir, backend) = new_backend().await;
+        let entries: HashMap<_, _> = ["a", "b", "c"]
+            .into_iter()
+            .map(|key| {
+                (
+                    key.to_string(),
+                    vec![CacheEntry::new(key.to_string(), "value".to_string())],
+                )
+            })
+            .collect();
+        backend.save(&entries).await.unwrap();
+
+        let mut keys: Vec<String> = backend
+            .load_stream()
+            .map_ok(|(key, _)| key)
+            .try_collect()
+            .await
+            .unwrap();
+        keys.sort();
+        assert_eq!(keys, ["a", "b", "c"]);
+    }
+
+    #[tokio::test]
+    async fn test_load_key_reads_one_file() {
+        let (_temp_dir, backend) = new_backend().await;
+        let entries: HashMap<_, _> = ["a", "b"]
+            .into_iter()
+            .map(|key| {
+                (
+                    key.to_string(),
+                    vec![CacheEntry::new(key.to_string(), format!("{key}-value"))],
+                )
+            })
+            .collect();
+        backend.save(&entries).await.unwrap();
+
+        let loaded = backend.load_key(&"b".to_string()).await.unwrap().unwrap();
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].value, "b-value");
+        assert!(backend.load_key(&"z".to_string()).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_compact_drops_expired_entries() {
+        let (temp_dir, backend) = new_backend().await;
+        let expired = |key: &str| {
+            CacheEntry::new(key.to_string(), "x".repeat(1000))
+                .with_ttl(chrono::Duration::seconds(-1))
+        };
+        let live = |key: &str| CacheEntry::new(key.to_string(), "live".to_string());
+        let entries = HashMap::from([
+            ("mixed".to_string(), vec![expired("mixed"), live("mixed")]),
+            ("dead".to_string(), vec![expired("dead")]),
+            ("fresh".to_string(), vec![live("fresh")]),
+        ]);
+        backend.save(&entries).await.unwrap();
+        let fresh_path = temp_dir.path().join("fresh.json");
+        let fresh_modified = fs::metadata(&fresh_path).await.unwrap().modified().unwrap();
+        let size_before = backend.size_bytes().await.unwrap();
+
+        backend.compact().await.unwrap();
+
+        assert!(!temp_dir.path().join("dead.json").exists());
+        let loaded = backend.load().await.unwrap();
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(loaded["mixed"].len(), 1);
+        assert!(!loaded["mixed"][0].is_expired());
+        // Files without expired entries are not rewritten
+        assert_eq!(
+            fs::metadata(&fresh_path).await.unwrap().modified().unwrap(),
+            fresh_modified
+        );
+        assert!(backend.size_bytes().await.unwrap() < size_before - 1000);
+    }
+
     #[tokio::test]
     async fn test_path_traversal_protection() {
         let (_temp_dir, backend) = new_backend().await;
@@ -360,6 +1343,346 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "json-serialization")]
+    #[tokio::test]
+    async fn test_json_style_is_compact_unless_pretty() {
+        let (temp_dir, backend) = new_backend().await;
+        let backend = backend.with_format(SerializationFormat::Json);
+        let path = temp_dir.path().join("key.json");
+
+        let mut entries = HashMap::new();
+        entries.insert(
+            "key".to_string(),
+            vec![CacheEntry::new("key".to_string(), "value".to_string())],
+        );
+        backend.save(&entries).await.unwrap();
+        let compact = fs::read_to_string(&path).await.unwrap();
+        assert!(!compact.contains('\n'));
+
+        let backend = backend.with_pretty(true);
+        backend.save(&entries).await.unwrap();
+        let pretty = fs::read_to_string(&path).await.unwrap();
+        assert!(pretty.contains('\n'));
+        assert_eq!(backend.load().await.unwrap()["key"][0].value, "value");
+    }
+
+    #[cfg(feature = "msgpack-serialization")]
+    #[tokio::test]
+    async fn test_msgpack_format_round_trip() {
+        let (temp_dir, backend) = new_backend().await;
+        let backend = backend.with_format(SerializationFormat::MessagePack);
+
+        let mut entries = HashMap::new();
+        entries.insert(
+            "key".to_string(),
+            vec![CacheEntry::new("key".to_string(), "value".to_string())],
+        );
+        backend.save(&entries).await.unwrap();
+
+        assert!(temp_dir.path().join("key.msgpack").exists());
+        let loaded = backend.load().await.unwrap();
+        assert_eq!(loaded["key"][0].value, "value");
+    }
+
+    #[cfg(all(feature = "json-serialization", feature = "msgpack-serialization"))]
+    #[tokio::test]
+    async fn test_format_autodetect_reads_files_in_other_formats() {
+        let (temp_dir, backend) = new_backend().await;
+        let backend = backend.with_format(SerializationFormat::Json);
+        let mut entries = HashMap::new();
+        for key in ["a", "b"] {
+            entries.insert(
+                key.to_string(),
+                vec![CacheEntry::new(key.to_string(), format!("{key}-json"))],
+            );
+        }
+        backend.save(&entries).await.unwrap();
+
+        let msgpack = FilesystemBackend::<String, String>::new(temp_dir.path())
+            .await
+            .unwrap()
+            .with_format(SerializationFormat::MessagePack);
+        assert!(msgpack.load().await.unwrap().is_empty());
+
+        let migrating = msgpack.with_format_autodetect(true);
+        let loaded = migrating.load().await.unwrap();
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(loaded["a"][0].value, "a-json");
+        assert!(migrating.contains(&"b".to_string()).await.unwrap());
+
+        // Files in the configured format win over old ones for the same key
+        let mut newer = HashMap::new();
+        newer.insert(
+            "a".to_string(),
+            vec![CacheEntry::new("a".to_string(), "a-msgpack".to_string())],
+        );
+        migrating.save(&newer).await.unwrap();
+        assert_eq!(migrating.load().await.unwrap()["a"][0].value, "a-msgpack");
+
+        migrating.remove(&"b".to_string()).await.unwrap();
+        assert!(!temp_dir.path().join("b.json").exists());
+
+        migrating.compact().await.unwrap();
+        assert!(!temp_dir.path().join("a.json").exists());
+        assert!(temp_dir.path().join("a.msgpack").exists());
+    }
+
+    #[tokio::test]
+    async fn test_migrate_format_moves_keys_between_directories() {
+        let (from_dir, from) = new_backend().await;
+        let (to_dir, to) = new_backend().await;
+        let to = to.with_pretty(true);
+        let mut entries = HashMap::new();
+        for key in ["a", "b"] {
+            entries.insert(
+                key.to_string(),
+                vec![CacheEntry::new(key.to_string(), format!("{key}-value"))],
+            );
+        }
+        from.save(&entries).await.unwrap();
+
+        assert_eq!(migrate_format(&from, &to).await.unwrap(), 2);
+        assert!(from.load().await.unwrap().is_empty());
+        assert!(!from_dir.path().join("metadata.json").exists());
+        let loaded = to.load().await.unwrap();
+        assert_eq!(loaded["b"][0].value, "b-value");
+        let written = fs::read_to_string(to_dir.path().join("a.json"))
+            .await
+            .unwrap();
+        assert!(written.contains('\n'));
+    }
+
+    #[cfg(all(feature = "json-serialization", feature = "bincode-serialization"))]
+    #[tokio::test]
+    async fn test_migrate_format_within_one_directory() {
+        let (temp_dir, json) = new_backend().await;
+        let json = json.with_format(SerializationFormat::Json);
+        let bincode = FilesystemBackend::<String, String>::new(temp_dir.path())
+            .await
+            .unwrap()
+            .with_format(SerializationFormat::Bincode);
+        let mut entries = HashMap::new();
+        for key in ["a", "b", "c"] {
+            entries.insert(
+                key.to_string(),
+                vec![CacheEntry::new(key.to_string(), key.to_string())],
+            );
+        }
+        json.save(&entries).await.unwrap();
+
+        assert_eq!(migrate_format(&json, &bincode).await.unwrap(), 3);
+        assert!(json.load().await.unwrap().is_empty());
+        assert_eq!(bincode.load().await.unwrap().len(), 3);
+        assert!(temp_dir.path().join("a.bin").exists());
+        assert!(!temp_dir.path().join("a.json").exists());
+
+        // Migrating onto the same format keeps the files
+        assert_eq!(migrate_format(&bincode, &bincode).await.unwrap(), 3);
+        assert_eq!(bincode.load().await.unwrap().len(), 3);
+    }
+
+    #[cfg(feature = "cbor-serialization")]
+    #[tokio::test]
+    async fn test_cbor_format_round_trip() {
+        let (temp_dir, backend) = new_backend().await;
+        let backend = backend.with_format(SerializationFormat::Cbor);
+
+        let mut entries = HashMap::new();
+        entries.insert(
+            "key".to_string(),
+            vec![CacheEntry::new("key".to_string(), "value".to_string())],
+        );
+        backend.save(&entries).await.unwrap();
+
+        let path = temp_dir.path().join("key.cbor");
+        assert!(backend.is_cache_file_path(&path));
+        assert!(!backend.is_cache_file_path(&backend.get_metadata_path()));
+        let loaded = backend.load().await.unwrap();
+        assert_eq!(loaded["key"][0].value, "value");
+
+        let data = fs::read(backend.get_metadata_path()).await.unwrap();
+        let metadata: CacheMetadata = SerializationFormat::Cbor.deserialize(&data).unwrap();
+        assert_eq!(metadata.total_keys, 1);
+    }
+
+    #[tokio::test]
+    async fn test_save_keys_writes_only_the_given_keys() {
+        let (_temp_dir, backend) = new_backend().await;
+        let bucket =
+            |key: &str, value: &str| vec![CacheEntry::new(key.to_string(), value.to_string())];
+        let mut entries = HashMap::new();
+        for key in ["a", "b", "gone"] {
+            entries.insert(key.to_string(), bucket(key, "old"));
+        }
+        backend.save(&entries).await.unwrap();
+
+        // Only the entries of the given keys are handed over, and the key
+        // count still covers the untouched ones
+        let partial: HashMap<_, _> = [("a".to_string(), bucket("a", "new"))].into();
+        backend
+            .save_keys(&partial, &["a".to_string(), "gone".to_string()])
+            .await
+            .unwrap();
+
+        let loaded = backend.load().await.unwrap();
+        assert_eq!(loaded["a"][0].value, "new");
+        assert_eq!(loaded["b"][0].value, "old");
+        assert!(!loaded.contains_key("gone"));
+        assert_eq!(backend.read_metadata().await.unwrap().total_keys, 2);
+    }
+
+    #[cfg(feature = "compression")]
+    #[tokio::test]
+    async fn test_compressed_round_trip() {
+        let (temp_dir, backend) = new_backend().await;
+        let backend = backend.with_compression(CompressionAlgorithm::Gzip);
+
+        let mut entries = HashMap::new();
+        entries.insert(
+            "key".to_string(),
+            vec![CacheEntry::new("key".to_string(), "value".repeat(100))],
+        );
+        backend.save(&entries).await.unwrap();
+
+        let path = temp_dir.path().join("key.json.gz");
+        assert!(path.exists());
+        assert!(backend.contains(&"key".to_string()).await.unwrap());
+
+        let loaded = backend.load().await.unwrap();
+        assert_eq!(loaded["key"][0].value, "value".repeat(100));
+
+        backend.remove(&"key".to_string()).await.unwrap();
+        assert!(!path.exists());
+    }
+
+    #[cfg(feature = "compression")]
+    #[tokio::test]
+    async fn test_value_compression_only_compresses_large_values() {
+        let (temp_dir, backend) = new_backend().await;
+        let backend = backend
+            .with_value_compression(ValueCompression {
+                threshold: 64,
+                algorithm: CompressionAlgorithm::Gzip,
+                level: 6,
+            })
+            .await
+            .unwrap();
+
+        let blob = "blob".repeat(1000);
+        let mut large =
+            CacheEntry::new("key".to_string(), blob.clone()).with_ttl(chrono::Duration::hours(1));
+        large.set_access_count(7);
+        let mut entries = HashMap::new();
+        entries.insert(
+            "key".to_string(),
+            vec![
+                large.clone(),
+                CacheEntry::new("key".to_string(), "small".to_string()),
+            ],
+        );
+        backend.save(&entries).await.unwrap();
+
+        let data = std::fs::read(temp_dir.path().join("key.json")).unwrap();
+        assert!(data.starts_with(VALUE_COMPRESSION_MAGIC));
+        assert!(data.len() < blob.len());
+        let text = String::from_utf8_lossy(&data);
+        assert!(text.contains("\"small\""));
+        assert!(!text.contains("\"data\":["));
+
+        // A backend without value compression still reads the file
+        let plain: FilesystemBackend<String, String> =
+            FilesystemBackend::new(temp_dir.path()).await.unwrap();
+        let loaded = plain.load().await.unwrap();
+        assert_eq!(loaded["key"][0].value, blob);
+        assert_eq!(loaded["key"][0].expiry, large.expiry);
+        assert_eq!(loaded["key"][0].access_count(), 7);
+        assert_eq!(loaded["key"][1].value, "small");
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn test_compressed_bytes_are_base64_in_json() {
+        let stored: StoredValue<String> = StoredValue::Compressed {
+            algorithm: CompressionAlgorithm::Gzip,
+            data: vec![1, 2, 255],
+        };
+        let json = serde_json::to_string(&stored).unwrap();
+        assert_eq!(json, r#"{"Compressed":{"algorithm":"Gzip","data":"AQL/"}}"#);
+
+        // Arrays of numbers, as older files hold, still read
+        for json in [
+            json.as_str(),
+            r#"{"Compressed":{"algorithm":"Gzip","data":[1,2,255]}}"#,
+        ] {
+            let StoredValue::Compressed { data, .. } =
+                serde_json::from_str::<StoredValue<String>>(json).unwrap()
+            else {
+                panic!("expected a compressed value");
+            };
+            assert_eq!(data, [1, 2, 255]);
+        }
+    }
+
+    #[cfg(feature = "compression")]
+    #[tokio::test]
+    async fn test_compressed_backend_loads_legacy_files() {
+        let (temp_dir, plain) = new_backend().await;
+
+        let mut entries = HashMap::new();
+        entries.insert(
+            "legacy".to_string(),
+            vec![CacheEntry::new("legacy".to_string(), "old".to_string())],
+        );
+        plain.save(&entries).await.unwrap();
+
+        let compressed: FilesystemBackend<String, String> = FilesystemBackend::new(temp_dir.path())
+            .await
+            .unwrap()
+            .with_compression(CompressionAlgorithm::Gzip);
+        let loaded = compressed.load().await.unwrap();
+        assert_eq!(loaded["legacy"][0].value, "old");
+
+        // Rewriting with compression supersedes the legacy file on load
+        entries.insert(
+            "legacy".to_string(),
+            vec![CacheEntry::new("legacy".to_string(), "new".to_string())],
+        );
+        compressed.save(&entries).await.unwrap();
+        let loaded = compressed.load().await.unwrap();
+        assert_eq!(loaded["legacy"][0].value, "new");
+    }
+
+    #[cfg(feature = "compression")]
+    #[tokio::test]
+    async fn test_compact_moves_files_to_current_compression() {
+        let (temp_dir, plain) = new_backend().await;
+        let mut entries = HashMap::new();
+        for key in ["legacy", "shadowed"] {
+            entries.insert(
+                key.to_string(),
+                vec![CacheEntry::new(key.to_string(), "old".to_string())],
+            );
+        }
+        plain.save(&entries).await.unwrap();
+
+        let compressed: FilesystemBackend<String, String> = FilesystemBackend::new(temp_dir.path())
+            .await
+            .unwrap()
+            .with_compression(CompressionAlgorithm::Gzip);
+        let newer = HashMap::from([(
+            "shadowed".to_string(),
+            vec![CacheEntry::new("shadowed".to_string(), "new".to_string())],
+        )]);
+        compressed.save(&newer).await.unwrap();
+        compressed.compact().await.unwrap();
+
+        assert!(!temp_dir.path().join("legacy.json").exists());
+        assert!(!temp_dir.path().join("shadowed.json").exists());
+        let loaded = compressed.load().await.unwrap();
+        assert_eq!(loaded["legacy"][0].value, "old");
+        assert_eq!(loaded["shadowed"][0].value, "new");
+    }
+
     #[test]
     fn test_filename_sanitization() {
         let cases = [
@@ -377,15 +1700,421 @@ mod tests {
             ("   ", ""),
         ];
         for (input, expected) in cases {
-            assert_eq!(
-                FilesystemBackend::<String, String>::sanitize_filename(input),
-                expected
-            );
+            assert_eq!(sanitize_filename(input), expected);
         }
 
-        let result = FilesystemBackend::<String, String>::sanitize_filename("../etc/passwd");
+        let result = sanitize_filename("../etc/passwd");
         assert!(!result.contains('/'));
         assert!(!result.contains('\\'));
         assert!(!result.starts_with('.'));
     }
+
+    #[tokio::test]
+    async fn test_colliding_keys_survive_round_trip() {
+        let (_temp_dir, backend) = new_backend().await;
+        let keys = ["a/b", "a_b", "a:b", "", "..."];
+        let entries: HashMap<_, _> = keys
+            .iter()
+            .map(|key| {
+                (
+                    key.to_string(),
+                    vec![CacheEntry::new(key.to_string(), format!("value of {key}"))],
+                )
+            })
+            .collect();
+        backend.save(&entries).await.unwrap();
+
+        let loaded = backend.load().await.unwrap();
+        assert_eq!(loaded.len(), keys.len());
+        for key in keys {
+            assert_eq!(loaded[key][0].value, format!("value of {key}"));
+        }
+
+        backend.remove(&"a/b".to_string()).await.unwrap();
+        assert!(!backend.contains(&"a/b".to_string()).await.unwrap());
+        assert!(backend.contains(&"a_b".to_string()).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_load_trusts_embedded_key_over_file_name() {
+        let (temp_dir, backend) = new_backend().await;
+        let ext = backend.format.extension();
+        let entry_vec = vec![CacheEntry::new("real".to_string(), "v".to_string())];
+        let path = temp_dir.path().join(format!("renamed.{ext}"));
+        backend
+            .write_data(&path, &backend.encode(&entry_vec).unwrap())
+            .await
+            .unwrap();
+
+        assert!(!FilesystemBackend::<String, String>::is_file_for_key(
+            &path, "real"
+        ));
+        assert!(FilesystemBackend::<String, String>::is_file_for_key(
+            &temp_dir.path().join(format!("real.{ext}")),
+            "real"
+        ));
+        let loaded = backend.load().await.unwrap();
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded["real"][0].value, "v");
+    }
+
+    #[tokio::test]
+    async fn test_legacy_file_names_are_read_and_retired() {
+        let (temp_dir, backend) = new_backend().await;
+        let key = "a/b".to_string();
+
+        // Older versions stored "a/b" in the file now reserved for "a_b"
+        let legacy_path = temp_dir
+            .path()
+            .join(format!("a_b.{}", backend.format.extension()));
+        let old = vec![CacheEntry::new(key.clone(), "old".to_string())];
+        backend
+            .write_data(&legacy_path, &backend.encode(&old).unwrap())
+            .await
+            .unwrap();
+
+        assert!(backend.contains(&key).await.unwrap());
+        assert_eq!(
+            backend.load_key(&key).await.unwrap().unwrap()[0].value,
+            "old"
+        );
+        // The legacy file does not belong to "a_b"
+        assert!(backend
+            .load_key(&"a_b".to_string())
+            .await
+            .unwrap()
+            .is_none());
+
+        let mut entries = HashMap::new();
+        entries.insert(
+            key.clone(),
+            vec![CacheEntry::new(key.clone(), "new".to_string())],
+        );
+        backend.save(&entries).await.unwrap();
+        assert!(!legacy_path.exists());
+        assert_eq!(backend.load().await.unwrap()[&key][0].value, "new");
+    }
+
+    #[tokio::test]
+    async fn test_hashed_names_support_keys_without_display() {
+        #[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+        struct FileKey {
+            path: String,
+            version: u32,
+        }
+
+        let temp_dir = TempDir::new().unwrap();
+        let backend: FilesystemBackend<FileKey, String> =
+            FilesystemBackend::new_hashed(temp_dir.path())
+                .await
+                .unwrap();
+        let key = |version| FileKey {
+            path: "/etc/hosts".to_string(),
+            version,
+        };
+
+        let mut entries = HashMap::new();
+        for version in [1, 2] {
+            entries.insert(
+                key(version),
+                vec![CacheEntry::new(key(version), format!("v{version}"))],
+            );
+        }
+        backend.save(&entries).await.unwrap();
+        assert_eq!(backend.load().await.unwrap().len(), 2);
+        assert_eq!(
+            backend.load_key(&key(2)).await.unwrap().unwrap()[0].value,
+            "v2"
+        );
+
+        // Names are stable and give nothing of the key away
+        let name = hashed_key_name(&key(1)).unwrap();
+        assert_eq!(name, hashed_key_name(&key(1)).unwrap());
+        assert!(!name.contains("hosts"));
+        assert!(backend.get_cache_file_path(&name).exists());
+
+        backend.remove(&key(1)).await.unwrap();
+        assert!(!backend.contains(&key(1)).await.unwrap());
+        assert!(backend.contains(&key(2)).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_errors_name_the_key() {
+        let (temp_dir, backend) = new_backend().await;
+        // A directory where the key's file belongs can be neither written
+        // nor removed as a file
+        let path = backend.get_cache_file_path("blocked");
+        fs::create_dir(&path).await.unwrap();
+
+        let err = backend.remove(&"blocked".to_string()).await.unwrap_err();
+        assert!(err.to_string().contains("key blocked"), "{err}");
+
+        let mut entries = HashMap::new();
+        entries.insert(
+            "blocked".to_string(),
+            vec![CacheEntry::new("blocked".to_string(), "v".to_string())],
+        );
+        let err = backend.save(&entries).await.unwrap_err();
+        assert!(err.to_string().contains("key blocked"), "{err}");
+        drop(temp_dir);
+    }
+
+    #[tokio::test]
+    async fn test_save_error_policy_continue_reports_every_failure() {
+        let (temp_dir, backend) = new_backend().await;
+        for key in ["blocked-1", "blocked-2"] {
+            fs::create_dir(backend.get_cache_file_path(key))
+                .await
+                .unwrap();
+        }
+        let entries: HashMap<_, _> = ["blocked-1", "ok", "blocked-2"]
+            .into_iter()
+            .map(|key| {
+                (
+                    key.to_string(),
+                    vec![CacheEntry::new(key.to_string(), "v".to_string())],
+                )
+            })
+            .collect();
+
+        let backend = backend.with_save_error_policy(SaveErrorPolicy::Continue);
+        let Err(CacheError::PartialSave { failures }) = backend.save(&entries).await else {
+            panic!("expected a partial save");
+        };
+        let mut failed: Vec<_> = failures.iter().map(|(key, _)| key.as_str()).collect();
+        failed.sort();
+        assert_eq!(failed, ["blocked-1", "blocked-2"]);
+        assert!(backend.contains(&"ok".to_string()).await.unwrap());
+
+        // Aborting names only the key it stopped at
+        let backend = backend.with_save_error_policy(SaveErrorPolicy::Abort);
+        let err = backend.save(&entries).await.unwrap_err();
+        assert!(
+            matches!(err, CacheError::Io(_)) && err.to_string().contains("key blocked-"),
+            "{err}"
+        );
+        drop(temp_dir);
+    }
+
+    #[tokio::test]
+    async fn test_save_error_policy_continue_names_keys_without_a_file_name() {
+        // JSON maps need string keys, so this key cannot be hashed into a name
+        type MapKey = std::collections::BTreeMap<Vec<u8>, u8>;
+
+        let temp_dir = TempDir::new().unwrap();
+        let backend: FilesystemBackend<MapKey, String> =
+            FilesystemBackend::new_hashed(temp_dir.path())
+                .await
+                .unwrap()
+                .with_save_error_policy(SaveErrorPolicy::Continue);
+        let key = MapKey::from([(vec![1], 2)]);
+        let entries = HashMap::from([(key.clone(), vec![CacheEntry::new(key, "v".to_string())])]);
+
+        let Err(CacheError::PartialSave { failures }) = backend.save(&entries).await else {
+            panic!("expected a partial save");
+        };
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].0, "{[1]: 2}");
+        drop(temp_dir);
+    }
+
+    #[tokio::test]
+    async fn test_namespace_uses_isolated_subdirectory() {
+        let (temp_dir, root) = new_backend().await;
+        let tenant = FilesystemBackend::<String, String>::new(temp_dir.path())
+            .await
+            .unwrap()
+            .with_namespace("tenant/a")
+            .await
+            .unwrap();
+        assert_eq!(
+            tenant.base_path,
+            temp_dir.path().join(namespace_segment("tenant/a").unwrap())
+        );
+        let plain = FilesystemBackend::<String, String>::new(temp_dir.path())
+            .await
+            .unwrap()
+            .with_namespace("tenant_a")
+            .await
+            .unwrap();
+        assert_eq!(plain.base_path, temp_dir.path().join("tenant_a"));
+
+        let mut entries = HashMap::new();
+        entries.insert(
+            "key".to_string(),
+            vec![CacheEntry::new("key".to_string(), "tenant".to_string())],
+        );
+        tenant.save(&entries).await.unwrap();
+        entries.get_mut("key").unwrap()[0].value = "root".to_string();
+        root.save(&entries).await.unwrap();
+
+        root.clear().await.unwrap();
+        assert!(root.load().await.unwrap().is_empty());
+        assert!(tenant.contains(&"key".to_string()).await.unwrap());
+        assert_eq!(tenant.load().await.unwrap()["key"][0].value, "tenant");
+        assert!(plain.load().await.unwrap().is_empty());
+
+        let invalid = FilesystemBackend::<String, String>::new(temp_dir.path())
+            .await
+            .unwrap()
+            .with_namespace(" ")
+            .await;
+        assert!(invalid.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_failed_write_leaves_previous_file_intact() {
+        let (_temp_dir, backend) = new_backend().await;
+        let mut entries = HashMap::new();
+        entries.insert(
+            "key".to_string(),
+            vec![CacheEntry::new("key".to_string(), "old".to_string())],
+        );
+        backend.save(&entries).await.unwrap();
+
+        // A directory in the way of the temporary file makes the write fail
+        // before the rename
+        let path = backend.get_cache_file_path("key");
+        let temp_path = FilesystemBackend::<String, String>::temp_path(&path);
+        fs::create_dir(&temp_path).await.unwrap();
+        entries.get_mut("key").unwrap()[0].value = "new".to_string();
+        assert!(backend.save(&entries).await.is_err());
+        fs::remove_dir(&temp_path).await.unwrap();
+
+        // A partial temporary file from a crash is ignored
+        fs::write(&temp_path, b"[{\"key\":").await.unwrap();
+        let loaded = backend.load().await.unwrap();
+        assert_eq!(loaded["key"][0].value, "old");
+
+        backend.save(&entries).await.unwrap();
+        assert_eq!(backend.load().await.unwrap()["key"][0].value, "new");
+    }
+
+    #[tokio::test]
+    async fn test_save_waits_for_exclusive_lock() {
+        let (_temp_dir, backend) = new_backend().await;
+        let mut entries = HashMap::new();
+        entries.insert(
+            "key".to_string(),
+            vec![CacheEntry::new("key".to_string(), "value".to_string())],
+        );
+
+        let path = backend.get_cache_file_path("key");
+        let held = FilesystemBackend::<String, String>::lock_exclusive(&path)
+            .await
+            .unwrap();
+        let save = tokio::spawn(async move { backend.save(&entries).await.map(|_| backend) });
+
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        assert!(!save.is_finished());
+        assert!(!path.exists());
+
+        drop(held);
+        let backend = save.await.unwrap().unwrap();
+        assert_eq!(backend.load().await.unwrap()["key"][0].value, "value");
+    }
+
+    #[tokio::test]
+    async fn test_reads_create_no_lock_files_and_removal_deletes_them() {
+        let (_temp_dir, backend) = new_backend().await;
+        let mut entries = HashMap::new();
+        for key in ["a", "b", "c"] {
+            entries.insert(
+                key.to_string(),
+                vec![CacheEntry::new(key.to_string(), "value".to_string())],
+            );
+        }
+        backend.save(&entries).await.unwrap();
+        let lock_a =
+            FilesystemBackend::<String, String>::lock_path(&backend.get_cache_file_path("a"));
+        let lock_b =
+            FilesystemBackend::<String, String>::lock_path(&backend.get_cache_file_path("b"));
+        assert!(lock_a.exists());
+
+        // A reader loads fine without a lock file and does not leave one
+        fs::remove_file(&lock_b).await.unwrap();
+        assert_eq!(backend.load().await.unwrap().len(), 3);
+        assert!(!lock_b.exists());
+
+        #[cfg(unix)]
+        {
+            backend.remove(&"a".to_string()).await.unwrap();
+            assert!(!lock_a.exists());
+
+            backend.clear().await.unwrap();
+            let mut dir_entries = fs::read_dir(&backend.base_path).await.unwrap();
+            while let Some(entry) = dir_entries.next_entry().await.unwrap() {
+                let name = entry.file_name();
+                let name = name.to_string_lossy();
+                assert!(
+                    !name.ends_with(".lock") || name.starts_with("metadata"),
+                    "{name}"
+                );
+            }
+        }
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_lock_files_are_deleted_under_their_lock() {
+        type Backend = FilesystemBackend<String, String>;
+
+        let (_temp_dir, backend) = new_backend().await;
+        let path = backend.get_cache_file_path("key");
+        let lock_path = Backend::lock_path(&path);
+
+        // Removal waits for the writer holding the lock
+        let held = Backend::lock_exclusive(&path).await.unwrap();
+        let removal_path = path.clone();
+        let removal = tokio::spawn(async move { Backend::remove_lock(&removal_path).await });
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        assert!(!removal.is_finished());
+        assert!(lock_path.exists());
+        drop(held);
+        removal.await.unwrap().unwrap();
+        assert!(!lock_path.exists());
+
+        // A writer that waited on a lock file deleted meanwhile locks anew
+        let held = Backend::lock_exclusive(&path).await.unwrap();
+        let waiting_path = path.clone();
+        let waiting = tokio::spawn(async move { Backend::lock_exclusive(&waiting_path).await });
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        fs::remove_file(&lock_path).await.unwrap();
+        drop(held);
+        let relocked = waiting.await.unwrap().unwrap();
+        assert!(is_current_lock(&relocked, &lock_path));
+    }
+
+    #[tokio::test]
+    async fn test_integrity_checks_skip_bit_rot() {
+        let (temp_dir, backend) = new_backend().await;
+        let backend = backend.with_integrity_checks(true);
+        let mut entries = HashMap::new();
+        for key in ["good", "rotten"] {
+            entries.insert(
+                key.to_string(),
+                vec![CacheEntry::new(key.to_string(), "value1".to_string())],
+            );
+        }
+        backend.save(&entries).await.unwrap();
+
+        // Flip a digit in the value; the file still parses
+        let path = backend.get_cache_file_path("rotten");
+        let mut data = fs::read(&path).await.unwrap();
+        let at = data.windows(6).position(|w| w == b"value1").unwrap();
+        data[at + 5] = b'2';
+        fs::write(&path, &data).await.unwrap();
+        assert!(FilesystemBackend::<String, String>::new(temp_dir.path())
+            .await
+            .unwrap()
+            .load()
+            .await
+            .unwrap()
+            .contains_key("rotten"));
+
+        let loaded = backend.load().await.unwrap();
+        assert!(loaded.contains_key("good"));
+        assert!(!loaded.contains_key("rotten"));
+        assert_eq!(backend.integrity_failures(), 1);
+    }
 }
@@ -2,14 +2,23 @@
 
 use async_trait::async_trait;
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, PoisonError};
 use tokio::sync::RwLock;
 
 use crate::backends::{StorageKey, StorageMeta, StorageValue};
-use crate::storage::EntryMap;
+use crate::storage::{EntryMap, SerializationFormat};
 use crate::{CacheEntry, Result, StorageBackend};
 
 /// In-memory storage backend
+///
+/// Clones share the same storage. Each namespace gets its own entry map, so
+/// clones scoped to different namespaces never see each other's entries.
+///
+/// Storage is unbounded unless a capacity is set with `with_capacity`.
+///
+/// `size_bytes` multiplies the entry count by the size of `CacheEntry`, which
+/// leaves out whatever values and metadata allocate on the heap. With
+/// `with_accurate_sizing` it serializes every entry instead.
 #[allow(clippy::type_complexity)]
 pub struct MemoryBackend<K, V, M = ()>
 where
@@ -17,7 +26,13 @@ where
     V: StorageValue,
     M: StorageMeta,
 {
-    data: Arc<RwLock<HashMap<K, Vec<CacheEntry<K, V, M>>>>>,
+    data: Arc<RwLock<HashMap<String, EntryMap<K, V, M>>>>,
+    namespace: String,
+    capacity: Option<usize>,
+    accurate_sizing: bool,
+    sizing_format: Option<SerializationFormat>,
+    /// Serialized size of each namespace, kept until it is next written
+    sizes: Arc<Mutex<HashMap<String, u64>>>,
 }
 
 impl<K, V, M> MemoryBackend<K, V, M>
@@ -30,8 +45,92 @@ where
     pub fn new() -> Self {
         Self {
             data: Arc::new(RwLock::new(HashMap::new())),
+            namespace: String::new(),
+            capacity: None,
+            accurate_sizing: false,
+            sizing_format: None,
+            sizes: Arc::default(),
         }
     }
+
+    /// Report the serialized size of the stored entries from `size_bytes`
+    ///
+    /// Every entry is serialized the first time the size is asked for after
+    /// a write, which costs about as much as saving them to disk. The format
+    /// is the default one unless `with_sizing_format` picks another.
+    pub fn with_accurate_sizing(mut self, enable: bool) -> Self {
+        self.accurate_sizing = enable;
+        self
+    }
+
+    /// Measure entries in `format` when accurate sizing is on
+    pub fn with_sizing_format(mut self, format: SerializationFormat) -> Self {
+        self.sizing_format = Some(format);
+        self
+    }
+
+    /// Forget the measured size of this backend's namespace
+    fn invalidate_size(&self) {
+        self.sizes
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(&self.namespace);
+    }
+
+    /// Serialized size of `entries`, measured at most once between writes
+    fn measured_size(&self, entries: Option<&EntryMap<K, V, M>>) -> Result<u64> {
+        let mut sizes = self.sizes.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(&size) = sizes.get(&self.namespace) {
+            return Ok(size);
+        }
+        let format = match self.sizing_format {
+            Some(format) => format,
+            None => SerializationFormat::default_format()?,
+        };
+        let mut size = 0u64;
+        for entry in entries
+            .into_iter()
+            .flat_map(|entries| entries.values().flatten())
+        {
+            size += format.serialize(entry)?.len() as u64;
+        }
+        sizes.insert(self.namespace.clone(), size);
+        Ok(size)
+    }
+
+    /// Keep at most `max_entries` entries per namespace
+    ///
+    /// Saves over capacity keep the newest entries across all keys, by
+    /// timestamp, and drop the rest. Keys left without entries are dropped.
+    pub fn with_capacity(mut self, max_entries: usize) -> Self {
+        self.capacity = Some(max_entries);
+        self
+    }
+
+    /// Copy `entries`, keeping only the newest `max_entries` of them
+    fn trimmed(entries: &EntryMap<K, V, M>, max_entries: usize) -> EntryMap<K, V, M> {
+        let mut ranked: Vec<(&K, usize)> = entries
+            .iter()
+            .flat_map(|(key, bucket)| (0..bucket.len()).map(move |index| (key, index)))
+            .collect();
+        ranked.sort_by(|a, b| {
+            entries[b.0][b.1]
+                .timestamp
+                .cmp(&entries[a.0][a.1].timestamp)
+        });
+        ranked.truncate(max_entries);
+        // Keep each key's remaining entries in their original order
+        ranked.sort_by_key(|&(_, index)| index);
+
+        let mut trimmed: EntryMap<K, V, M> = HashMap::new();
+        for (key, index) in ranked {
+            trimmed
+                .entry(key.clone())
+                .or_default()
+                .push(entries[key][index].clone());
+        }
+        trimmed
+    }
 }
 
 impl<K, V, M> Default for MemoryBackend<K, V, M>
@@ -54,6 +153,11 @@ where
     fn clone(&self) -> Self {
         Self {
             data: Arc::clone(&self.data),
+            namespace: self.namespace.clone(),
+            capacity: self.capacity,
+            accurate_sizing: self.accurate_sizing,
+            sizing_format: self.sizing_format,
+            sizes: Arc::clone(&self.sizes),
         }
     }
 }
@@ -70,42 +174,102 @@ where
     type Metadata = M;
 
     async fn save(&self, entries: &EntryMap<K, V, M>) -> Result<()> {
+        let entries = match self.capacity {
+            Some(max_entries) if entries.values().map(Vec::len).sum::<usize>() > max_entries => {
+                Self::trimmed(entries, max_entries)
+            }
+            _ => entries.clone(),
+        };
+        let mut data = self.data.write().await;
+        data.insert(self.namespace.clone(), entries);
+        self.invalidate_size();
+        Ok(())
+    }
+
+    /// Replace only the buckets of `keys`, leaving the other keys as stored
+    ///
+    /// A backend with a capacity saves everything instead, since which
+    /// entries it keeps depends on all of them.
+    async fn save_keys(&self, entries: &EntryMap<K, V, M>, keys: &[K]) -> Result<()> {
+        if self.capacity.is_some() {
+            return self.save(entries).await;
+        }
         let mut data = self.data.write().await;
-        *data = entries.clone();
+        let stored = data.entry(self.namespace.clone()).or_default();
+        for key in keys {
+            match entries.get(key) {
+                Some(bucket) => stored.insert(key.clone(), bucket.clone()),
+                None => stored.remove(key),
+            };
+        }
+        self.invalidate_size();
         Ok(())
     }
 
+    fn supports_partial_save(&self) -> bool {
+        self.capacity.is_none()
+    }
+
     async fn load(&self) -> Result<EntryMap<K, V, M>> {
         let data = self.data.read().await;
-        Ok(data.clone())
+        Ok(data.get(&self.namespace).cloned().unwrap_or_default())
+    }
+
+    async fn load_key(&self, key: &K) -> Result<Option<Vec<CacheEntry<K, V, M>>>> {
+        let data = self.data.read().await;
+        Ok(data
+            .get(&self.namespace)
+            .and_then(|entries| entries.get(key))
+            .cloned())
     }
 
     async fn remove(&self, key: &K) -> Result<()> {
         let mut data = self.data.write().await;
-        data.remove(key);
+        if let Some(entries) = data.get_mut(&self.namespace) {
+            if entries.remove(key).is_some() {
+                self.invalidate_size();
+            }
+        }
         Ok(())
     }
 
     async fn clear(&self) -> Result<()> {
         let mut data = self.data.write().await;
-        data.clear();
+        data.remove(&self.namespace);
+        self.invalidate_size();
         Ok(())
     }
 
     async fn contains(&self, key: &K) -> Result<bool> {
         let data = self.data.read().await;
-        Ok(data.contains_key(key))
+        Ok(data
+            .get(&self.namespace)
+            .is_some_and(|entries| entries.contains_key(key)))
     }
 
     async fn size_bytes(&self) -> Result<u64> {
         let data = self.data.read().await;
+        if self.accurate_sizing {
+            // The read lock keeps writers from invalidating the size while
+            // it is measured
+            return self.measured_size(data.get(&self.namespace));
+        }
 
         // Estimate size based on number of entries
-        let total_entries: usize = data.values().map(|v| v.len()).sum();
+        let total_entries: usize = data
+            .get(&self.namespace)
+            .map_or(0, |entries| entries.values().map(|v| v.len()).sum());
         let estimated_size = total_entries * std::mem::size_of::<CacheEntry<K, V, M>>();
 
         Ok(estimated_size as u64)
     }
+
+    async fn with_namespace(self, namespace: &str) -> Result<Self> {
+        Ok(Self {
+            namespace: namespace.to_string(),
+            ..self
+        })
+    }
 }
 
 #[cfg(test)]
@@ -125,4 +289,109 @@ mod tests {
         backend1.save(&entries).await.unwrap();
         assert!(backend2.contains(&"key1".to_string()).await.unwrap());
     }
+
+    #[tokio::test]
+    async fn test_namespaces_share_storage_but_not_entries() {
+        let shared: MemoryBackend<String, String> = MemoryBackend::new();
+        let tenant_a = shared.clone().with_namespace("a").await.unwrap();
+        let tenant_b = shared.clone().with_namespace("b").await.unwrap();
+
+        let mut entries = HashMap::new();
+        entries.insert(
+            "key".to_string(),
+            vec![CacheEntry::new("key".to_string(), "a".to_string())],
+        );
+        tenant_a.save(&entries).await.unwrap();
+        tenant_b.save(&entries).await.unwrap();
+
+        tenant_b.remove(&"key".to_string()).await.unwrap();
+        assert!(tenant_a.contains(&"key".to_string()).await.unwrap());
+        assert!(!tenant_b.contains(&"key".to_string()).await.unwrap());
+        assert!(!shared.contains(&"key".to_string()).await.unwrap());
+
+        tenant_b.save(&entries).await.unwrap();
+        tenant_a.clear().await.unwrap();
+        assert!(tenant_a.load().await.unwrap().is_empty());
+        assert_eq!(tenant_b.load().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_save_keys_merges_only_the_given_keys() {
+        let backend: MemoryBackend<String, String> = MemoryBackend::new();
+        let bucket =
+            |key: &str, value: &str| vec![CacheEntry::new(key.to_string(), value.to_string())];
+        let mut entries = HashMap::new();
+        for key in ["a", "b", "gone"] {
+            entries.insert(key.to_string(), bucket(key, "old"));
+        }
+        backend.save(&entries).await.unwrap();
+
+        entries.insert("a".to_string(), bucket("a", "new"));
+        entries.insert("b".to_string(), bucket("b", "new"));
+        entries.remove("gone");
+        backend
+            .save_keys(&entries, &["a".to_string(), "gone".to_string()])
+            .await
+            .unwrap();
+
+        let stored = backend.load().await.unwrap();
+        assert_eq!(stored.len(), 2);
+        assert_eq!(stored["a"][0].value, "new");
+        assert_eq!(stored["b"][0].value, "old");
+    }
+
+    #[cfg(feature = "json-serialization")]
+    #[tokio::test]
+    async fn test_accurate_sizing_counts_heap_contents() {
+        let estimated: MemoryBackend<String, String> = MemoryBackend::new();
+        let accurate = estimated
+            .clone()
+            .with_accurate_sizing(true)
+            .with_sizing_format(SerializationFormat::Json);
+        let entries = |value: &str| {
+            HashMap::from([(
+                "key".to_string(),
+                vec![CacheEntry::new("key".to_string(), value.to_string())],
+            )])
+        };
+
+        accurate.save(&entries(&"x".repeat(100_000))).await.unwrap();
+        assert!(estimated.size_bytes().await.unwrap() < 1_000);
+        let large = accurate.size_bytes().await.unwrap();
+        assert!(large > 100_000);
+        assert_eq!(accurate.size_bytes().await.unwrap(), large);
+
+        // Writes through any clone drop the measured size
+        estimated.save(&entries("small")).await.unwrap();
+        assert!(accurate.size_bytes().await.unwrap() < 1_000);
+        estimated.clear().await.unwrap();
+        assert_eq!(accurate.size_bytes().await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_capacity_keeps_newest_entries() {
+        let unbounded: MemoryBackend<String, String> = MemoryBackend::new();
+        let backend = unbounded.clone().with_capacity(2);
+        let now = chrono::Utc::now();
+        let entry = |key: &str, age: i64| {
+            let mut entry = CacheEntry::new(key.to_string(), age.to_string());
+            entry.timestamp = now - chrono::Duration::seconds(age);
+            entry
+        };
+        let entries = HashMap::from([
+            ("a".to_string(), vec![entry("a", 3), entry("a", 1)]),
+            ("b".to_string(), vec![entry("b", 2)]),
+            ("c".to_string(), vec![entry("c", 4)]),
+        ]);
+
+        unbounded.save(&entries).await.unwrap();
+        assert_eq!(unbounded.load().await.unwrap().len(), 3);
+
+        backend.save(&entries).await.unwrap();
+        let loaded = backend.load().await.unwrap();
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(loaded["a"].len(), 1);
+        assert_eq!(loaded["a"][0].value, "1");
+        assert_eq!(loaded["b"][0].value, "2");
+    }
 }
@@ -28,7 +28,121 @@ impl<T> StorageValue for T where T: BackendValue + Serialize + DeserializeOwned
 pub trait StorageMeta: BackendMeta + Serialize + DeserializeOwned + EntryMetadata {}
 impl<T> StorageMeta for T where T: BackendMeta + Serialize + DeserializeOwned + EntryMetadata {}
 
+/// Sanitize a filename by removing or replacing dangerous characters
+#[cfg(any(
+    feature = "filesystem-backend",
+    feature = "s3-backend",
+    feature = "append-log-backend"
+))]
+pub(crate) fn sanitize_filename(filename: &str) -> String {
+    // Replace path separators and other dangerous characters with safe alternatives
+    let mut result = filename
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_', // Replace control characters
+            c => c,
+        })
+        .collect::<String>();
+
+    // Replace leading dots to prevent hidden files
+    if result.starts_with('.') {
+        result = result.replacen('.', "_", 1);
+    }
+
+    // Clean up trailing dots and whitespace
+    result.trim_matches('.').trim().to_string()
+}
+
+/// Get the file (or object) stem used for a cache key
+///
+/// Keys that sanitizing leaves unchanged are used as is. Any other key gets a
+/// hash of the raw key appended, so keys that sanitize to the same name, such
+/// as `a/b` and `a_b`, are still stored apart.
+#[cfg(any(feature = "filesystem-backend", feature = "s3-backend"))]
+pub(crate) fn cache_file_stem(key: &str) -> String {
+    let sanitized_key = sanitize_filename(key);
+    if !sanitized_key.is_empty() && sanitized_key == key {
+        return sanitized_key;
+    }
+    let base = if sanitized_key.is_empty() {
+        "cache_entry"
+    } else {
+        &sanitized_key
+    };
+    format!("{base}-{:016x}", fnv1a(key.as_bytes()))
+}
+
+/// Get the directory (or object key segment) used for a namespace
+///
+/// As with `cache_file_stem`, a namespace that sanitizing changes gets a hash
+/// of the raw namespace appended, so `tenant/a` and `tenant_a` are still
+/// kept apart. Returns `None` if nothing is left after sanitizing.
+#[cfg(any(
+    feature = "filesystem-backend",
+    feature = "s3-backend",
+    feature = "append-log-backend"
+))]
+pub(crate) fn namespace_segment(namespace: &str) -> Option<String> {
+    let sanitized = sanitize_filename(namespace);
+    if sanitized.is_empty() {
+        None
+    } else if sanitized == namespace {
+        Some(sanitized)
+    } else {
+        Some(format!("{sanitized}-{:016x}", fnv1a(namespace.as_bytes())))
+    }
+}
+
+/// Get the stem earlier versions used for a key, if it differs from the
+/// current one
+///
+/// Those versions stored every key under its bare sanitized name, so a file
+/// with this stem may belong to a different key and must be checked before
+/// it is trusted.
+#[cfg(any(feature = "filesystem-backend", feature = "s3-backend"))]
+pub(crate) fn legacy_cache_file_stem(key: &str) -> Option<String> {
+    let sanitized_key = sanitize_filename(key);
+    if sanitized_key == key {
+        return None;
+    }
+    Some(if sanitized_key.is_empty() {
+        "cache_entry".to_string()
+    } else {
+        sanitized_key
+    })
+}
+
+/// 64-bit FNV-1a hash, which unlike `DefaultHasher` is stable across Rust
+/// releases, so file names stay valid after upgrading
+#[cfg(any(
+    feature = "filesystem-backend",
+    feature = "s3-backend",
+    feature = "append-log-backend"
+))]
+pub(crate) fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
 pub mod memory;
+pub mod tiered;
+
+#[cfg(feature = "append-log-backend")]
+pub mod append_log;
+
+#[cfg(feature = "dashmap-backend")]
+pub mod dashmap;
 
 #[cfg(feature = "filesystem-backend")]
 pub mod filesystem;
+
+#[cfg(feature = "redis-backend")]
+pub mod redis;
+
+#[cfg(feature = "s3-backend")]
+pub mod s3;
+
+#[cfg(feature = "sqlite-backend")]
+pub mod sqlite;
@@ -0,0 +1,252 @@
+//! Redis storage backend
+
+use ::redis::aio::MultiplexedConnection;
+use ::redis::AsyncCommands;
+use async_trait::async_trait;
+use std::collections::HashMap;
+
+use crate::backends::{StorageKey, StorageMeta, StorageValue};
+use crate::{
+    storage::{EntryMap, SerializationFormat, DEFAULT_FORMAT},
+    CacheEntry, CacheError, Result, StorageBackend,
+};
+
+/// Type alias for complex phantom data type
+type PhantomTypes<K, V, M> = std::marker::PhantomData<(K, V, M)>;
+
+/// Number of keys fetched or deleted per round trip
+const BATCH_SIZE: usize = 512;
+
+/// Redis storage backend
+///
+/// Each cache key is stored as a single Redis string holding the serialized
+/// entry vector. Redis keys are built from a namespace prefix followed by the
+/// serialized cache key, so several caches can share one Redis instance.
+#[allow(clippy::type_complexity)]
+pub struct RedisBackend<K, V, M = ()>
+where
+    K: StorageKey,
+    V: StorageValue,
+    M: StorageMeta,
+{
+    connection: MultiplexedConnection,
+    prefix: String,
+    format: SerializationFormat,
+    _phantom: PhantomTypes<K, V, M>,
+}
+
+impl<K, V, M> RedisBackend<K, V, M>
+where
+    K: StorageKey,
+    V: StorageValue,
+    M: StorageMeta,
+{
+    /// Connect to Redis at `url` and store entries under `prefix`
+    pub async fn new<P: Into<String>>(url: &str, prefix: P) -> Result<Self> {
+        let client = ::redis::Client::open(url)?;
+        let connection = client.get_multiplexed_async_connection().await?;
+
+        Ok(Self {
+            connection,
+            prefix: prefix.into(),
+            format: DEFAULT_FORMAT,
+            _phantom: std::marker::PhantomData,
+        })
+    }
+
+    /// Set the serialization format
+    pub fn with_format(mut self, format: SerializationFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Build the Redis key for a cache key
+    fn redis_key(&self, key: &K) -> Result<Vec<u8>> {
+        Ok(prefixed_key(&self.prefix, &self.format.serialize(key)?))
+    }
+
+    /// Collect every Redis key under this backend's prefix
+    async fn scan_keys(&self) -> Result<Vec<Vec<u8>>> {
+        let mut conn = self.connection.clone();
+        let mut iter = conn
+            .scan_match::<_, Vec<u8>>(scan_pattern(&self.prefix))
+            .await?;
+
+        let mut keys = Vec::new();
+        while let Some(key) = iter.next_item().await {
+            keys.push(key);
+        }
+        Ok(keys)
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn decode_entries(
+        &self,
+        redis_key: &[u8],
+        data: &[u8],
+    ) -> Option<(K, Vec<CacheEntry<K, V, M>>)> {
+        let entry_vec: Vec<CacheEntry<K, V, M>> = match self.format.deserialize(data) {
+            Ok(v) => v,
+            Err(e) => {
+                eprintln!(
+                    "Failed to deserialize redis entry {}: {e}",
+                    String::from_utf8_lossy(redis_key)
+                );
+                return None;
+            }
+        };
+        let key = entry_vec.first()?.key.clone();
+        Some((key, entry_vec))
+    }
+}
+
+#[async_trait]
+impl<K, V, M> StorageBackend for RedisBackend<K, V, M>
+where
+    K: StorageKey,
+    V: StorageValue,
+    M: StorageMeta,
+{
+    type Key = K;
+    type Value = V;
+    type Metadata = M;
+
+    async fn save(&self, entries: &EntryMap<K, V, M>) -> Result<()> {
+        if entries.is_empty() {
+            return Ok(());
+        }
+
+        let mut pipe = ::redis::pipe();
+        for (key, entry_vec) in entries {
+            pipe.set(self.redis_key(key)?, self.format.serialize(entry_vec)?)
+                .ignore();
+        }
+
+        let mut conn = self.connection.clone();
+        pipe.query_async::<()>(&mut conn).await?;
+        Ok(())
+    }
+
+    async fn load(&self) -> Result<EntryMap<K, V, M>> {
+        let mut entries: EntryMap<K, V, M> = HashMap::new();
+        let mut conn = self.connection.clone();
+
+        for chunk in self.scan_keys().await?.chunks(BATCH_SIZE) {
+            let values: Vec<Option<Vec<u8>>> = ::redis::cmd("MGET")
+                .arg(chunk)
+                .query_async(&mut conn)
+                .await?;
+
+            for (redis_key, data) in chunk.iter().zip(values) {
+                // Keys may expire or be deleted between SCAN and MGET
+                let Some(data) = data else { continue };
+                if let Some((key, entry_vec)) = self.decode_entries(redis_key, &data) {
+                    entries.insert(key, entry_vec);
+                }
+            }
+        }
+
+        Ok(entries)
+    }
+
+    async fn load_key(&self, key: &K) -> Result<Option<Vec<CacheEntry<K, V, M>>>> {
+        let redis_key = self.redis_key(key)?;
+        let mut conn = self.connection.clone();
+        let data: Option<Vec<u8>> = conn
+            .get(&redis_key)
+            .await
+            .map_err(|e| CacheError::from(e).with_key(String::from_utf8_lossy(&redis_key)))?;
+        Ok(data
+            .and_then(|data| self.decode_entries(&redis_key, &data))
+            .map(|(_, entry_vec)| entry_vec))
+    }
+
+    async fn remove(&self, key: &K) -> Result<()> {
+        let redis_key = self.redis_key(key)?;
+        let mut conn = self.connection.clone();
+        conn.del::<_, ()>(&redis_key)
+            .await
+            .map_err(|e| CacheError::from(e).with_key(String::from_utf8_lossy(&redis_key)))
+    }
+
+    async fn clear(&self) -> Result<()> {
+        let mut conn = self.connection.clone();
+        for chunk in self.scan_keys().await?.chunks(BATCH_SIZE) {
+            conn.del::<_, ()>(chunk).await?;
+        }
+        Ok(())
+    }
+
+    async fn contains(&self, key: &K) -> Result<bool> {
+        let redis_key = self.redis_key(key)?;
+        let mut conn = self.connection.clone();
+        conn.exists(&redis_key)
+            .await
+            .map_err(|e| CacheError::from(e).with_key(String::from_utf8_lossy(&redis_key)))
+    }
+
+    async fn size_bytes(&self) -> Result<u64> {
+        let mut conn = self.connection.clone();
+        let mut total_size = 0u64;
+
+        for chunk in self.scan_keys().await?.chunks(BATCH_SIZE) {
+            let mut pipe = ::redis::pipe();
+            for redis_key in chunk {
+                pipe.strlen(redis_key);
+            }
+            let sizes: Vec<u64> = pipe.query_async(&mut conn).await?;
+            total_size += sizes.iter().sum::<u64>();
+        }
+
+        Ok(total_size)
+    }
+
+    /// Put the namespace in front of the prefix, so a namespaced backend's
+    /// keys never fall under an unnamespaced backend's scan pattern
+    async fn with_namespace(mut self, namespace: &str) -> Result<Self> {
+        self.prefix = format!("{namespace}/{}", self.prefix);
+        Ok(self)
+    }
+}
+
+/// Join the namespace prefix and serialized key into a Redis key
+fn prefixed_key(prefix: &str, serialized_key: &[u8]) -> Vec<u8> {
+    let mut redis_key = Vec::with_capacity(prefix.len() + 1 + serialized_key.len());
+    redis_key.extend_from_slice(prefix.as_bytes());
+    redis_key.push(b':');
+    redis_key.extend_from_slice(serialized_key);
+    redis_key
+}
+
+/// Build a SCAN pattern matching every key under `prefix`
+///
+/// Glob metacharacters in the prefix are escaped so they match literally.
+fn scan_pattern(prefix: &str) -> String {
+    let mut pattern = String::with_capacity(prefix.len() + 2);
+    for c in prefix.chars() {
+        if matches!(c, '*' | '?' | '[' | ']' | '\\') {
+            pattern.push('\\');
+        }
+        pattern.push(c);
+    }
+    pattern.push_str(":*");
+    pattern
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prefixed_key() {
+        assert_eq!(prefixed_key("cache", b"\"k\""), b"cache:\"k\"".to_vec());
+        assert_eq!(prefixed_key("", b"k"), b":k".to_vec());
+    }
+
+    #[test]
+    fn test_scan_pattern_escapes_globs() {
+        assert_eq!(scan_pattern("tenant-a"), "tenant-a:*");
+        assert_eq!(scan_pattern("a*b?[c]"), "a\\*b\\?\\[c\\]:*");
+        assert_eq!(scan_pattern("back\\slash"), "back\\\\slash:*");
+    }
+}
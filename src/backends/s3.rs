@@ -0,0 +1,531 @@
+//! S3-compatible object store backend
+
+use async_trait::async_trait;
+use aws_sdk_s3::primitives::ByteStream;
+use aws_sdk_s3::types::{Delete, Object, ObjectIdentifier};
+use aws_sdk_s3::Client;
+use std::collections::{HashMap, HashSet};
+
+use crate::backends::{
+    cache_file_stem, legacy_cache_file_stem, namespace_segment, StorageKey, StorageMeta,
+    StorageValue,
+};
+use crate::{
+    storage::{EntryMap, SerializationFormat, DEFAULT_FORMAT},
+    CacheEntry, CacheError, Result, StorageBackend,
+};
+
+/// Type alias for complex phantom data type
+type PhantomTypes<K, V, M> = std::marker::PhantomData<(K, V, M)>;
+
+/// Most keys a single DeleteObjects request accepts
+const DELETE_BATCH_SIZE: usize = 1000;
+
+/// S3-compatible object store backend
+///
+/// Each cache key is stored as one object named after the key's sanitized
+/// `Display` string, the same way the filesystem backend names its files.
+/// Objects live directly under the prefix; deeper objects (such as those of
+/// a namespaced backend) are ignored.
+///
+/// The backend takes a ready [`Client`], so credentials, region, endpoint and
+/// HTTP client are configured by the caller, typically with `aws-config`.
+#[allow(clippy::type_complexity)]
+pub struct S3Backend<K, V, M = ()>
+where
+    K: StorageKey + std::fmt::Display,
+    V: StorageValue,
+    M: StorageMeta,
+{
+    client: Client,
+    bucket: String,
+    prefix: String,
+    format: SerializationFormat,
+    _phantom: PhantomTypes<K, V, M>,
+}
+
+impl<K, V, M> S3Backend<K, V, M>
+where
+    K: StorageKey + std::fmt::Display,
+    V: StorageValue,
+    M: StorageMeta,
+{
+    /// Store objects in `bucket` under `prefix`
+    ///
+    /// Leading and trailing slashes in the prefix are ignored; an empty
+    /// prefix stores objects at the bucket root.
+    pub fn new<B: Into<String>, P: AsRef<str>>(client: Client, bucket: B, prefix: P) -> Self {
+        Self {
+            client,
+            bucket: bucket.into(),
+            prefix: prefix.as_ref().trim_matches('/').to_string(),
+            format: DEFAULT_FORMAT,
+            _phantom: std::marker::PhantomData,
+        }
+    }
+
+    /// Set the serialization format
+    pub fn with_format(mut self, format: SerializationFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Get the string every object key of this backend starts with
+    fn list_prefix(&self) -> String {
+        if self.prefix.is_empty() {
+            String::new()
+        } else {
+            format!("{}/", self.prefix)
+        }
+    }
+
+    /// Get the object key for a cache key
+    fn object_key(&self, key: &K) -> String {
+        format!(
+            "{}{}.{}",
+            self.list_prefix(),
+            cache_file_stem(&key.to_string()),
+            self.format.extension()
+        )
+    }
+
+    /// Get the object key an older version would have used for a cache key,
+    /// if it differs from the current one
+    fn legacy_object_key(&self, key: &K) -> Option<String> {
+        legacy_cache_file_stem(&key.to_string())
+            .map(|stem| format!("{}{stem}.{}", self.list_prefix(), self.format.extension()))
+    }
+
+    /// Fetch the legacy object of `key` if it holds that key's entries rather
+    /// than those of a key it collided with
+    #[allow(clippy::type_complexity)]
+    async fn owned_legacy_object(
+        &self,
+        key: &K,
+    ) -> Result<Option<(String, Vec<CacheEntry<K, V, M>>)>> {
+        let Some(object_key) = self.legacy_object_key(key) else {
+            return Ok(None);
+        };
+        Ok(match self.load_object(&object_key).await? {
+            Some((stored_key, entry_vec)) if stored_key == *key => Some((object_key, entry_vec)),
+            _ => None,
+        })
+    }
+
+    /// Delete one object
+    async fn delete_object(&self, object_key: &str) -> Result<()> {
+        self.client
+            .delete_object()
+            .bucket(&self.bucket)
+            .key(object_key)
+            .send()
+            .await
+            .map_err(|e| CacheError::from(e).with_key(object_key))?;
+        Ok(())
+    }
+
+    /// Whether an object key names one of this backend's cache objects
+    fn is_cache_object_key(&self, object_key: &str) -> bool {
+        let Some(name) = object_key.strip_prefix(&self.list_prefix()) else {
+            return false;
+        };
+        !name.contains('/')
+            && name
+                .strip_suffix(self.format.extension())
+                .is_some_and(|stem| stem.ends_with('.') && stem.len() > 1)
+    }
+
+    /// List this backend's cache objects
+    async fn list_objects(&self) -> Result<Vec<Object>> {
+        let mut pages = self
+            .client
+            .list_objects_v2()
+            .bucket(&self.bucket)
+            .prefix(self.list_prefix())
+            .into_paginator()
+            .send();
+
+        let mut objects = Vec::new();
+        while let Some(page) = pages.next().await {
+            objects.extend(
+                page?
+                    .contents()
+                    .iter()
+                    .filter(|object| object.key().is_some_and(|k| self.is_cache_object_key(k)))
+                    .cloned(),
+            );
+        }
+        Ok(objects)
+    }
+
+    /// Fetch and decode one object, skipping it if it is unreadable
+    #[allow(clippy::type_complexity)]
+    async fn load_object(&self, object_key: &str) -> Result<Option<(K, Vec<CacheEntry<K, V, M>>)>> {
+        let response = self
+            .client
+            .get_object()
+            .bucket(&self.bucket)
+            .key(object_key)
+            .send()
+            .await;
+        let data = match response {
+            Ok(output) => output.body.collect().await?.into_bytes(),
+            // The object may be deleted between listing and fetching
+            Err(e) if e.as_service_error().is_some_and(|e| e.is_no_such_key()) => return Ok(None),
+            Err(e) => return Err(CacheError::from(e).with_key(object_key)),
+        };
+
+        let entry_vec: Vec<CacheEntry<K, V, M>> = match self.format.deserialize(&data) {
+            Ok(v) => v,
+            Err(e) => {
+                eprintln!("Failed to deserialize S3 object {object_key}: {e}");
+                return Ok(None);
+            }
+        };
+        Ok(entry_vec
+            .first()
+            .map(|first| first.key.clone())
+            .map(|key| (key, entry_vec)))
+    }
+}
+
+#[async_trait]
+impl<K, V, M> StorageBackend for S3Backend<K, V, M>
+where
+    K: StorageKey + std::fmt::Display,
+    V: StorageValue,
+    M: StorageMeta,
+{
+    type Key = K;
+    type Value = V;
+    type Metadata = M;
+
+    async fn save(&self, entries: &EntryMap<K, V, M>) -> Result<()> {
+        for (key, entry_vec) in entries {
+            self.client
+                .put_object()
+                .bucket(&self.bucket)
+                .key(self.object_key(key))
+                .body(ByteStream::from(self.format.serialize(entry_vec)?))
+                .send()
+                .await?;
+            // Retire the key's legacy object, which would otherwise keep
+            // its old entries around
+            if let Some((object_key, _)) = self.owned_legacy_object(key).await? {
+                self.delete_object(&object_key).await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn load(&self) -> Result<EntryMap<K, V, M>> {
+        let objects = self.list_objects().await?;
+        let listed: HashSet<&str> = objects.iter().filter_map(|object| object.key()).collect();
+        let mut entries: EntryMap<K, V, M> = HashMap::new();
+        for object_key in &listed {
+            if let Some((key, entry_vec)) = self.load_object(object_key).await? {
+                // A legacy object never wins over the key's current one
+                let current = self.object_key(&key);
+                if *object_key != current && listed.contains(current.as_str()) {
+                    continue;
+                }
+                entries.insert(key, entry_vec);
+            }
+        }
+        Ok(entries)
+    }
+
+    async fn load_key(&self, key: &K) -> Result<Option<Vec<CacheEntry<K, V, M>>>> {
+        if let Some((_, entry_vec)) = self.load_object(&self.object_key(key)).await? {
+            return Ok(Some(entry_vec));
+        }
+        Ok(self
+            .owned_legacy_object(key)
+            .await?
+            .map(|(_, entry_vec)| entry_vec))
+    }
+
+    async fn remove(&self, key: &K) -> Result<()> {
+        let legacy = self
+            .owned_legacy_object(key)
+            .await?
+            .map(|(object_key, _)| object_key);
+        for object_key in std::iter::once(self.object_key(key)).chain(legacy) {
+            self.delete_object(&object_key).await?;
+        }
+        Ok(())
+    }
+
+    async fn clear(&self) -> Result<()> {
+        let objects = self.list_objects().await?;
+        for chunk in objects.chunks(DELETE_BATCH_SIZE) {
+            let identifiers = chunk
+                .iter()
+                .filter_map(|object| object.key())
+                .map(|key| ObjectIdentifier::builder().key(key).build())
+                .collect::<std::result::Result<Vec<_>, _>>()?;
+            let delete = Delete::builder()
+                .set_objects(Some(identifiers))
+                .quiet(true)
+                .build()?;
+
+            let output = self
+                .client
+                .delete_objects()
+                .bucket(&self.bucket)
+                .delete(delete)
+                .send()
+                .await?;
+            if let Some(error) = output.errors().first() {
+                return Err(CacheError::StorageBackend(format!(
+                    "failed to delete S3 object {}: {}",
+                    error.key().unwrap_or_default(),
+                    error.message().unwrap_or_default()
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    async fn contains(&self, key: &K) -> Result<bool> {
+        let object_key = self.object_key(key);
+        let response = self
+            .client
+            .head_object()
+            .bucket(&self.bucket)
+            .key(&object_key)
+            .send()
+            .await;
+        match response {
+            Ok(_) => Ok(true),
+            Err(e) if e.as_service_error().is_some_and(|e| e.is_not_found()) => {
+                Ok(self.owned_legacy_object(key).await?.is_some())
+            }
+            Err(e) => Err(CacheError::from(e).with_key(&object_key)),
+        }
+    }
+
+    async fn size_bytes(&self) -> Result<u64> {
+        Ok(self
+            .list_objects()
+            .await?
+            .iter()
+            .filter_map(|object| object.size())
+            .map(|size| size.max(0) as u64)
+            .sum())
+    }
+
+    /// Nest the backend's objects under a namespace segment of the prefix
+    ///
+    /// A namespace that is not a safe segment as is gets a sanitized name
+    /// with a hash suffix, like keys do.
+    async fn with_namespace(mut self, namespace: &str) -> Result<Self> {
+        let Some(segment) = namespace_segment(namespace) else {
+            return Err(CacheError::InvalidConfiguration(format!(
+                "namespace {namespace:?} is not a valid object key segment"
+            )));
+        };
+        self.prefix = format!("{}{segment}", self.list_prefix());
+        Ok(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aws_sdk_s3::config::{
+        BehaviorVersion, Credentials, HttpClient, Region, RequestChecksumCalculation,
+        RuntimeComponents, SharedHttpClient,
+    };
+    use aws_sdk_s3::primitives::SdkBody;
+    use aws_smithy_runtime_api::client::http::{
+        HttpConnector, HttpConnectorFuture, HttpConnectorSettings, SharedHttpConnector,
+    };
+    use aws_smithy_runtime_api::client::orchestrator::{HttpRequest, HttpResponse};
+    use aws_smithy_runtime_api::shared::IntoShared;
+    use std::collections::BTreeMap;
+    use std::sync::{Arc, Mutex};
+
+    /// In-memory stand-in for the bucket, answering the requests the backend
+    /// makes
+    #[derive(Clone, Debug, Default)]
+    struct FakeBucket {
+        objects: Arc<Mutex<BTreeMap<String, Vec<u8>>>>,
+    }
+
+    impl FakeBucket {
+        fn respond(&self, request: HttpRequest) -> HttpResponse {
+            let uri = request.uri().to_string();
+            let (path, query) = uri.split_once('?').unwrap_or((&uri, ""));
+            let path = path.strip_prefix("http://s3.test/bucket").unwrap();
+            let key = path.trim_start_matches('/').to_string();
+            let mut objects = self.objects.lock().unwrap();
+            let (status, body) = match request.method() {
+                "GET" if key.is_empty() => {
+                    let prefix = query
+                        .split('&')
+                        .find_map(|pair| pair.strip_prefix("prefix="))
+                        .unwrap_or_default()
+                        .replace("%2F", "/");
+                    let contents: String = objects
+                        .iter()
+                        .filter(|(key, _)| key.starts_with(&prefix))
+                        .map(|(key, data)| {
+                            format!(
+                                "<Contents><Key>{key}</Key><Size>{}</Size></Contents>",
+                                data.len()
+                            )
+                        })
+                        .collect();
+                    let body = format!(
+                        "<ListBucketResult><Name>bucket</Name><IsTruncated>false</IsTruncated>\
+                         {contents}</ListBucketResult>"
+                    );
+                    (200, body.into_bytes())
+                }
+                "GET" | "HEAD" => match objects.get(&key) {
+                    Some(data) if request.method() == "GET" => (200, data.clone()),
+                    Some(_) => (200, Vec::new()),
+                    None if request.method() == "GET" => {
+                        (404, b"<Error><Code>NoSuchKey</Code></Error>".to_vec())
+                    }
+                    None => (404, Vec::new()),
+                },
+                "PUT" => {
+                    let data = request.body().bytes().unwrap().to_vec();
+                    objects.insert(key, data);
+                    (200, Vec::new())
+                }
+                "DELETE" => {
+                    objects.remove(&key);
+                    (204, Vec::new())
+                }
+                method => panic!("unexpected {method} {uri}"),
+            };
+            HttpResponse::new(status.try_into().unwrap(), SdkBody::from(body))
+        }
+    }
+
+    impl HttpConnector for FakeBucket {
+        fn call(&self, request: HttpRequest) -> HttpConnectorFuture {
+            HttpConnectorFuture::ready(Ok(self.respond(request)))
+        }
+    }
+
+    impl HttpClient for FakeBucket {
+        fn http_connector(
+            &self,
+            _: &HttpConnectorSettings,
+            _: &RuntimeComponents,
+        ) -> SharedHttpConnector {
+            self.clone().into_shared()
+        }
+    }
+
+    fn fake_backend(bucket: &FakeBucket) -> S3Backend<String, String> {
+        let http_client: SharedHttpClient = bucket.clone().into_shared();
+        let config = aws_sdk_s3::Config::builder()
+            .behavior_version(BehaviorVersion::latest())
+            .region(Region::new("us-east-1"))
+            .credentials_provider(Credentials::new("id", "secret", None, None, "test"))
+            .endpoint_url("http://s3.test")
+            .force_path_style(true)
+            .request_checksum_calculation(RequestChecksumCalculation::WhenRequired)
+            .http_client(http_client)
+            .build();
+        S3Backend::new(Client::from_conf(config), "bucket", "cache")
+    }
+
+    fn new_backend(prefix: &str) -> S3Backend<String, String> {
+        let config = aws_sdk_s3::Config::builder()
+            .behavior_version(BehaviorVersion::latest())
+            .region(Region::new("us-east-1"))
+            .build();
+        S3Backend::new(Client::from_conf(config), "bucket", prefix)
+    }
+
+    #[test]
+    fn test_object_keys_are_sanitized() {
+        let backend = new_backend("/cache/");
+        let traversal = backend.object_key(&"../etc/passwd".to_string());
+        assert!(traversal.starts_with("cache/_._etc_passwd-"));
+        assert!(traversal.ends_with(".json"));
+        assert!(backend
+            .object_key(&"".to_string())
+            .starts_with("cache/cache_entry-"));
+        assert_ne!(
+            backend.object_key(&"a/b".to_string()),
+            backend.object_key(&"a_b".to_string())
+        );
+        assert_eq!(new_backend("").object_key(&"key".to_string()), "key.json");
+    }
+
+    #[test]
+    fn test_only_direct_children_are_cache_objects() {
+        let backend = new_backend("cache");
+        assert!(backend.is_cache_object_key("cache/key.json"));
+        assert!(!backend.is_cache_object_key("cache/tenant/key.json"));
+        assert!(!backend.is_cache_object_key("cache/key.bin"));
+        assert!(!backend.is_cache_object_key("cache/.json"));
+        assert!(!backend.is_cache_object_key("cached/key.json"));
+    }
+
+    #[tokio::test]
+    async fn test_saving_over_a_legacy_object_retires_it() {
+        let bucket = FakeBucket::default();
+        let backend = fake_backend(&bucket);
+        let key = "a/b".to_string();
+        let legacy_key = backend.legacy_object_key(&key).unwrap();
+        let legacy: Vec<CacheEntry<String, String>> =
+            vec![CacheEntry::new(key.clone(), "old".to_string())];
+        bucket.objects.lock().unwrap().insert(
+            legacy_key.clone(),
+            backend.format.serialize(&legacy).unwrap(),
+        );
+        assert!(backend.contains(&key).await.unwrap());
+        assert_eq!(backend.load().await.unwrap()[&key][0].value, "old");
+
+        let mut entries = HashMap::new();
+        entries.insert(
+            key.clone(),
+            vec![CacheEntry::new(key.clone(), "new".to_string())],
+        );
+        backend.save(&entries).await.unwrap();
+        assert!(!bucket.objects.lock().unwrap().contains_key(&legacy_key));
+        assert_eq!(backend.load().await.unwrap()[&key][0].value, "new");
+
+        // A legacy object left next to the current one is ignored
+        bucket
+            .objects
+            .lock()
+            .unwrap()
+            .insert(legacy_key, backend.format.serialize(&legacy).unwrap());
+        assert_eq!(backend.load().await.unwrap()[&key][0].value, "new");
+        assert_eq!(
+            backend.load_key(&key).await.unwrap().unwrap()[0].value,
+            "new"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_namespace_nests_prefix() {
+        let tenant = new_backend("cache")
+            .with_namespace("tenant/a")
+            .await
+            .unwrap();
+        assert_eq!(
+            tenant.object_key(&"key".to_string()),
+            format!("cache/{}/key.json", namespace_segment("tenant/a").unwrap())
+        );
+        let plain = new_backend("cache")
+            .with_namespace("tenant_a")
+            .await
+            .unwrap();
+        assert_eq!(
+            plain.object_key(&"key".to_string()),
+            "cache/tenant_a/key.json"
+        );
+        assert_ne!(tenant.prefix, plain.prefix);
+        assert!(new_backend("cache").with_namespace(" ").await.is_err());
+    }
+}
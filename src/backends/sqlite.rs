@@ -0,0 +1,339 @@
+//! SQLite storage backend
+
+use async_trait::async_trait;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use crate::backends::{StorageKey, StorageMeta, StorageValue};
+use crate::{
+    storage::{EntryMap, SerializationFormat, DEFAULT_FORMAT},
+    CacheEntry, CacheError, Result, StorageBackend,
+};
+
+/// Type alias for complex phantom data type
+type PhantomTypes<K, V, M> = std::marker::PhantomData<(K, V, M)>;
+
+/// Table holding the rows of a backend without a namespace
+const DEFAULT_TABLE: &str = "cache_entries";
+
+/// SQLite storage backend
+///
+/// Each cache key is stored as one row holding the serialized entry vector,
+/// keyed by the key's `Display` string. All statements run on tokio's blocking
+/// thread pool so the async runtime is never stalled on disk I/O. A
+/// namespaced backend keeps its rows in a table of its own, so caches
+/// sharing a database file never see each other's keys.
+#[allow(clippy::type_complexity)]
+pub struct SqliteBackend<K, V, M = ()>
+where
+    K: StorageKey + std::fmt::Display,
+    V: StorageValue,
+    M: StorageMeta,
+{
+    conn: Arc<Mutex<Connection>>,
+    /// Quoted name of the table holding this backend's rows
+    table: String,
+    format: SerializationFormat,
+    _phantom: PhantomTypes<K, V, M>,
+}
+
+impl<K, V, M> SqliteBackend<K, V, M>
+where
+    K: StorageKey + std::fmt::Display,
+    V: StorageValue,
+    M: StorageMeta,
+{
+    /// Open (or create) a SQLite database at the given path
+    pub async fn new<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let conn = tokio::task::spawn_blocking(move || Connection::open(path))
+            .await
+            .map_err(|e| CacheError::StorageBackend(e.to_string()))??;
+        Self::from_connection(conn).await
+    }
+
+    /// Create a backend backed by a private in-memory database
+    pub async fn in_memory() -> Result<Self> {
+        Self::from_connection(Connection::open_in_memory()?).await
+    }
+
+    async fn from_connection(conn: Connection) -> Result<Self> {
+        let backend = Self {
+            conn: Arc::new(Mutex::new(conn)),
+            table: quote_identifier(DEFAULT_TABLE),
+            format: DEFAULT_FORMAT,
+            _phantom: std::marker::PhantomData,
+        };
+        backend.create_table().await?;
+        Ok(backend)
+    }
+
+    /// Create the backend's table if it does not exist yet
+    async fn create_table(&self) -> Result<()> {
+        let sql = format!(
+            "CREATE TABLE IF NOT EXISTS {} (key TEXT PRIMARY KEY, data BLOB NOT NULL)",
+            self.table
+        );
+        self.with_conn(move |conn| conn.execute(&sql, []).map(|_| ()))
+            .await
+    }
+
+    /// Set the serialization format
+    pub fn with_format(mut self, format: SerializationFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Run a blocking closure against the connection
+    async fn with_conn<F, T>(&self, f: F) -> Result<T>
+    where
+        F: FnOnce(&mut Connection) -> rusqlite::Result<T> + Send + 'static,
+        T: Send + 'static,
+    {
+        let conn = Arc::clone(&self.conn);
+        tokio::task::spawn_blocking(move || {
+            let mut conn = conn
+                .lock()
+                .map_err(|e| CacheError::StorageBackend(e.to_string()))?;
+            f(&mut conn).map_err(CacheError::from)
+        })
+        .await
+        .map_err(|e| CacheError::StorageBackend(e.to_string()))?
+    }
+}
+
+#[async_trait]
+impl<K, V, M> StorageBackend for SqliteBackend<K, V, M>
+where
+    K: StorageKey + std::fmt::Display,
+    V: StorageValue,
+    M: StorageMeta,
+{
+    type Key = K;
+    type Value = V;
+    type Metadata = M;
+
+    async fn save(&self, entries: &EntryMap<K, V, M>) -> Result<()> {
+        let rows = entries
+            .iter()
+            .map(|(key, entry_vec)| Ok((key.to_string(), self.format.serialize(entry_vec)?)))
+            .collect::<Result<Vec<_>>>()?;
+
+        let sql = format!(
+            "INSERT OR REPLACE INTO {} (key, data) VALUES (?1, ?2)",
+            self.table
+        );
+        self.with_conn(move |conn| {
+            let tx = conn.transaction()?;
+            {
+                let mut stmt = tx.prepare(&sql)?;
+                for (key, data) in &rows {
+                    stmt.execute(params![key, data])?;
+                }
+            }
+            tx.commit()
+        })
+        .await
+    }
+
+    async fn load(&self) -> Result<EntryMap<K, V, M>> {
+        let sql = format!("SELECT key, data FROM {}", self.table);
+        let rows = self
+            .with_conn(move |conn| {
+                let mut stmt = conn.prepare(&sql)?;
+                let rows = stmt
+                    .query_map([], |row| {
+                        Ok((row.get::<_, String>(0)?, row.get::<_, Vec<u8>>(1)?))
+                    })?
+                    .collect::<rusqlite::Result<Vec<_>>>()?;
+                Ok(rows)
+            })
+            .await?;
+
+        let mut entries: EntryMap<K, V, M> = HashMap::new();
+        for (row_key, data) in rows {
+            let entry_vec: Vec<CacheEntry<K, V, M>> = match self.format.deserialize(&data) {
+                Ok(v) => v,
+                Err(e) => {
+                    eprintln!("Failed to deserialize sqlite row {row_key:?}: {e}");
+                    continue;
+                }
+            };
+            if let Some(first) = entry_vec.first() {
+                entries.insert(first.key.clone(), entry_vec);
+            }
+        }
+        Ok(entries)
+    }
+
+    async fn load_key(&self, key: &K) -> Result<Option<Vec<CacheEntry<K, V, M>>>> {
+        let row_key = key.to_string();
+        let sql = format!("SELECT data FROM {} WHERE key = ?1", self.table);
+        let data = self
+            .with_conn({
+                let row_key = row_key.clone();
+                move |conn| {
+                    conn.query_row(&sql, params![row_key], |row| row.get::<_, Vec<u8>>(0))
+                        .optional()
+                }
+            })
+            .await
+            .map_err(|e| e.with_key(&row_key))?;
+
+        let Some(data) = data else {
+            return Ok(None);
+        };
+        match self.format.deserialize(&data) {
+            Ok(entry_vec) => Ok(Some(entry_vec)),
+            Err(e) => {
+                eprintln!("Failed to deserialize sqlite row {row_key:?}: {e}");
+                Ok(None)
+            }
+        }
+    }
+
+    async fn remove(&self, key: &K) -> Result<()> {
+        let key = key.to_string();
+        let sql = format!("DELETE FROM {} WHERE key = ?1", self.table);
+        self.with_conn({
+            let key = key.clone();
+            move |conn| conn.execute(&sql, params![key]).map(|_| ())
+        })
+        .await
+        .map_err(|e| e.with_key(&key))
+    }
+
+    async fn clear(&self) -> Result<()> {
+        let sql = format!("DELETE FROM {}", self.table);
+        self.with_conn(move |conn| conn.execute(&sql, []).map(|_| ()))
+            .await
+    }
+
+    async fn contains(&self, key: &K) -> Result<bool> {
+        let key = key.to_string();
+        let sql = format!("SELECT 1 FROM {} WHERE key = ?1", self.table);
+        self.with_conn(move |conn| {
+            conn.query_row(&sql, params![key], |_| Ok(()))
+                .optional()
+                .map(|row| row.is_some())
+        })
+        .await
+    }
+
+    async fn size_bytes(&self) -> Result<u64> {
+        self.with_conn(|conn| {
+            let page_count: u64 = conn.query_row("PRAGMA page_count", [], |row| row.get(0))?;
+            let page_size: u64 = conn.query_row("PRAGMA page_size", [], |row| row.get(0))?;
+            Ok(page_count * page_size)
+        })
+        .await
+    }
+
+    async fn compact(&self) -> Result<()> {
+        self.with_conn(|conn| conn.execute_batch("VACUUM")).await
+    }
+
+    /// Move the backend to a table of its own named after the namespace
+    async fn with_namespace(mut self, namespace: &str) -> Result<Self> {
+        self.table = quote_identifier(&format!("{DEFAULT_TABLE}/{namespace}"));
+        self.create_table().await?;
+        Ok(self)
+    }
+}
+
+/// Quote `name` as an SQL identifier, so any namespace makes a valid table
+/// name
+fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_sqlite_backend_persistence() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("cache.db");
+
+        {
+            let backend: SqliteBackend<String, String> = SqliteBackend::new(&path).await.unwrap();
+            let mut entries = HashMap::new();
+            entries.insert(
+                "key".to_string(),
+                vec![CacheEntry::new("key".to_string(), "value".to_string())],
+            );
+            backend.save(&entries).await.unwrap();
+        }
+
+        let backend: SqliteBackend<String, String> = SqliteBackend::new(&path).await.unwrap();
+        let loaded = backend.load().await.unwrap();
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded["key"][0].value, "value");
+        assert!(backend.size_bytes().await.unwrap() > 0);
+    }
+
+    #[tokio::test]
+    async fn test_load_skips_corrupt_rows() {
+        let backend: SqliteBackend<String, String> = SqliteBackend::in_memory().await.unwrap();
+        backend
+            .with_conn(|conn| {
+                conn.execute(
+                    "INSERT INTO cache_entries (key, data) VALUES ('bad', x'00ff')",
+                    [],
+                )
+                .map(|_| ())
+            })
+            .await
+            .unwrap();
+
+        let mut entries = HashMap::new();
+        entries.insert(
+            "good".to_string(),
+            vec![CacheEntry::new("good".to_string(), "value".to_string())],
+        );
+        backend.save(&entries).await.unwrap();
+
+        let loaded = backend.load().await.unwrap();
+        assert_eq!(loaded.len(), 1);
+        assert!(loaded.contains_key("good"));
+    }
+
+    #[tokio::test]
+    async fn test_namespaces_share_a_database_without_seeing_each_other() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("cache.db");
+        let open = |namespace: &'static str| {
+            let path = path.clone();
+            async move {
+                SqliteBackend::<String, String>::new(&path)
+                    .await
+                    .unwrap()
+                    .with_namespace(namespace)
+                    .await
+                    .unwrap()
+            }
+        };
+        let tenant_a = open("tenant \"a\"").await;
+        let tenant_b = open("tenant-b").await;
+
+        for (backend, value) in [(&tenant_a, "a"), (&tenant_b, "b")] {
+            let mut entries = HashMap::new();
+            entries.insert(
+                "key".to_string(),
+                vec![CacheEntry::new("key".to_string(), value.to_string())],
+            );
+            backend.save(&entries).await.unwrap();
+        }
+        assert_eq!(tenant_a.load().await.unwrap()["key"][0].value, "a");
+
+        tenant_b.clear().await.unwrap();
+        assert!(tenant_a.contains(&"key".to_string()).await.unwrap());
+        assert!(!tenant_b.contains(&"key".to_string()).await.unwrap());
+        let unscoped = SqliteBackend::<String, String>::new(&path).await.unwrap();
+        assert!(unscoped.load().await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,177 @@
+//! Tiered storage backend
+
+use async_trait::async_trait;
+
+use crate::{storage::EntryMap, CacheEntry, Result, StorageBackend};
+
+/// Storage backend layering a fast backend over a slow one
+///
+/// The slow backend is the source of truth. Saves write through to both
+/// tiers, loads read from the slow tier and warm the fast tier with the
+/// result, and lookups try the fast tier before falling back to the slow one.
+pub struct TieredBackend<F, S> {
+    fast: F,
+    slow: S,
+}
+
+impl<F, S> TieredBackend<F, S>
+where
+    F: StorageBackend,
+    S: StorageBackend<Key = F::Key, Value = F::Value, Metadata = F::Metadata>,
+{
+    /// Layer `fast` over `slow`
+    pub fn new(fast: F, slow: S) -> Self {
+        Self { fast, slow }
+    }
+
+    /// Get the fast tier
+    pub fn fast(&self) -> &F {
+        &self.fast
+    }
+
+    /// Get the slow tier
+    pub fn slow(&self) -> &S {
+        &self.slow
+    }
+}
+
+#[async_trait]
+impl<F, S> StorageBackend for TieredBackend<F, S>
+where
+    F: StorageBackend,
+    S: StorageBackend<Key = F::Key, Value = F::Value, Metadata = F::Metadata>,
+{
+    type Key = F::Key;
+    type Value = F::Value;
+    type Metadata = F::Metadata;
+
+    async fn save(&self, entries: &EntryMap<Self::Key, Self::Value, Self::Metadata>) -> Result<()> {
+        // Write the durable tier first so the fast tier never holds data the
+        // slow tier is missing
+        self.slow.save(entries).await?;
+        self.fast.save(entries).await
+    }
+
+    async fn save_keys(
+        &self,
+        entries: &EntryMap<Self::Key, Self::Value, Self::Metadata>,
+        keys: &[Self::Key],
+    ) -> Result<()> {
+        self.slow.save_keys(entries, keys).await?;
+        self.fast.save_keys(entries, keys).await
+    }
+
+    fn supports_partial_save(&self) -> bool {
+        self.fast.supports_partial_save() && self.slow.supports_partial_save()
+    }
+
+    async fn load(&self) -> Result<EntryMap<Self::Key, Self::Value, Self::Metadata>> {
+        let entries = self.slow.load().await?;
+        self.fast.save(&entries).await?;
+        Ok(entries)
+    }
+
+    async fn load_key(
+        &self,
+        key: &Self::Key,
+    ) -> Result<Option<Vec<CacheEntry<Self::Key, Self::Value, Self::Metadata>>>> {
+        if let Some(entry_vec) = self.fast.load_key(key).await? {
+            return Ok(Some(entry_vec));
+        }
+        self.slow.load_key(key).await
+    }
+
+    async fn remove(&self, key: &Self::Key) -> Result<()> {
+        self.fast.remove(key).await?;
+        self.slow.remove(key).await
+    }
+
+    async fn clear(&self) -> Result<()> {
+        self.fast.clear().await?;
+        self.slow.clear().await
+    }
+
+    async fn contains(&self, key: &Self::Key) -> Result<bool> {
+        if self.fast.contains(key).await? {
+            return Ok(true);
+        }
+        self.slow.contains(key).await
+    }
+
+    async fn size_bytes(&self) -> Result<u64> {
+        self.slow.size_bytes().await
+    }
+
+    async fn compact(&self) -> Result<()> {
+        self.fast.compact().await?;
+        self.slow.compact().await
+    }
+
+    async fn with_namespace(self, namespace: &str) -> Result<Self> {
+        Ok(Self {
+            fast: self.fast.with_namespace(namespace).await?,
+            slow: self.slow.with_namespace(namespace).await?,
+        })
+    }
+
+    #[cfg(feature = "compression")]
+    async fn with_value_compression(
+        self,
+        compression: crate::config::ValueCompression,
+    ) -> Result<Self> {
+        Ok(Self {
+            fast: self.fast.with_value_compression(compression).await?,
+            slow: self.slow.with_value_compression(compression).await?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CacheEntry, MemoryBackend};
+    use std::collections::HashMap;
+
+    type Tiered = TieredBackend<MemoryBackend<String, String>, MemoryBackend<String, String>>;
+
+    fn single_entry(key: &str) -> EntryMap<String, String, ()> {
+        let mut entries = HashMap::new();
+        entries.insert(
+            key.to_string(),
+            vec![CacheEntry::new(key.to_string(), "value".to_string())],
+        );
+        entries
+    }
+
+    #[tokio::test]
+    async fn test_save_writes_both_tiers() {
+        let backend: Tiered = TieredBackend::new(MemoryBackend::new(), MemoryBackend::new());
+        backend.save(&single_entry("key")).await.unwrap();
+
+        assert!(backend.fast().contains(&"key".to_string()).await.unwrap());
+        assert!(backend.slow().contains(&"key".to_string()).await.unwrap());
+
+        backend.remove(&"key".to_string()).await.unwrap();
+        assert!(!backend.fast().contains(&"key".to_string()).await.unwrap());
+        assert!(!backend.slow().contains(&"key".to_string()).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_load_warms_fast_tier() {
+        let slow: MemoryBackend<String, String> = MemoryBackend::new();
+        slow.save(&single_entry("key")).await.unwrap();
+
+        let backend = TieredBackend::new(MemoryBackend::new(), slow);
+        assert!(!backend.fast().contains(&"key".to_string()).await.unwrap());
+        // Contains falls back to the slow tier
+        assert!(backend.contains(&"key".to_string()).await.unwrap());
+
+        let loaded = backend.load().await.unwrap();
+        assert_eq!(loaded["key"][0].value, "value");
+        assert!(backend.fast().contains(&"key".to_string()).await.unwrap());
+
+        backend.clear().await.unwrap();
+        assert!(backend.fast().load().await.unwrap().is_empty());
+        assert!(backend.slow().load().await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,164 @@
+//! Circuit breaker guarding backend calls
+
+use std::future::Future;
+use std::sync::Mutex;
+use tokio::time::Instant;
+
+use crate::{CacheError, CircuitBreakerConfig, Result};
+
+/// Tracks backend failures and refuses calls while the backend looks down
+///
+/// Shared by every clone of a cache, so failures from background saves and
+/// foreground calls count towards the same breaker.
+pub(crate) struct CircuitBreaker {
+    config: Option<CircuitBreakerConfig>,
+    state: Mutex<BreakerState>,
+}
+
+#[derive(Default)]
+struct BreakerState {
+    consecutive_failures: u32,
+    /// When the breaker last opened or a trial call failed
+    opened_at: Option<Instant>,
+}
+
+impl CircuitBreaker {
+    /// Create a breaker; without a config it never opens
+    pub(crate) fn new(config: Option<CircuitBreakerConfig>) -> Self {
+        Self {
+            config,
+            state: Mutex::default(),
+        }
+    }
+
+    /// Whether the breaker is closed, so the backend is believed healthy
+    pub(crate) fn is_closed(&self) -> bool {
+        self.state.lock().unwrap().opened_at.is_none()
+    }
+
+    /// Whether a backend call may be made now
+    ///
+    /// This is true while the breaker is closed, and again once it has been
+    /// open for the cooldown, to let calls find out if the backend is back.
+    pub(crate) fn allows_call(&self) -> bool {
+        let Some(config) = self.config else {
+            return true;
+        };
+        match self.state.lock().unwrap().opened_at {
+            Some(opened_at) => opened_at.elapsed() >= config.cooldown,
+            None => true,
+        }
+    }
+
+    /// Run `op` if the breaker allows it and record how it went
+    ///
+    /// A refused call fails with a storage backend error without running
+    /// `op`.
+    pub(crate) async fn call<T, F, Fut>(&self, op: F) -> Result<T>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        if !self.allows_call() {
+            return Err(CacheError::StorageBackend(
+                "backend circuit breaker is open".to_string(),
+            ));
+        }
+        let result = op().await;
+        match &result {
+            Ok(_) => self.record_success(),
+            Err(e) => self.record_failure(e),
+        }
+        result
+    }
+
+    fn record_success(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.consecutive_failures = 0;
+        if state.opened_at.take().is_some() {
+            #[cfg(feature = "tracing")]
+            tracing::info!("backend recovered, circuit breaker closed");
+            #[cfg(not(feature = "tracing"))]
+            eprintln!("Backend recovered, circuit breaker closed");
+        }
+    }
+
+    fn record_failure(&self, _error: &CacheError) {
+        let Some(config) = self.config else {
+            return;
+        };
+        let mut state = self.state.lock().unwrap();
+        state.consecutive_failures = state.consecutive_failures.saturating_add(1);
+        if state.opened_at.is_some() {
+            // A failed trial call restarts the cooldown
+            state.opened_at = Some(Instant::now());
+        } else if state.consecutive_failures >= config.failure_threshold {
+            state.opened_at = Some(Instant::now());
+            #[cfg(feature = "tracing")]
+            tracing::warn!(
+                error = %_error,
+                failures = state.consecutive_failures,
+                cooldown = ?config.cooldown,
+                "backend keeps failing, circuit breaker opened"
+            );
+            #[cfg(not(feature = "tracing"))]
+            eprintln!(
+                "Backend failed {} times in a row, circuit breaker opened for {:?}: {_error}",
+                state.consecutive_failures, config.cooldown
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    async fn fail(breaker: &CircuitBreaker) -> Result<()> {
+        breaker
+            .call(|| async { Err(CacheError::StorageBackend("down".into())) })
+            .await
+    }
+
+    #[tokio::test]
+    async fn test_opens_after_threshold_and_closes_on_success() {
+        let breaker = CircuitBreaker::new(Some(CircuitBreakerConfig::new(
+            2,
+            Duration::from_millis(20),
+        )));
+        fail(&breaker).await.unwrap_err();
+        assert!(breaker.is_closed());
+        fail(&breaker).await.unwrap_err();
+        assert!(!breaker.is_closed());
+
+        let mut ran = false;
+        let refused = breaker
+            .call(|| {
+                ran = true;
+                async { Ok(()) }
+            })
+            .await;
+        assert!(refused.is_err());
+        assert!(!ran);
+
+        // A failed trial after the cooldown keeps it open
+        tokio::time::sleep(Duration::from_millis(25)).await;
+        fail(&breaker).await.unwrap_err();
+        assert!(!breaker.allows_call());
+
+        tokio::time::sleep(Duration::from_millis(25)).await;
+        breaker.call(|| async { Ok(()) }).await.unwrap();
+        assert!(breaker.is_closed());
+    }
+
+    #[tokio::test]
+    async fn test_without_config_never_opens() {
+        let breaker = CircuitBreaker::new(None);
+        for _ in 0..10 {
+            fail(&breaker).await.unwrap_err();
+        }
+        assert!(breaker.is_closed());
+        assert!(breaker.allows_call());
+    }
+}
@@ -0,0 +1,292 @@
+//! Fluent construction of caches
+
+use std::time::Duration;
+
+use crate::backends::memory::MemoryBackend;
+use crate::backends::{StorageKey, StorageMeta, StorageValue};
+use crate::cache::{CacheKey, CacheValue};
+use crate::clock::Clock;
+use crate::eviction::EvictionStrategy;
+use crate::loader::Loader;
+use crate::{
+    Cache, CacheConfig, CacheEntry, CacheEvent, EntryMetadata, EvictionPolicy, Result,
+    StorageBackend,
+};
+
+/// Type alias for the eviction callback held until the cache is built
+type BuilderEvictionCallback<K, V, M> = Box<dyn Fn(&K, &[CacheEntry<K, V, M>]) + Send + Sync>;
+
+/// Builder for [`Cache`]
+///
+/// Starts from the default configuration and an in-memory backend, so
+/// `CacheBuilder::new().build().await` is enough for the common case.
+///
+/// ```rust
+/// use threatflux_cache::{AsyncCache, Cache, CacheBuilder, EvictionPolicy};
+/// use std::time::Duration;
+///
+/// # #[tokio::main]
+/// # async fn main() -> threatflux_cache::Result<()> {
+/// let cache: Cache<String, String> = CacheBuilder::new()
+///     .eviction(EvictionPolicy::Lfu)
+///     .default_ttl(Duration::from_secs(60))
+///     .build()
+///     .await?;
+/// cache.put("key".to_string(), "value".to_string()).await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct CacheBuilder<K, V, M = (), B = MemoryBackend<K, V, M>>
+where
+    K: CacheKey,
+    V: CacheValue,
+    M: EntryMetadata + Default,
+    B: StorageBackend<Key = K, Value = V, Metadata = M>,
+{
+    config: CacheConfig,
+    backend: B,
+    eviction_strategy: Option<Box<dyn EvictionStrategy<K, V, M>>>,
+    on_evict: Option<BuilderEvictionCallback<K, V, M>>,
+    loader: Option<std::sync::Arc<dyn Loader<K, V>>>,
+    events: Option<tokio::sync::broadcast::Sender<CacheEvent<K>>>,
+    clock: Option<std::sync::Arc<dyn Clock>>,
+}
+
+impl<K, V, M> CacheBuilder<K, V, M>
+where
+    K: StorageKey,
+    V: StorageValue,
+    M: StorageMeta + Default,
+{
+    /// Create a builder with the default configuration and a memory backend
+    pub fn new() -> Self {
+        Self {
+            config: CacheConfig::default(),
+            backend: MemoryBackend::new(),
+            eviction_strategy: None,
+            on_evict: None,
+            loader: None,
+            events: None,
+            clock: None,
+        }
+    }
+}
+
+impl<K, V, M> Default for CacheBuilder<K, V, M>
+where
+    K: StorageKey,
+    V: StorageValue,
+    M: StorageMeta + Default,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V, M, B> CacheBuilder<K, V, M, B>
+where
+    K: CacheKey,
+    V: CacheValue,
+    M: EntryMetadata + Default,
+    B: StorageBackend<Key = K, Value = V, Metadata = M>,
+{
+    /// Replace the whole configuration
+    ///
+    /// Settings applied earlier through `eviction`, `default_ttl`,
+    /// `measured_sizes` or `save_limit` are overwritten, so call this first.
+    pub fn config(mut self, config: CacheConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Store entries in `backend` instead of memory
+    pub fn backend<B2>(self, backend: B2) -> CacheBuilder<K, V, M, B2>
+    where
+        B2: StorageBackend<Key = K, Value = V, Metadata = M>,
+    {
+        CacheBuilder {
+            config: self.config,
+            backend,
+            eviction_strategy: self.eviction_strategy,
+            on_evict: self.on_evict,
+            loader: self.loader,
+            events: self.events,
+            clock: self.clock,
+        }
+    }
+
+    /// Set the eviction policy
+    pub fn eviction(mut self, policy: EvictionPolicy) -> Self {
+        self.config.eviction_policy = policy;
+        self
+    }
+
+    /// Evict with a custom strategy, taking precedence over `eviction`
+    pub fn eviction_strategy(mut self, strategy: Box<dyn EvictionStrategy<K, V, M>>) -> Self {
+        self.eviction_strategy = Some(strategy);
+        self
+    }
+
+    /// Set the TTL applied to entries inserted without one
+    pub fn default_ttl(mut self, ttl: Duration) -> Self {
+        self.config.default_ttl = Some(ttl);
+        self
+    }
+
+    /// Randomize the default TTL of each entry by up to `jitter` either way
+    pub fn ttl_jitter(mut self, jitter: Duration) -> Self {
+        self.config.ttl_jitter = Some(jitter);
+        self
+    }
+
+    /// Fill in the size of entries added without one
+    ///
+    /// See [`Cache::with_measured_sizes`].
+    #[cfg(feature = "json-serialization")]
+    pub fn measured_sizes(mut self, enable: bool) -> Self {
+        self.config.measure_sizes = enable;
+        self
+    }
+
+    /// Share `limit` as a save-concurrency limit with other caches
+    ///
+    /// See [`PersistenceConfig::save_limit`](crate::PersistenceConfig::save_limit).
+    pub fn save_limit(mut self, limit: std::sync::Arc<tokio::sync::Semaphore>) -> Self {
+        self.config.persistence.save_limit = Some(limit);
+        self
+    }
+
+    /// Register a callback invoked for every key removed by eviction
+    ///
+    /// See [`Cache::with_on_evict`].
+    pub fn on_evict<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&K, &[CacheEntry<K, V, M>]) + Send + Sync + 'static,
+    {
+        self.on_evict = Some(Box::new(callback));
+        self
+    }
+
+    /// Load keys that `get` misses from `loader`
+    ///
+    /// See [`Cache::with_loader`].
+    pub fn loader(mut self, loader: std::sync::Arc<dyn Loader<K, V>>) -> Self {
+        self.loader = Some(loader);
+        self
+    }
+
+    /// Publish cache events on `sender`
+    ///
+    /// See [`Cache::with_event_channel`].
+    pub fn event_channel(mut self, sender: tokio::sync::broadcast::Sender<CacheEvent<K>>) -> Self {
+        self.events = Some(sender);
+        self
+    }
+
+    /// Take the current time from `clock`
+    ///
+    /// See [`Cache::with_clock`].
+    pub fn clock(mut self, clock: std::sync::Arc<dyn Clock>) -> Self {
+        self.clock = Some(clock);
+        self
+    }
+
+    /// Build the cache, loading persisted entries if the configuration asks
+    /// for it
+    pub async fn build(self) -> Result<Cache<K, V, M, B>> {
+        let mut cache = Cache::new(self.config, self.backend).await?;
+        if let Some(strategy) = self.eviction_strategy {
+            cache = cache.with_eviction_strategy(strategy);
+        }
+        if let Some(loader) = self.loader {
+            cache = cache.with_loader(loader);
+        }
+        if let Some(sender) = self.events {
+            cache = cache.with_event_channel(sender);
+        }
+        if let Some(clock) = self.clock {
+            cache = cache.with_clock(clock);
+        }
+        Ok(match self.on_evict {
+            Some(callback) => cache.with_on_evict(callback),
+            None => cache,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AsyncCache;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_default_build_uses_memory_backend() {
+        let cache: Cache<String, String> = CacheBuilder::new().build().await.unwrap();
+        cache.put("k".to_string(), "v".to_string()).await.unwrap();
+        assert_eq!(
+            cache.get(&"k".to_string()).await.unwrap().as_deref(),
+            Some("v")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_custom_strategy_overrides_policy() {
+        let cache: Cache<String, String> = CacheBuilder::new()
+            .config(CacheConfig::default().with_max_total_entries(1))
+            .eviction(EvictionPolicy::Fifo)
+            .eviction_strategy(Box::new(crate::eviction::NoEviction))
+            .build()
+            .await
+            .unwrap();
+
+        cache.put("a".to_string(), "1".to_string()).await.unwrap();
+        cache.put("b".to_string(), "2".to_string()).await.unwrap();
+        assert_eq!(cache.len().await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_builder_applies_settings() {
+        let evicted = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&evicted);
+        let backend: MemoryBackend<String, String> = MemoryBackend::new();
+
+        let cache = CacheBuilder::new()
+            .config(CacheConfig::default().with_max_total_entries(1))
+            .backend(backend)
+            .eviction(EvictionPolicy::Fifo)
+            .default_ttl(Duration::from_secs(60))
+            .on_evict(move |_, _| {
+                counter.fetch_add(1, Ordering::SeqCst);
+            })
+            .build()
+            .await
+            .unwrap();
+
+        cache.put("a".to_string(), "1".to_string()).await.unwrap();
+        cache.put("b".to_string(), "2".to_string()).await.unwrap();
+
+        assert_eq!(evicted.load(Ordering::SeqCst), 1);
+        assert!(!cache.contains(&"a".to_string()).await.unwrap());
+        let latest = cache.get_latest(&"b".to_string()).await.unwrap();
+        assert!(latest.expiry.is_some());
+    }
+
+    #[cfg(feature = "json-serialization")]
+    #[tokio::test]
+    async fn test_builder_measures_sizes() {
+        let cache: Cache<String, String, crate::MeasuredMetadata> = CacheBuilder::new()
+            .measured_sizes(true)
+            .build()
+            .await
+            .unwrap();
+
+        cache
+            .put("k".to_string(), "value".to_string())
+            .await
+            .unwrap();
+        let latest = cache.get_latest(&"k".to_string()).await.unwrap();
+        assert_eq!(latest.metadata.size_bytes(), Some("\"value\"".len() as u64));
+    }
+}
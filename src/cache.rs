@@ -1,27 +1,204 @@
 //! Core cache implementation
 
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures::TryStreamExt;
 use serde::{de::DeserializeOwned, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
 use std::hash::Hash;
+use std::ops::ControlFlow;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::sync::Arc;
-use tokio::sync::{RwLock, Semaphore};
+use tokio::sync::{broadcast, watch, OnceCell, Semaphore, SemaphorePermit};
 
 use crate::{
+    breaker::CircuitBreaker,
+    clock::{Clock, SystemClock},
+    dedup::{DedupSavings, ValueDedup},
+    events::CacheEvent,
     eviction::{EvictionContext, EvictionStrategy},
+    loader::Loader,
+    random::{random_fraction, seeded_fraction},
+    retry::{retry_delay, with_retry},
     search::Searchable,
-    CacheConfig, CacheEntry, CacheError, EntryMetadata, Result, StorageBackend,
+    shards::Shards,
+    storage::EntryMap,
+    CacheConfig, CacheEntry, CacheError, CacheMode, EntryMetadata, MergePolicy, PerKeyEviction,
+    Result, RetryConfig, StorageBackend,
 };
 
 /// Type alias for cache entries storage
-type CacheStorage<K, V, M> = Arc<RwLock<HashMap<K, Vec<CacheEntry<K, V, M>>>>>;
+type CacheStorage<K, V, M> = Arc<Shards<K, V, M>>;
 
-/// Type alias for eviction strategy
-type EvictionStrategyBox<K, V, M> = Box<dyn EvictionStrategy<K, V, M>>;
+/// Type alias for the eviction strategy shared between cache clones
+type SharedEvictionStrategy<K, V, M> = Arc<dyn EvictionStrategy<K, V, M>>;
+
+/// Type alias for the eviction strategy of each shard, by shard index
+type ShardEvictionStrategies<K, V, M> = Arc<[SharedEvictionStrategy<K, V, M>]>;
 
 /// Type alias for cache entry
 type Entry<K, V, M> = CacheEntry<K, V, M>;
 
+/// Type alias for the loads in progress, one shared result per key
+type LoadsInFlight<K, V> = Arc<std::sync::Mutex<HashMap<K, Arc<OnceCell<Option<V>>>>>>;
+
+/// Type alias for the senders behind `Cache::watch`, one per watched key
+type KeyWatchers<K, V> = Arc<std::sync::Mutex<HashMap<K, watch::Sender<Option<V>>>>>;
+
+/// Type alias for the keys changed since they were last saved
+type DirtyKeys<K> = Arc<std::sync::Mutex<HashSet<K>>>;
+
+/// Permits held while saving: the cache's own, then the shared limit's
+type SavePermits<'a> = (SemaphorePermit<'a>, Option<SemaphorePermit<'a>>);
+
+/// Take the permits a save needs, or `None` if a semaphore was closed
+///
+/// The cache's own permit is always taken first, so caches waiting on the
+/// shared limit never hold it while waiting on each other.
+async fn acquire_save_permits<'a>(
+    own: &'a Semaphore,
+    shared: Option<&'a Semaphore>,
+) -> Option<SavePermits<'a>> {
+    let own = own.acquire().await.ok()?;
+    let shared = match shared {
+        Some(shared) => Some(shared.acquire().await.ok()?),
+        None => None,
+    };
+    Some((own, shared))
+}
+
+/// Callback invoked with each key removed by eviction and its dropped entries
+pub type EvictionCallback<K, V, M> = Arc<dyn Fn(&K, &[CacheEntry<K, V, M>]) + Send + Sync>;
+
+/// Record a field on the current tracing span
+///
+/// Expands to nothing, value expression included, without the `tracing`
+/// feature.
+macro_rules! trace_record {
+    ($field:literal, $value:expr) => {
+        #[cfg(feature = "tracing")]
+        tracing::Span::current().record($field, $value);
+    };
+}
+
+/// Record `key` on the current tracing span if key tracing is enabled
+macro_rules! trace_key {
+    ($cache:expr, $key:expr) => {
+        #[cfg(feature = "tracing")]
+        if let Some(display) = $cache.key_display {
+            tracing::Span::current().record("key", display($key).as_str());
+        }
+    };
+}
+
+/// Get shard `shard`'s part of `total` split across `shards`
+///
+/// The remainder goes to the first shards, one each, so the parts add up to
+/// `total` exactly.
+fn shard_share(total: u64, shards: usize, shard: usize) -> u64 {
+    let shards = shards as u64;
+    total / shards + u64::from((shard as u64) < total % shards)
+}
+
+/// Save every shard to `backend` in one call, through `breaker` and
+/// retrying as `retry` allows
+///
+/// A single shard is saved as is; several are merged into one map first.
+/// The shards are locked again for each attempt, so a retry saves whatever
+/// they hold by then. With `only_dirty`, just the keys in `dirty` go through
+/// [`StorageBackend::save_keys`]. Either way the saved keys are taken out of
+/// `dirty`, and put back if the attempt fails. The key count is recorded on
+/// the current tracing span.
+async fn save_shards<K, V, M, B>(
+    backend: &B,
+    shards: &Shards<K, V, M>,
+    dirty: &std::sync::Mutex<HashSet<K>>,
+    only_dirty: bool,
+    retry: Option<&RetryConfig>,
+    breaker: &CircuitBreaker,
+) -> Result<()>
+where
+    K: CacheKey,
+    V: CacheValue,
+    M: EntryMetadata,
+    B: StorageBackend<Key = K, Value = V, Metadata = M>,
+{
+    breaker
+        .call(|| {
+            with_retry(retry, || {
+                save_shards_once(backend, shards, dirty, only_dirty)
+            })
+        })
+        .await
+}
+
+/// Make one attempt at [`save_shards`]
+async fn save_shards_once<K, V, M, B>(
+    backend: &B,
+    shards: &Shards<K, V, M>,
+    dirty: &std::sync::Mutex<HashSet<K>>,
+    only_dirty: bool,
+) -> Result<()>
+where
+    K: CacheKey,
+    V: CacheValue,
+    M: EntryMetadata,
+    B: StorageBackend<Key = K, Value = V, Metadata = M>,
+{
+    let guards = shards.read_all().await;
+    // Writers mark keys with their shard locked, so nothing is marked
+    // between taking the set and reading the shards
+    let saved = std::mem::take(&mut *dirty.lock().unwrap());
+    if only_dirty && saved.is_empty() {
+        return Ok(());
+    }
+    let merged: EntryMap<K, V, M>;
+    let entries = match guards.as_slice() {
+        [entries] => &**entries,
+        // Backends that write keys one by one only need the changed ones
+        guards if only_dirty && backend.supports_partial_save() => {
+            merged = saved
+                .iter()
+                .filter_map(|key| {
+                    let bucket = guards[shards.index_of(key)].get(key)?;
+                    Some((key.clone(), bucket.clone()))
+                })
+                .collect();
+            &merged
+        }
+        guards => {
+            merged = guards
+                .iter()
+                .flat_map(|entries| entries.iter())
+                .map(|(key, bucket)| (key.clone(), bucket.clone()))
+                .collect();
+            &merged
+        }
+    };
+    let result = if only_dirty {
+        trace_record!("keys", saved.len());
+        let keys: Vec<K> = saved.iter().cloned().collect();
+        backend.save_keys(entries, &keys).await
+    } else {
+        trace_record!("keys", entries.len());
+        backend.save(entries).await
+    };
+    if result.is_err() {
+        dirty.lock().unwrap().extend(saved);
+    }
+    result
+}
+
+/// Length of `value` serialized as JSON, for `CacheConfig::measure_sizes`
+///
+/// Values stored by a backend are always serializable, while the cache's
+/// own value type does not say so.
+#[cfg(feature = "json-serialization")]
+fn backend_value_size<B: StorageBackend>(value: &B::Value) -> Option<u64> {
+    crate::entry::serialized_size(value)
+}
+
 macro_rules! impl_cache_common {
     ($(#[$meta:meta])? $trait:path, $($body:tt)*) => {
         $(#[$meta])?
@@ -73,6 +250,8 @@ where
     async fn remove(&self, key: &K) -> std::result::Result<Option<V>, Self::Error>;
 
     /// Clear all entries from the cache
+    ///
+    /// Implementations may keep statistics across a clear.
     async fn clear(&self) -> std::result::Result<(), Self::Error>;
 
     /// Check if the cache contains a key
@@ -85,6 +264,25 @@ where
     async fn is_empty(&self) -> std::result::Result<bool, Self::Error> {
         Ok(self.len().await? == 0)
     }
+
+    /// Get a value from the cache, computing and inserting it with `f` on a miss
+    ///
+    /// The default implementation is a plain `get` followed by `put`, so two
+    /// concurrent misses may both run `f`. Implementations are encouraged to
+    /// override it with a version that computes at most once per key.
+    async fn get_or_insert_with<F, Fut>(&self, key: K, f: F) -> std::result::Result<V, Self::Error>
+    where
+        F: FnOnce() -> Fut + Send,
+        Fut: Future<Output = V> + Send,
+    {
+        if let Some(value) = self.get(&key).await? {
+            return Ok(value);
+        }
+
+        let value = f().await;
+        self.put(key, value.clone()).await?;
+        Ok(value)
+    }
 }
 
 /// Main cache implementation
@@ -100,8 +298,26 @@ where
     config: CacheConfig,
     backend: Arc<B>,
     save_semaphore: Arc<Semaphore>,
-    operation_count: Arc<RwLock<usize>>,
-    eviction_strategy: EvictionStrategyBox<K, V, M>,
+    operation_count: Arc<AtomicUsize>,
+    eviction_strategies: ShardEvictionStrategies<K, V, M>,
+    on_evict: Option<EvictionCallback<K, V, M>>,
+    eviction_count: Arc<AtomicU64>,
+    hits: Arc<AtomicU64>,
+    misses: Arc<AtomicU64>,
+    load_errors: Arc<AtomicU64>,
+    loader: Option<Arc<dyn Loader<K, V>>>,
+    loads_in_flight: LoadsInFlight<K, V>,
+    events: Option<broadcast::Sender<CacheEvent<K>>>,
+    watchers: KeyWatchers<K, V>,
+    breaker: Arc<CircuitBreaker>,
+    clock: Arc<dyn Clock>,
+    value_size: Option<fn(&V) -> Option<u64>>,
+    dedup: Option<Arc<dyn ValueDedup<V>>>,
+    ttl_jitter_seed: Option<Arc<AtomicU64>>,
+    dirty: DirtyKeys<K>,
+    handles: Arc<()>,
+    #[cfg(feature = "tracing")]
+    key_display: Option<fn(&K) -> String>,
 }
 
 impl<K, V, M, B> Cache<K, V, M, B>
@@ -112,21 +328,82 @@ where
     B: StorageBackend<Key = K, Value = V, Metadata = M>,
 {
     /// Create a new cache with the given configuration and backend
+    ///
+    /// Fails with [`CacheError::InvalidConfiguration`] if
+    /// [`CacheConfig::validate`] rejects the configuration.
     pub async fn new(config: CacheConfig, backend: B) -> Result<Self> {
-        let eviction_strategy = crate::eviction::create_strategy(&config.eviction_policy);
+        config.validate()?;
+        let entries = Shards::new(config.shard_count);
+        // Stateful strategies only track the keys of the shard they evict in
+        let eviction_strategies = (0..entries.count())
+            .map(|_| Arc::from(crate::eviction::create_strategy(&config.eviction_policy)))
+            .collect();
+        let backend = match &config.namespace {
+            Some(namespace) => backend.with_namespace(namespace).await?,
+            None => backend,
+        };
+        #[cfg(feature = "compression")]
+        let backend = match config.value_compression() {
+            Some(compression) => backend.with_value_compression(compression).await?,
+            None => backend,
+        };
 
-        let cache = Self {
-            entries: Arc::new(RwLock::new(HashMap::new())),
+        let breaker = Arc::new(CircuitBreaker::new(config.circuit_breaker));
+        #[cfg(feature = "json-serialization")]
+        let value_size = config.measure_sizes.then_some(backend_value_size::<B> as _);
+        #[cfg(not(feature = "json-serialization"))]
+        let value_size = None;
+
+        let mut cache = Self {
+            entries: Arc::new(entries),
             config,
             backend: Arc::new(backend),
             save_semaphore: Arc::new(Semaphore::new(1)),
-            operation_count: Arc::new(RwLock::new(0)),
-            eviction_strategy,
+            operation_count: Arc::new(AtomicUsize::new(0)),
+            eviction_strategies,
+            on_evict: None,
+            eviction_count: Arc::new(AtomicU64::new(0)),
+            hits: Arc::new(AtomicU64::new(0)),
+            misses: Arc::new(AtomicU64::new(0)),
+            load_errors: Arc::new(AtomicU64::new(0)),
+            loader: None,
+            loads_in_flight: Arc::default(),
+            events: None,
+            watchers: Arc::default(),
+            breaker,
+            clock: Arc::new(SystemClock),
+            value_size,
+            dedup: None,
+            ttl_jitter_seed: None,
+            dirty: Arc::default(),
+            handles: Arc::default(),
+            #[cfg(feature = "tracing")]
+            key_display: None,
         };
 
         // Load existing cache if configured
         if cache.config.persistence.enabled && cache.config.persistence.load_on_startup {
-            let _ = cache.load_from_storage().await;
+            if let Err(e) = cache.load_from_storage().await {
+                if cache.config.fail_on_load_error {
+                    // Dropping the half-built cache must not overwrite the
+                    // storage it failed to read
+                    cache.config.persistence.save_on_drop = false;
+                    return Err(e);
+                }
+                cache.load_errors.fetch_add(1, Ordering::Relaxed);
+                #[cfg(feature = "tracing")]
+                tracing::warn!(error = %e, "failed to load cache from storage, keeping the entries read so far");
+                #[cfg(not(feature = "tracing"))]
+                eprintln!(
+                    "Failed to load cache from storage, keeping the entries read so far: {e}"
+                );
+            }
+        }
+
+        if cache.config.persistence.enabled && !cache.config.read_only {
+            if let Some(period) = cache.config.persistence.effective_sync_strategy().period() {
+                cache.spawn_sync_timer(period);
+            }
         }
 
         Ok(cache)
@@ -140,12 +417,208 @@ where
         Self::new(config, B::default()).await
     }
 
+    /// Register a callback invoked for every key removed by eviction
+    ///
+    /// The callback runs while a shard's write lock is held, so it must not
+    /// call back into the cache.
+    pub fn with_on_evict<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&K, &[CacheEntry<K, V, M>]) + Send + Sync + 'static,
+    {
+        self.on_evict = Some(Arc::new(callback));
+        self
+    }
+
+    /// Evict with `strategy` instead of the one built from the configured
+    /// `EvictionPolicy`
+    ///
+    /// The strategy is shared with every clone made from the returned cache,
+    /// and by every shard when `shard_count` is above one, so it only sees
+    /// one shard's entries at a time. Strategies that track keys, such as
+    /// [`ArcEviction`](crate::eviction::ArcEviction), belong in
+    /// [`Cache::with_eviction_strategy_per_shard`] instead.
+    pub fn with_eviction_strategy(mut self, strategy: Box<dyn EvictionStrategy<K, V, M>>) -> Self {
+        let strategy: SharedEvictionStrategy<K, V, M> = Arc::from(strategy);
+        self.eviction_strategies = vec![strategy; self.entries.count()].into();
+        self
+    }
+
+    /// Evict with a strategy made by `make` for each shard, instead of the
+    /// ones built from the configured `EvictionPolicy`
+    ///
+    /// Each strategy sees only its own shard's keys. Clones made from the
+    /// returned cache share the strategies.
+    pub fn with_eviction_strategy_per_shard<F>(mut self, make: F) -> Self
+    where
+        F: Fn() -> Box<dyn EvictionStrategy<K, V, M>>,
+    {
+        self.eviction_strategies = (0..self.entries.count())
+            .map(|_| Arc::from(make()))
+            .collect();
+        self
+    }
+
+    /// Load keys that `get` misses from `loader`
+    ///
+    /// Values the loader finds are stored with `put` before being returned.
+    /// Concurrent misses on one key share a single loader call; if it fails,
+    /// each waiting caller retries the load itself.
+    pub fn with_loader(mut self, loader: Arc<dyn Loader<K, V>>) -> Self {
+        self.loader = Some(loader);
+        self
+    }
+
+    /// Take the current time from `clock` instead of the system clock
+    ///
+    /// The clock stamps entries the cache creates and decides expiry, age
+    /// and access times, so a [`ManualClock`](crate::ManualClock) lets tests
+    /// expire entries without sleeping. Entries built by callers keep the
+    /// timestamps they were given, and entries loaded by `Cache::new` were
+    /// checked against the system clock. Search queries also judge expiry by
+    /// the system clock, since `Searchable::matches` is not given the time.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Draw the TTL jitter from a sequence seeded with `seed`, so the same
+    /// inserts get the same expiries from run to run
+    ///
+    /// Clones of the cache share the sequence.
+    pub fn with_ttl_jitter_seed(mut self, seed: u64) -> Self {
+        self.ttl_jitter_seed = Some(Arc::new(AtomicU64::new(seed)));
+        self
+    }
+
+    /// Publish a [`CacheEvent`] on `sender` for every put, lookup, eviction
+    /// and expiry
+    ///
+    /// Sending never blocks or fails an operation: events nobody is
+    /// subscribed to are not built, and slow subscribers miss events rather
+    /// than hold the cache up.
+    pub fn with_event_channel(mut self, sender: broadcast::Sender<CacheEvent<K>>) -> Self {
+        self.events = Some(sender);
+        self
+    }
+
+    /// Subscribe to the events published on the channel passed to
+    /// `with_event_channel`
+    ///
+    /// Without a channel the receiver is already closed and yields nothing.
+    pub fn subscribe(&self) -> broadcast::Receiver<CacheEvent<K>> {
+        match &self.events {
+            Some(events) => events.subscribe(),
+            None => broadcast::channel(1).1,
+        }
+    }
+
+    /// Send the event built by `event` if anyone is subscribed
+    fn emit(&self, event: impl FnOnce() -> CacheEvent<K>) {
+        if let Some(events) = &self.events {
+            if events.receiver_count() > 0 {
+                // A send only fails once every subscriber has gone
+                let _ = events.send(event());
+            }
+        }
+    }
+
+    /// Watch the latest value of `key`
+    ///
+    /// The receiver starts with the current value, `None` if the key is
+    /// absent, and sees every put, removal, eviction and expiry of the key
+    /// made through this cache or its clones. Keys filled by bulk loads and
+    /// snapshot imports are not reported. All watchers of a key share one
+    /// sender, which is dropped at the key's next change or the next `watch`
+    /// call once every receiver is gone.
+    pub async fn watch(&self, key: &K) -> watch::Receiver<Option<V>> {
+        // Holding the shard keeps writers from changing the key before the
+        // sender exists
+        let entries = self.entries.for_key(key).read().await;
+        let mut watchers = self.watchers.lock().unwrap();
+        watchers.retain(|_, sender| !sender.is_closed());
+        if let Some(sender) = watchers.get(key) {
+            return sender.subscribe();
+        }
+        let current = entries
+            .get(key)
+            .and_then(|bucket| self.visible_latest(bucket))
+            .map(|entry| entry.value.clone());
+        let (sender, receiver) = watch::channel(current);
+        watchers.insert(key.clone(), sender);
+        receiver
+    }
+
+    /// Send the latest value of `bucket` to the watchers of `key`
+    ///
+    /// Called with the key's shard locked, so watchers see changes in the
+    /// order they were made.
+    fn notify_watchers(&self, key: &K, bucket: Option<&[CacheEntry<K, V, M>]>) {
+        let mut watchers = self.watchers.lock().unwrap();
+        let Some(sender) = watchers.get(key) else {
+            return;
+        };
+        let value = bucket
+            .and_then(|bucket| self.visible_latest(bucket))
+            .map(|entry| entry.value.clone());
+        if sender.send(value).is_err() {
+            watchers.remove(key);
+        }
+    }
+
+    /// Record keys, formatted with `Display`, on this cache's tracing spans
+    ///
+    /// Keys are left out of spans by default since they may be sensitive or
+    /// expensive to format.
+    #[cfg(feature = "tracing")]
+    pub fn with_key_tracing(mut self) -> Self
+    where
+        K: std::fmt::Display,
+    {
+        self.key_display = Some(|key| key.to_string());
+        self
+    }
+
+    /// Fill in `size_bytes` for entries added without one, from the length
+    /// of their value serialized as JSON
+    ///
+    /// This gives `max_total_bytes` something to count without callers
+    /// measuring values themselves. The metadata type has to be able to hold
+    /// a size, as [`BasicMetadata`](crate::entry::BasicMetadata) and
+    /// [`MeasuredMetadata`](crate::MeasuredMetadata) can; JSON length is an
+    /// estimate of memory use, not an exact figure. Setting
+    /// [`CacheConfig::measure_sizes`] does the same.
+    #[cfg(feature = "json-serialization")]
+    pub fn with_measured_sizes(mut self) -> Self
+    where
+        V: serde::Serialize,
+    {
+        self.value_size = Some(|value| crate::entry::serialized_size(value));
+        self
+    }
+
     /// Add an entry to the cache
     #[allow(clippy::type_complexity)]
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "cache.add_entry",
+            level = "debug",
+            skip_all,
+            fields(
+                key = tracing::field::Empty,
+                shard = tracing::field::Empty,
+                keys = tracing::field::Empty
+            )
+        )
+    )]
     pub async fn add_entry(&self, entry: Entry<K, V, M>) -> Result<()> {
+        self.ensure_writable()?;
+        trace_key!(self, &entry.key);
         {
-            let mut entries = self.entries.write().await;
-            self.insert_entry(&mut entries, entry).await;
+            let shard = self.entries.index_of(&entry.key);
+            let mut entries = self.entries.shard(shard).write().await;
+            self.insert_entry(shard, &mut entries, entry).await;
+            self.trace_shard_keys(shard, entries.len());
         }
 
         // Increment operation count and check if we need to sync
@@ -156,228 +629,1967 @@ where
 
     async fn insert_entry(
         &self,
+        shard: usize,
         entries: &mut HashMap<K, Vec<CacheEntry<K, V, M>>>,
         entry: Entry<K, V, M>,
     ) {
+        let entry = self.dedup_value(self.measure_size(self.apply_default_ttl(entry)));
+        self.eviction_strategies[shard].on_insert(&entry.key);
+        self.emit(|| CacheEvent::Put(entry.key.clone()));
         let key_entries = entries.entry(entry.key.clone()).or_default();
-        key_entries.push(entry);
 
-        // Limit entries per key
-        if key_entries.len() > self.config.max_entries_per_key {
-            key_entries.remove(0);
+        // Limit entries per key, never dropping the new entry
+        if key_entries.len() >= self.config.max_entries_per_key {
+            let index = Self::per_key_victim(self.config.per_key_eviction, key_entries);
+            key_entries.remove(index);
         }
+        let position = if self.config.keep_buckets_sorted {
+            key_entries.partition_point(|e| e.timestamp <= entry.timestamp)
+        } else {
+            key_entries.len()
+        };
+        key_entries.insert(position, entry);
+        self.mark_dirty(&key_entries[position].key);
+        self.notify_watchers(&key_entries[position].key, Some(key_entries));
 
-        // Check if we need to evict
-        let total_entries: usize = entries.values().map(|v| v.len()).sum();
-        if total_entries > self.config.max_total_entries {
-            let context = EvictionContext {
-                max_total_entries: self.config.max_total_entries,
-                current_total_entries: total_entries,
-            };
-            self.eviction_strategy.evict(entries, &context).await;
-        }
+        self.evict_if_needed(shard, entries).await;
     }
 
-    /// Get all entries for a key
-    pub async fn get_entries(&self, key: &K) -> Option<Vec<CacheEntry<K, V, M>>> {
-        let mut entries = self.entries.write().await;
-        entries.get_mut(key).map(|entries| {
-            // Update access statistics
-            for entry in entries.iter_mut() {
-                entry.record_access();
-            }
-            entries.clone()
-        })
+    /// Index of the stored entry to drop from an over-full key
+    fn per_key_victim(eviction: PerKeyEviction, stored: &[CacheEntry<K, V, M>]) -> usize {
+        let position = match eviction {
+            PerKeyEviction::Oldest => return 0,
+            PerKeyEviction::Newest => stored
+                .iter()
+                .enumerate()
+                .max_by_key(|(_, e)| e.timestamp)
+                .map(|(i, _)| i),
+            PerKeyEviction::LeastAccessed => stored
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, e)| e.last_accessed())
+                .map(|(i, _)| i),
+        };
+        position.unwrap_or(0)
     }
 
-    /// Get the latest entry for a key
-    pub async fn get_latest(&self, key: &K) -> Option<CacheEntry<K, V, M>> {
-        let mut entries = self.entries.write().await;
-        entries.get_mut(key).and_then(|entries| {
-            entries.iter_mut().max_by_key(|e| e.timestamp).map(|e| {
-                e.record_access();
-                e.clone()
-            })
-        })
+    /// Replace all entries for the entry's key with the given entry,
+    /// returning the replaced entries
+    fn replace_entry(
+        &self,
+        shard: usize,
+        entries: &mut HashMap<K, Vec<CacheEntry<K, V, M>>>,
+        entry: Entry<K, V, M>,
+    ) -> Vec<CacheEntry<K, V, M>> {
+        let entry = self.dedup_value(self.measure_size(self.apply_default_ttl(entry)));
+        self.eviction_strategies[shard].on_insert(&entry.key);
+        self.emit(|| CacheEvent::Put(entry.key.clone()));
+        let key_entries = entries.entry(entry.key.clone()).or_default();
+        let replaced = std::mem::replace(key_entries, vec![entry]);
+        self.mark_dirty(&key_entries[0].key);
+        self.notify_watchers(&key_entries[0].key, Some(key_entries));
+        replaced
     }
 
-    /// Search entries based on a query
-    pub async fn search<Q>(&self, query: &Q) -> Vec<CacheEntry<K, V, M>>
-    where
-        CacheEntry<K, V, M>: Searchable<Query = Q>,
-    {
-        let entries = self.entries.read().await;
-        entries
-            .values()
-            .flat_map(|v| v.iter())
-            .filter(|entry| entry.matches(query))
-            .cloned()
-            .collect()
+    /// Create an entry stamped with the cache's clock
+    fn new_entry(&self, key: K, value: V) -> Entry<K, V, M> {
+        CacheEntry::new(key, value).created_at(self.clock.now())
     }
 
-    /// Aggregate statistics for a slice of cache entries
-    fn entry_vec_stats(entry_vec: &[CacheEntry<K, V, M>]) -> (usize, u64, usize) {
-        entry_vec
-            .iter()
-            .fold((0, 0, 0), |(count, access, expired), entry| {
-                (
-                    count + 1,
-                    access + entry.access_count,
-                    expired + usize::from(entry.is_expired()),
-                )
-            })
+    /// Latest entry of a bucket, expired or not
+    ///
+    /// Among entries with the same timestamp, the one inserted last wins.
+    fn latest<'a>(&self, bucket: &'a [CacheEntry<K, V, M>]) -> Option<&'a CacheEntry<K, V, M>> {
+        if self.config.keep_buckets_sorted {
+            bucket.last()
+        } else {
+            bucket.iter().max_by_key(|e| e.timestamp)
+        }
     }
 
-    /// Get cache statistics
-    pub async fn get_stats(&self) -> CacheStats {
-        let entries = self.entries.read().await;
-        let total_keys = entries.len();
-
-        let (total_entries, total_access_count, expired_count) =
-            entries.values().fold((0, 0, 0), |acc, entry_vec| {
-                let (e, a, exp) = Self::entry_vec_stats(entry_vec);
-                (acc.0 + e, acc.1 + a, acc.2 + exp)
-            });
-
-        CacheStats {
-            total_entries,
-            total_keys,
-            total_access_count,
-            expired_count,
-            memory_usage_bytes: 0, // Would need size estimation
+    /// Mutable form of [`Self::latest`]
+    fn latest_mut<'a>(
+        &self,
+        bucket: &'a mut [CacheEntry<K, V, M>],
+    ) -> Option<&'a mut CacheEntry<K, V, M>> {
+        if self.config.keep_buckets_sorted {
+            bucket.last_mut()
+        } else {
+            bucket.iter_mut().max_by_key(|e| e.timestamp)
         }
     }
 
-    /// Save cache to storage backend
-    async fn save_to_storage(&self) -> Result<()> {
-        if !self.config.persistence.enabled {
-            return Ok(());
+    /// Sort a bucket that came from outside the cache if buckets are kept
+    /// sorted
+    ///
+    /// The sort is stable, so entries with the same timestamp keep their
+    /// order.
+    fn ordered(&self, mut bucket: Vec<CacheEntry<K, V, M>>) -> Vec<CacheEntry<K, V, M>> {
+        if self.config.keep_buckets_sorted {
+            bucket.sort_by_key(|e| e.timestamp);
         }
-
-        let _permit = self.save_semaphore.acquire().await.unwrap();
-        let entries = self.entries.read().await;
-        self.backend.save(&entries).await
+        bucket
     }
 
-    /// Load cache from storage backend
-    async fn load_from_storage(&self) -> Result<()> {
-        if !self.config.persistence.enabled {
-            return Ok(());
+    /// Fill in the size of an entry whose metadata has none, for caches
+    /// built with `with_measured_sizes`
+    fn measure_size(&self, mut entry: Entry<K, V, M>) -> Entry<K, V, M> {
+        if let Some(value_size) = self.value_size {
+            if entry.metadata.size_bytes().is_none() {
+                if let Some(size) = value_size(&entry.value) {
+                    entry.metadata.set_size_bytes(size);
+                }
+            }
         }
-
-        let loaded_entries = self.backend.load().await?;
-        let mut entries = self.entries.write().await;
-        *entries = loaded_entries;
-        Ok(())
+        entry
     }
 
-    /// Increment operation count and sync if needed
-    async fn increment_and_maybe_sync(&self) -> Result<()> {
-        let mut count = self.operation_count.write().await;
-        *count += 1;
+    /// Swap an entry's value for an identical stored one, for caches built
+    /// with `with_value_dedup`
+    fn dedup_value(&self, mut entry: Entry<K, V, M>) -> Entry<K, V, M> {
+        if let Some(dedup) = &self.dedup {
+            entry.value = dedup.intern(entry.value);
+        }
+        entry
+    }
 
-        if *count >= self.config.persistence.sync_interval {
-            *count = 0;
-            drop(count); // Release the lock before saving
+    /// Give an entry without an expiry the configured `default_ttl`
+    fn apply_default_ttl(&self, entry: Entry<K, V, M>) -> Entry<K, V, M> {
+        if entry.expiry.is_some() {
+            return entry;
+        }
+        let ttl = match self.config.ttl_jitter {
+            // Only a jittered TTL draws, so seeded sequences are not advanced
+            // and unseeded ones not hashed for nothing
+            Some(_) if self.config.default_ttl.is_some() => {
+                let random = match &self.ttl_jitter_seed {
+                    Some(state) => seeded_fraction(state),
+                    None => random_fraction(),
+                };
+                self.config.jittered_default_ttl(random)
+            }
+            _ => self.config.default_ttl,
+        };
+        match ttl {
+            // A TTL too large for chrono is treated as never expiring
+            Some(ttl) => match chrono::Duration::from_std(ttl) {
+                Ok(ttl) => entry.with_ttl(ttl),
+                Err(_) => entry,
+            },
+            None => entry,
+        }
+    }
 
-            // Spawn background save
-            let cache = self.clone();
-            tokio::spawn(async move {
-                let _ = cache.save_to_storage().await;
-            });
+    /// Run the shard's eviction strategy if the shard is over its share of
+    /// the capacity
+    ///
+    /// The shares add up to the configured limits, so no mix of shards can
+    /// hold more than the cache may.
+    ///
+    /// Strategies evict until the shard is down to its target, which sits
+    /// below the limit with `eviction_low_watermark` set; the pass is
+    /// repeated for strategies that stop short, until one evicts nothing.
+    /// Returns each key that lost entries, once.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "cache.evict",
+            level = "debug",
+            skip_all,
+            fields(
+                shard = tracing::field::Empty,
+                entries = tracing::field::Empty,
+                evicted = tracing::field::Empty
+            )
+        )
+    )]
+    async fn evict_if_needed(
+        &self,
+        shard: usize,
+        entries: &mut HashMap<K, Vec<CacheEntry<K, V, M>>>,
+    ) -> Vec<K> {
+        let shards = self.entries.count();
+        let max_entries = shard_share(self.config.max_total_entries as u64, shards, shard) as usize;
+        let max_bytes = self
+            .config
+            .max_total_bytes
+            .map(|max| shard_share(max, shards, shard));
+        #[cfg(feature = "tracing")]
+        if shards > 1 {
+            tracing::Span::current().record("shard", shard);
         }
+        let target = match self.config.eviction_low_watermark {
+            Some(fraction) => (max_entries as f64 * f64::from(fraction.clamp(0.0, 1.0))) as usize,
+            None => max_entries,
+        };
+        let mut evicted_keys = Vec::new();
+        let mut evicting = false;
+        loop {
+            let context = EvictionContext::measure(entries, max_entries, max_bytes)
+                .with_target_total_entries(target)
+                .with_now(self.clock.now());
+            trace_record!("entries", context.current_total_entries);
+            // Only crossing the limit starts eviction; once started it runs
+            // down to the target
+            let needed = if evicting {
+                context.above_target()
+            } else {
+                context.over_capacity()
+            };
+            if !needed {
+                return evicted_keys;
+            }
+            evicting = true;
 
-        Ok(())
+            let evicted = self.eviction_strategies[shard]
+                .evict(entries, &context)
+                .await;
+            self.eviction_count
+                .fetch_add(evicted.len() as u64, Ordering::Relaxed);
+            #[cfg(feature = "tracing")]
+            {
+                for (key, removed) in &evicted {
+                    let key = self.key_display.map(|display| display(key));
+                    tracing::debug!(key = key.as_deref(), entries = removed.len(), "evicted key");
+                }
+            }
+            for (key, _) in &evicted {
+                self.emit(|| CacheEvent::Evict(key.clone()));
+                self.mark_dirty(key);
+                self.notify_watchers(key, entries.get(key).map(Vec::as_slice));
+            }
+            if let Some(callback) = &self.on_evict {
+                for (key, removed) in &evicted {
+                    callback(key, removed);
+                }
+            }
+            if evicted.is_empty() {
+                return evicted_keys;
+            }
+            for (key, _) in evicted {
+                if !evicted_keys.contains(&key) {
+                    evicted_keys.push(key);
+                }
+            }
+            trace_record!("evicted", evicted_keys.len());
+        }
     }
-}
 
-impl_cache_common!(
-    Clone,
-    fn clone(&self) -> Self {
-        Self {
-            entries: Arc::clone(&self.entries),
-            config: self.config.clone(),
-            backend: Arc::clone(&self.backend),
-            save_semaphore: Arc::clone(&self.save_semaphore),
-            operation_count: Arc::clone(&self.operation_count),
-            eviction_strategy: crate::eviction::create_strategy(&self.config.eviction_policy),
+    /// Look up the bucket for a key, dropping expired entries first when
+    /// `expire_on_access` is enabled
+    ///
+    /// A bucket whose entries have all expired is removed entirely.
+    fn live_bucket<'a>(
+        &self,
+        entries: &'a mut HashMap<K, Vec<CacheEntry<K, V, M>>>,
+        key: &K,
+    ) -> Option<&'a mut Vec<CacheEntry<K, V, M>>> {
+        if self.config.expire_on_access {
+            let bucket = entries.get_mut(key)?;
+            let before = bucket.len();
+            bucket.retain(|e| !e.is_expired_at(self.clock.now()));
+            if bucket.len() < before {
+                self.emit(|| CacheEvent::Expire(key.clone()));
+                self.mark_dirty(key);
+                self.notify_watchers(key, Some(bucket));
+            }
+            if bucket.is_empty() {
+                entries.remove(key);
+                return None;
+            }
         }
+        entries.get_mut(key)
     }
-);
-
-impl_cache_common!(#[async_trait] AsyncCache<K, V>,
-    type Error = CacheError;
 
-    async fn get(&self, key: &K) -> std::result::Result<Option<V>, Self::Error> {
-        Ok(self.get_latest(key).await.map(|entry| entry.value))
+    /// Whether `key` holds expired entries that `expire_on_access` has to
+    /// drop
+    ///
+    /// Read-only caches never drop them; `visit` hides them instead.
+    fn needs_sweep(&self, entries: &HashMap<K, Vec<CacheEntry<K, V, M>>>, key: &K) -> bool {
+        self.config.expire_on_access
+            && !self.config.read_only
+            && entries
+                .get(key)
+                .is_some_and(|bucket| bucket.iter().any(|e| e.is_expired_at(self.clock.now())))
     }
 
-    async fn put(&self, key: K, value: V) -> std::result::Result<(), Self::Error> {
+    /// Map the live bucket of each key with `f`
+    ///
+    /// Recording access only needs a shared reference, so lookups run under
+    /// read locks on every shard. Write locks are taken only when expired
+    /// entries have to be dropped.
+    async fn lookup_many<T, F>(&self, keys: &[K], f: F) -> Vec<Option<T>>
+    where
+        F: Fn(&[CacheEntry<K, V, M>]) -> Option<T> + Send,
+    {
         {
-            let mut entries = self.entries.write().await;
-            let key_entries = entries.entry(key.clone()).or_insert_with(Vec::new);
-
-            // For AsyncCache trait, replace existing entries rather than add
-            key_entries.clear();
-            key_entries.push(CacheEntry::new(key, value));
+            let shards = self.entries.read_all().await;
+            let entries = |key: &K| &shards[self.entries.index_of(key)];
+            if !keys.iter().any(|key| self.needs_sweep(entries(key), key)) {
+                return keys
+                    .iter()
+                    .map(|key| {
+                        entries(key)
+                            .get(key)
+                            .and_then(|bucket| self.visit(key, bucket, &f))
+                    })
+                    .collect();
+            }
         }
 
-        // Increment operation count and check if we need to sync
-        self.increment_and_maybe_sync().await?;
-        Ok(())
+        let mut shards = self.entries.write_all().await;
+        keys.iter()
+            .map(|key| {
+                let entries = &mut shards[self.entries.index_of(key)];
+                self.live_bucket(entries, key)
+                    .and_then(|bucket| self.visit(key, bucket, &f))
+            })
+            .collect()
     }
 
-    async fn remove(&self, key: &K) -> std::result::Result<Option<V>, Self::Error> {
-        let mut entries = self.entries.write().await;
-        let removed = entries.remove(key);
+    /// Apply a lookup to a bucket, telling the eviction strategy about hits
+    ///
+    /// In a read-only cache, expired entries `expire_on_access` would have
+    /// dropped are left out of the bucket `f` sees.
+    fn visit<T, F>(&self, key: &K, bucket: &[CacheEntry<K, V, M>], f: &F) -> Option<T>
+    where
+        F: Fn(&[CacheEntry<K, V, M>]) -> Option<T>,
+    {
+        if self.config.read_only {
+            let now = self.clock.now();
+            if self.config.expire_on_access && bucket.iter().any(|e| e.is_expired_at(now)) {
+                let live: Vec<_> = bucket
+                    .iter()
+                    .filter(|e| !e.is_expired_at(now))
+                    .cloned()
+                    .collect();
+                return if live.is_empty() { None } else { f(&live) };
+            }
+            return f(bucket);
+        }
+        let result = f(bucket);
+        if result.is_some() {
+            self.eviction_strategies[self.entries.index_of(key)].on_access(key);
+        }
+        result
+    }
 
-        if removed.is_some() {
-            // Remove from backend
-            self.backend.remove(key).await?;
-            self.increment_and_maybe_sync().await?;
+    /// Map the live bucket of a single key with `f`
+    ///
+    /// Like `lookup_many`, but only the key's own shard is locked.
+    async fn lookup<T, F>(&self, key: &K, f: F) -> Option<T>
+    where
+        F: Fn(&[CacheEntry<K, V, M>]) -> Option<T> + Send,
+    {
+        let shard = self.entries.for_key(key);
+        {
+            let entries = shard.read().await;
+            if !self.needs_sweep(&entries, key) {
+                return entries
+                    .get(key)
+                    .and_then(|bucket| self.visit(key, bucket, &f));
+            }
         }
 
-        Ok(removed.and_then(|entries| entries.into_iter().next_back().map(|e| e.value)))
+        let mut entries = shard.write().await;
+        self.live_bucket(&mut entries, key)
+            .and_then(|bucket| self.visit(key, bucket, &f))
     }
 
-    async fn clear(&self) -> std::result::Result<(), Self::Error> {
-        let mut entries = self.entries.write().await;
-        entries.clear();
+    /// Get all entries for a key
+    pub async fn get_entries(&self, key: &K) -> Option<Vec<CacheEntry<K, V, M>>> {
+        self.lookup(key, |bucket| {
+            // Update access statistics
+            for entry in bucket {
+                self.record_access(entry);
+            }
+            Some(bucket.to_vec())
+        })
+        .await
+    }
 
-        self.backend.clear().await?;
+    /// Get the `index`th entry stored for a key, counting from the oldest
+    ///
+    /// Entries are ordered by `timestamp`, so index 0 is the oldest version
+    /// and `version_count(key) - 1` the latest. Only the returned entry is
+    /// cloned and counted as accessed.
+    pub async fn get_version(&self, key: &K, index: usize) -> Option<CacheEntry<K, V, M>> {
+        self.lookup(key, |bucket| {
+            let mut versions: Vec<_> = bucket.iter().collect();
+            versions.sort_by_key(|e| e.timestamp);
+            versions.get(index).map(|e| {
+                self.record_access(e);
+                (*e).clone()
+            })
+        })
+        .await
+    }
 
-        Ok(())
+    /// Get the entries for a key created from `start` up to but excluding
+    /// `end`, oldest first
+    ///
+    /// Only the returned entries are cloned and counted as accessed.
+    pub async fn get_versions_between(
+        &self,
+        key: &K,
+        start: chrono::DateTime<chrono::Utc>,
+        end: chrono::DateTime<chrono::Utc>,
+    ) -> Vec<CacheEntry<K, V, M>> {
+        self.lookup(key, |bucket| {
+            let mut versions: Vec<_> = bucket
+                .iter()
+                .filter(|e| start <= e.timestamp && e.timestamp < end)
+                .collect();
+            if versions.is_empty() {
+                return None;
+            }
+            versions.sort_by_key(|e| e.timestamp);
+            Some(
+                versions
+                    .into_iter()
+                    .map(|e| {
+                        self.record_access(e);
+                        e.clone()
+                    })
+                    .collect::<Vec<_>>(),
+            )
+        })
+        .await
+        .unwrap_or_default()
     }
 
-    async fn contains(&self, key: &K) -> std::result::Result<bool, Self::Error> {
-        let entries = self.entries.read().await;
-        Ok(entries.contains_key(key))
+    /// Count the entries stored for a key, or 0 if it is absent
+    ///
+    /// Nothing is cloned and the lookup is not counted as an access.
+    /// Expired entries are not counted when `expire_on_access` is enabled.
+    pub async fn version_count(&self, key: &K) -> usize {
+        let entries = self.entries.for_key(key).read().await;
+        entries.get(key).map_or(0, |bucket| {
+            bucket
+                .iter()
+                .filter(|e| !self.config.expire_on_access || !e.is_expired_at(self.clock.now()))
+                .count()
+        })
     }
 
-    async fn len(&self) -> std::result::Result<usize, Self::Error> {
-        let entries = self.entries.read().await;
-        Ok(entries.values().map(|v| v.len()).sum())
+    /// Get the latest entry for a key
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "cache.get",
+            level = "debug",
+            skip_all,
+            fields(key = tracing::field::Empty, hit = tracing::field::Empty)
+        )
+    )]
+    pub async fn get_latest(&self, key: &K) -> Option<CacheEntry<K, V, M>> {
+        trace_key!(self, key);
+        let latest = self
+            .lookup(key, |bucket| {
+                self.latest(bucket).map(|e| {
+                    self.record_access(e);
+                    e.clone()
+                })
+            })
+            .await;
+        self.record_lookup(key, latest.is_some());
+        trace_record!("hit", latest.is_some());
+        latest
     }
-);
 
-impl_cache_common!(
-    Drop,
-    fn drop(&mut self) {
-        if self.config.persistence.enabled && self.config.persistence.save_on_drop {
-            // Try to save synchronously in drop
-            let entries = self.entries.clone();
-            let backend = self.backend.clone();
-
-            // We can't use async in drop, so we spawn a task to save
-            if let Ok(handle) = tokio::runtime::Handle::try_current() {
-                handle.spawn(async move {
-                    let entries = entries.read().await;
-                    let _ = backend.save(&entries).await;
-                });
+    /// Get the latest entry for a key without counting it as an access
+    ///
+    /// Unlike `get_latest`, access statistics, hit/miss counters and the
+    /// eviction strategy are left untouched, and expired entries are hidden
+    /// rather than dropped.
+    pub async fn peek_entry(&self, key: &K) -> Option<CacheEntry<K, V, M>> {
+        let entries = self.entries.for_key(key).read().await;
+        entries
+            .get(key)
+            .and_then(|bucket| self.visible_latest(bucket))
+            .cloned()
+    }
+
+    /// Get the latest value for a key without counting it as an access
+    ///
+    /// See [`Cache::peek_entry`].
+    pub async fn peek(&self, key: &K) -> Result<Option<V>> {
+        Ok(self.peek_entry(key).await.map(|entry| entry.value))
+    }
+
+    /// Get the latest value for a key, falling back to the loader on a miss
+    ///
+    /// The cache traits forward here, so their boxed and unboxed futures
+    /// share one implementation.
+    pub(crate) async fn get_value(&self, key: &K) -> Result<Option<V>> {
+        if let Some(entry) = self.get_latest(key).await {
+            return Ok(Some(entry.value));
+        }
+        if self.config.persistence.enabled
+            && self.config.mode == CacheMode::ReadThrough
+            && self.breaker.allows_call()
+        {
+            if let Some(value) = self.read_through(key).await? {
+                return Ok(Some(value));
             }
         }
+        match &self.loader {
+            Some(loader) => self.load_through(loader.as_ref(), key).await,
+            None => Ok(None),
+        }
     }
-);
+
+    /// Get the latest value for a key, or `V::default()` on a miss
+    ///
+    /// Counts as a lookup like `get`, but a miss never writes the default
+    /// back into the cache.
+    pub async fn get_or_default(&self, key: &K) -> Result<V>
+    where
+        V: Default,
+    {
+        Ok(self.get_value(key).await?.unwrap_or_default())
+    }
+
+    /// Store `value` as the only entry for `key`
+    pub(crate) async fn put_value(&self, key: K, value: V) -> Result<()> {
+        self.put_entry(self.new_entry(key, value)).await.map(|_| ())
+    }
+
+    /// Remove a key, returning its latest value
+    ///
+    /// While the circuit breaker is open the backend is left alone and the
+    /// key is marked as changed, so the next save removes it there.
+    pub(crate) async fn remove_key(&self, key: &K) -> Result<Option<V>> {
+        self.ensure_writable()?;
+        let removed = {
+            let mut entries = self.entries.for_key(key).write().await;
+            let removed = entries.remove(key);
+            if removed.is_some() {
+                self.notify_watchers(key, None);
+            }
+            removed
+        };
+
+        if removed.is_some() {
+            if self.breaker.allows_call() {
+                // The shard is unlocked while the backend call is retried; as
+                // in `remove_where`, a key put back meanwhile stays marked as
+                // changed
+                self.call_backend(|| self.backend.remove(key)).await?;
+                let entries = self.entries.for_key(key).read().await;
+                if entries.contains_key(key) {
+                    self.mark_dirty(key);
+                } else {
+                    self.dirty.lock().unwrap().remove(key);
+                }
+            } else {
+                self.mark_dirty(key);
+            }
+            self.increment_and_maybe_sync().await?;
+        }
+
+        Ok(removed.and_then(|entries| entries.into_iter().next_back().map(|e| e.value)))
+    }
+
+    /// Remove every entry from the cache and its backend
+    ///
+    /// Statistics and the count of writes since the last sync are kept; see
+    /// [`Cache::reset`] for a clear that also drops them. While the circuit
+    /// breaker is open the backend is left alone and the cleared keys are
+    /// marked as changed, so the next save removes them there; keys the
+    /// backend holds that were never in memory are then left in place.
+    pub(crate) async fn clear_all(&self) -> Result<()> {
+        self.ensure_writable()?;
+        let reach_backend = self.breaker.allows_call();
+        let mut shards = self.entries.write_all().await;
+        for entries in shards.iter_mut() {
+            if !reach_backend {
+                for key in entries.keys() {
+                    self.mark_dirty(key);
+                }
+            }
+            entries.clear();
+        }
+        for sender in self.watchers.lock().unwrap().values() {
+            sender.send_if_modified(|value| value.take().is_some());
+        }
+        drop(shards);
+        if !reach_backend {
+            return Ok(());
+        }
+
+        // Keys put while the backend clears may have been saved before it
+        // wiped them, so whatever the cache holds afterwards is marked as
+        // changed for the next save to restore
+        self.call_backend(|| self.backend.clear()).await?;
+        let shards = self.entries.read_all().await;
+        let mut dirty = self.dirty.lock().unwrap();
+        dirty.clear();
+        if self.config.persistence.enabled {
+            dirty.extend(shards.iter().flat_map(|entries| entries.keys().cloned()));
+        }
+
+        Ok(())
+    }
+
+    /// Whether the cache holds a live entry for `key`
+    pub(crate) async fn contains_key(&self, key: &K) -> bool {
+        let entries = self.entries.for_key(key).read().await;
+        self.holds_live(&entries, key)
+    }
+
+    /// Whether each of `keys` has a live entry, locking each shard once
+    ///
+    /// The returned vector is in the same order as `keys`. Like `contains`,
+    /// this is not counted as a lookup.
+    pub async fn contains_many(&self, keys: &[K]) -> Vec<bool> {
+        let shards = self.entries.read_all().await;
+        keys.iter()
+            .map(|key| self.holds_live(&shards[self.entries.index_of(key)], key))
+            .collect()
+    }
+
+    /// Whether `entries` holds a live entry for `key`
+    fn holds_live(&self, entries: &HashMap<K, Vec<CacheEntry<K, V, M>>>, key: &K) -> bool {
+        if self.config.expire_on_access {
+            return entries
+                .get(key)
+                .is_some_and(|entries| entries.iter().any(|e| !e.is_expired_at(self.clock.now())));
+        }
+        entries.contains_key(key)
+    }
+
+    /// Count the entries across all keys
+    pub(crate) async fn entry_count(&self) -> usize {
+        let shards = self.entries.read_all().await;
+        shards
+            .iter()
+            .flat_map(|entries| entries.values())
+            .map(|v| v.len())
+            .sum()
+    }
+
+    /// Load a missing key with `loader`, sharing the call with concurrent
+    /// misses on the same key
+    async fn load_through(&self, loader: &dyn Loader<K, V>, key: &K) -> Result<Option<V>> {
+        let flight = {
+            let mut flights = self
+                .loads_in_flight
+                .lock()
+                .unwrap_or_else(std::sync::PoisonError::into_inner);
+            Arc::clone(flights.entry(key.clone()).or_default())
+        };
+
+        let loaded = flight
+            .get_or_try_init(|| async {
+                let value = loader.load(key).await?;
+                match &value {
+                    Some(value) if !self.config.read_only => {
+                        self.put_value(key.clone(), value.clone()).await?;
+                    }
+                    _ => {}
+                }
+                Ok::<_, CacheError>(value)
+            })
+            .await
+            .cloned();
+
+        // Later misses must load again rather than reuse this result
+        let mut flights = self
+            .loads_in_flight
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        if flights
+            .get(key)
+            .is_some_and(|current| Arc::ptr_eq(current, &flight))
+        {
+            flights.remove(key);
+        }
+        loaded
+    }
+
+    /// Look a missed key up in the backend, keeping what it has in memory
+    async fn read_through(&self, key: &K) -> Result<Option<V>> {
+        let Some(bucket) = self.call_backend(|| self.backend.load_key(key)).await? else {
+            return Ok(None);
+        };
+
+        let shard = self.entries.index_of(key);
+        let mut entries = self.entries.shard(shard).write().await;
+        // A writer may have filled the key while the backend was read
+        if let Some(latest) = entries.get(key).and_then(|b| self.visible_latest(b)) {
+            return Ok(Some(latest.value.clone()));
+        }
+        let Some(latest) = self.visible_latest(&bucket) else {
+            return Ok(None);
+        };
+        self.record_access(latest);
+        let value = latest.value.clone();
+        if self.config.read_only {
+            return Ok(Some(value));
+        }
+        self.eviction_strategies[shard].on_insert(key);
+        entries.insert(key.clone(), self.ordered(bucket));
+        self.evict_if_needed(shard, &mut entries).await;
+        Ok(Some(value))
+    }
+
+    /// Record an access to `entry`, unless the cache is read-only
+    fn record_access(&self, entry: &CacheEntry<K, V, M>) {
+        if !self.config.read_only {
+            entry.record_access_at(self.clock.now());
+        }
+    }
+
+    /// Record the key count of shard `shard` on the current tracing span,
+    /// naming the shard when there are several
+    #[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+    fn trace_shard_keys(&self, shard: usize, keys: usize) {
+        #[cfg(feature = "tracing")]
+        {
+            let span = tracing::Span::current();
+            if self.entries.count() > 1 {
+                span.record("shard", shard);
+            }
+            span.record("keys", keys);
+        }
+    }
+
+    /// Note that `key` changed since it was last saved, for `save_dirty`
+    ///
+    /// Called with the key's shard locked.
+    fn mark_dirty(&self, key: &K) {
+        if self.config.persistence.enabled {
+            self.dirty.lock().unwrap().insert(key.clone());
+        }
+    }
+
+    /// Fail with [`CacheError::ReadOnly`] if the cache is read-only
+    fn ensure_writable(&self) -> Result<()> {
+        if self.config.read_only {
+            return Err(CacheError::ReadOnly);
+        }
+        Ok(())
+    }
+
+    /// Count a lookup of `key` towards the hit/miss statistics
+    fn record_lookup(&self, key: &K, hit: bool) {
+        let counter = if hit { &self.hits } else { &self.misses };
+        counter.fetch_add(1, Ordering::Relaxed);
+        self.emit(|| {
+            if hit {
+                CacheEvent::Get(key.clone())
+            } else {
+                CacheEvent::Miss(key.clone())
+            }
+        });
+    }
+
+    /// Reset the lifetime hit, miss, eviction and load error counters to zero
+    ///
+    /// `clear` leaves these counters untouched.
+    pub fn reset_stats(&self) {
+        self.hits.store(0, Ordering::Relaxed);
+        self.misses.store(0, Ordering::Relaxed);
+        self.eviction_count.store(0, Ordering::Relaxed);
+        self.load_errors.store(0, Ordering::Relaxed);
+    }
+
+    /// Start over as if the cache had just been created empty
+    ///
+    /// This clears the entries and the backend like `clear`, then zeroes the
+    /// statistics as `reset_stats` does and forgets the writes counted
+    /// towards the next sync. `clear` on its own keeps both, so that hit
+    /// rates and sync timing carry on across it. If clearing the backend
+    /// fails, the counters are left as they were.
+    pub async fn reset(&self) -> Result<()> {
+        self.clear_all().await?;
+        self.operation_count.store(0, Ordering::Release);
+        self.reset_stats();
+        Ok(())
+    }
+
+    /// Put a value into the cache that expires after `ttl`
+    ///
+    /// Like `put`, this replaces any existing entries for the key.
+    pub async fn put_with_ttl(&self, key: K, value: V, ttl: chrono::Duration) -> Result<()> {
+        self.put_entry(self.new_entry(key, value).with_ttl(ttl))
+            .await
+            .map(|_| ())
+    }
+
+    /// Replace the entries for the entry's key and sync if needed
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "cache.put",
+            level = "debug",
+            skip_all,
+            fields(
+                key = tracing::field::Empty,
+                shard = tracing::field::Empty,
+                keys = tracing::field::Empty
+            )
+        )
+    )]
+    async fn put_entry(&self, entry: Entry<K, V, M>) -> Result<Option<V>> {
+        let (replaced, _) = self.store_entry(entry).await?;
+        Ok(replaced)
+    }
+
+    /// Store `entry` as the only entry for its key, returning the latest
+    /// value it replaced and the keys evicted to make room
+    async fn store_entry(&self, entry: Entry<K, V, M>) -> Result<(Option<V>, Vec<K>)> {
+        self.ensure_writable()?;
+        trace_key!(self, &entry.key);
+        let (replaced, evicted) = {
+            let shard = self.entries.index_of(&entry.key);
+            let mut entries = self.entries.shard(shard).write().await;
+            let replaced = self.replace_entry(shard, &mut entries, entry);
+            let evicted = self.evict_if_needed(shard, &mut entries).await;
+            self.trace_shard_keys(shard, entries.len());
+            (replaced, evicted)
+        };
+
+        // Increment operation count and check if we need to sync
+        self.increment_and_maybe_sync().await?;
+        let replaced = replaced
+            .into_iter()
+            .filter(|e| !self.config.expire_on_access || !e.is_expired_at(self.clock.now()))
+            .max_by_key(|e| e.timestamp)
+            .map(|e| e.value);
+        Ok((replaced, evicted))
+    }
+
+    /// Put a value into the cache, reporting whether it replaced a value and
+    /// which keys were evicted to make room
+    ///
+    /// The evicted keys can include `key` itself, when an admission policy
+    /// such as [`EvictionPolicy::TinyLfu`](crate::EvictionPolicy::TinyLfu)
+    /// turns the new value away.
+    pub async fn put_reporting(&self, key: K, value: V) -> Result<PutOutcome<K>> {
+        let (replaced, evicted_keys) = self.store_entry(self.new_entry(key, value)).await?;
+        Ok(PutOutcome {
+            evicted_keys,
+            replaced_existing: replaced.is_some(),
+        })
+    }
+
+    /// Put a value into the cache, returning the latest value it replaced
+    ///
+    /// This is `put` for callers that need the previous value, for example
+    /// to emit change events. Expired entries are not returned when
+    /// `expire_on_access` is enabled.
+    pub async fn replace(&self, key: K, value: V) -> Result<Option<V>> {
+        self.put_entry(self.new_entry(key, value)).await
+    }
+
+    /// Put many values into the cache at once
+    ///
+    /// Each key follows the same replace semantics as `put`, but the write
+    /// locks are taken once, eviction runs once after all inserts and the
+    /// operation only counts once towards `sync_interval`.
+    pub async fn put_many(&self, items: Vec<(K, V)>) -> Result<()> {
+        self.ensure_writable()?;
+        self.insert_many(items).await
+    }
+
+    /// `put_many` without the read-only check
+    async fn insert_many(&self, items: Vec<(K, V)>) -> Result<()> {
+        {
+            let mut shards = self.entries.write_all().await;
+            for (key, value) in items {
+                let shard = self.entries.index_of(&key);
+                self.replace_entry(shard, &mut shards[shard], self.new_entry(key, value));
+            }
+            for (shard, entries) in shards.iter_mut().enumerate() {
+                self.evict_if_needed(shard, entries).await;
+            }
+        }
+
+        self.increment_and_maybe_sync().await
+    }
+
+    /// Put every pair of `items` into the cache, as `put_many` does
+    pub async fn extend<I>(&self, items: I) -> Result<()>
+    where
+        I: IntoIterator<Item = (K, V)>,
+    {
+        self.put_many(items.into_iter().collect()).await
+    }
+
+    /// Create a cache like [`Cache::new`] and put every pair of `items` into
+    /// it
+    ///
+    /// Items go in after any entries loaded on startup, replacing them for
+    /// the same keys. This also fills caches configured as read-only.
+    pub async fn from_entries<I>(config: CacheConfig, backend: B, items: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = (K, V)>,
+    {
+        let cache = Self::new(config, backend).await?;
+        cache.insert_many(items.into_iter().collect()).await?;
+        Ok(cache)
+    }
+
+    /// Replace the value for `key` only if its latest value equals `expected`
+    ///
+    /// The comparison and the write happen under one write lock, so no other
+    /// writer can slip in between. On success the key's entries are replaced
+    /// just as `put` would. Returns whether the swap happened; a missing key
+    /// never matches.
+    pub async fn replace_if(&self, key: &K, expected: &V, new: V) -> Result<bool>
+    where
+        V: PartialEq,
+    {
+        self.ensure_writable()?;
+        {
+            let shard = self.entries.index_of(key);
+            let mut entries = self.entries.shard(shard).write().await;
+            let matches = self
+                .live_bucket(&mut entries, key)
+                .and_then(|bucket| self.latest(bucket))
+                .is_some_and(|latest| latest.value == *expected);
+            if !matches {
+                return Ok(false);
+            }
+
+            self.replace_entry(shard, &mut entries, self.new_entry(key.clone(), new));
+            self.evict_if_needed(shard, &mut entries).await;
+        }
+
+        self.increment_and_maybe_sync().await?;
+        Ok(true)
+    }
+
+    /// Apply `f` to the metadata of the latest entry for `key` in place
+    ///
+    /// The entry keeps its value, timestamps and access statistics. Eviction
+    /// runs afterwards in case the new metadata reports a larger size.
+    /// Returns whether the key had an entry to update.
+    pub async fn update_metadata<F>(&self, key: &K, mut f: F) -> Result<bool>
+    where
+        F: FnMut(&mut M),
+    {
+        self.ensure_writable()?;
+        {
+            let shard = self.entries.index_of(key);
+            let mut entries = self.entries.shard(shard).write().await;
+            let Some(latest) = self
+                .live_bucket(&mut entries, key)
+                .and_then(|bucket| self.latest_mut(bucket))
+            else {
+                return Ok(false);
+            };
+
+            f(&mut latest.metadata);
+            self.mark_dirty(key);
+            self.evict_if_needed(shard, &mut entries).await;
+        }
+
+        self.increment_and_maybe_sync().await?;
+        Ok(true)
+    }
+
+    /// Record an access to the latest entry for `key` without cloning it
+    ///
+    /// When `new_ttl` is given the entry expires that long from now. Returns
+    /// whether the key had an entry to touch.
+    pub async fn touch(&self, key: &K, new_ttl: Option<chrono::Duration>) -> Result<bool> {
+        self.ensure_writable()?;
+        {
+            let shard = self.entries.index_of(key);
+            let mut entries = self.entries.shard(shard).write().await;
+            let Some(latest) = self
+                .live_bucket(&mut entries, key)
+                .and_then(|bucket| self.latest_mut(bucket))
+            else {
+                return Ok(false);
+            };
+
+            self.record_access(latest);
+            if let Some(ttl) = new_ttl {
+                latest.expiry = Some(self.clock.now() + ttl);
+                self.mark_dirty(key);
+            }
+            self.eviction_strategies[shard].on_access(key);
+        }
+
+        // Only a new expiry is worth persisting
+        if new_ttl.is_some() {
+            self.increment_and_maybe_sync().await?;
+        }
+        Ok(true)
+    }
+
+    /// Remove every entry for which `pred` returns true
+    ///
+    /// Keys left without entries are dropped from the cache and removed from
+    /// the backend; keys that keep some entries reach the backend with the
+    /// next sync, as do all removals while the circuit breaker is open.
+    /// Returns the number of entries removed.
+    pub async fn remove_where<F>(&self, pred: F) -> Result<usize>
+    where
+        F: Fn(&K, &CacheEntry<K, V, M>) -> bool,
+    {
+        self.ensure_writable()?;
+        let mut shards = self.entries.write_all().await;
+        let mut removed = 0;
+        let mut emptied = Vec::new();
+        for entries in shards.iter_mut() {
+            let start = emptied.len();
+            for (key, bucket) in entries.iter_mut() {
+                let before = bucket.len();
+                bucket.retain(|entry| !pred(key, entry));
+                if bucket.len() < before {
+                    self.mark_dirty(key);
+                    self.notify_watchers(key, Some(bucket));
+                }
+                removed += before - bucket.len();
+                if bucket.is_empty() {
+                    emptied.push(key.clone());
+                }
+            }
+            for key in &emptied[start..] {
+                entries.remove(key);
+            }
+        }
+        drop(shards);
+
+        // A put racing a removal may even be saved before the backend
+        // removes the key, so keys back in the cache by then are marked as
+        // changed again for the next save to restore
+        for key in &emptied {
+            // Emptied keys are already marked as changed, which is all an
+            // open breaker leaves to do
+            if !self.breaker.allows_call() {
+                break;
+            }
+            self.call_backend(|| self.backend.remove(key)).await?;
+            let entries = self.entries.for_key(key).read().await;
+            if entries.contains_key(key) {
+                self.mark_dirty(key);
+            }
+        }
+
+        if removed > 0 {
+            self.increment_and_maybe_sync().await?;
+        }
+        Ok(removed)
+    }
+
+    /// Remove every entry created more than `max_age` ago, whatever its TTL
+    ///
+    /// Age is measured from each entry's `timestamp`, as by
+    /// [`CacheEntry::age`]. Removals reach the backend as described for
+    /// [`Cache::remove_where`]. Returns the number of entries removed.
+    pub async fn purge_older_than(&self, max_age: chrono::Duration) -> Result<usize> {
+        let now = self.clock.now();
+        self.remove_where(|_, entry| entry.age_at(now) > max_age)
+            .await
+    }
+
+    /// Get the latest values for many keys, locking each shard once
+    ///
+    /// The returned vector is in the same order as `keys`.
+    pub async fn get_many(&self, keys: &[K]) -> Result<Vec<Option<V>>> {
+        let values = self
+            .lookup_many(keys, |bucket| {
+                self.latest(bucket).map(|e| {
+                    self.record_access(e);
+                    e.value.clone()
+                })
+            })
+            .await;
+        for (key, value) in keys.iter().zip(&values) {
+            self.record_lookup(key, value.is_some());
+        }
+        Ok(values)
+    }
+
+    /// Search entries based on a query
+    ///
+    /// Matches are ordered newest first, so the query's offset and limit page
+    /// through a stable sequence.
+    pub async fn search<Q>(&self, query: &Q) -> Vec<CacheEntry<K, V, M>>
+    where
+        CacheEntry<K, V, M>: Searchable<Query = Q>,
+    {
+        let shards = self.entries.read_all().await;
+        let mut matches: Vec<_> = shards
+            .iter()
+            .flat_map(|entries| entries.values())
+            .flat_map(|v| v.iter())
+            .filter(|entry| entry.matches(query))
+            .collect();
+        matches.sort_by_key(|entry| std::cmp::Reverse(entry.timestamp));
+
+        let limit = CacheEntry::query_limit(query).unwrap_or(usize::MAX);
+        matches
+            .into_iter()
+            .skip(CacheEntry::query_offset(query))
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+
+    /// Call `f` with each entry matching `query` until it returns
+    /// [`ControlFlow::Break`], returning how many entries it was called with
+    ///
+    /// Unlike [`Cache::search`], nothing is cloned or sorted, so stopping
+    /// early skips the work of finding the remaining matches. Entries come in
+    /// no particular order and the query's offset and limit are ignored.
+    /// Each shard is read-locked while its entries are visited, so `f` must
+    /// not write to the cache.
+    pub async fn search_for_each<Q, F>(&self, query: &Q, mut f: F) -> usize
+    where
+        CacheEntry<K, V, M>: Searchable<Query = Q>,
+        F: FnMut(&CacheEntry<K, V, M>) -> ControlFlow<()>,
+    {
+        let mut visited = 0;
+        for index in 0..self.entries.count() {
+            let entries = self.entries.shard(index).read().await;
+            for entry in entries.values().flatten() {
+                if !entry.matches(query) {
+                    continue;
+                }
+                visited += 1;
+                if f(entry).is_break() {
+                    return visited;
+                }
+            }
+        }
+        visited
+    }
+
+    /// Latest entry of a bucket, skipping expired entries when
+    /// `expire_on_access` is enabled
+    fn visible_latest<'a>(
+        &self,
+        bucket: &'a [CacheEntry<K, V, M>],
+    ) -> Option<&'a CacheEntry<K, V, M>> {
+        let visible = |e: &&CacheEntry<K, V, M>| {
+            !self.config.expire_on_access || !e.is_expired_at(self.clock.now())
+        };
+        if self.config.keep_buckets_sorted {
+            bucket.iter().rev().find(visible)
+        } else {
+            bucket.iter().filter(visible).max_by_key(|e| e.timestamp)
+        }
+    }
+
+    /// Get every key currently in the cache
+    ///
+    /// Access statistics are left untouched, and the order is unspecified.
+    pub async fn keys(&self) -> Vec<K> {
+        let shards = self.entries.read_all().await;
+        shards
+            .iter()
+            .flat_map(|entries| entries.iter())
+            .filter(|(_, bucket)| self.visible_latest(bucket).is_some())
+            .map(|(key, _)| key.clone())
+            .collect()
+    }
+
+    /// Get the latest entry for every key in the cache
+    ///
+    /// Like `keys`, this is meant for inspection and does not count as an
+    /// access for eviction or statistics.
+    pub async fn iter(&self) -> Vec<(K, CacheEntry<K, V, M>)> {
+        let shards = self.entries.read_all().await;
+        shards
+            .iter()
+            .flat_map(|entries| entries.iter())
+            .filter_map(|(key, bucket)| {
+                self.visible_latest(bucket)
+                    .map(|entry| (key.clone(), entry.clone()))
+            })
+            .collect()
+    }
+
+    /// Whether any key's latest value equals `value`
+    ///
+    /// This scans every key, so it is O(n) in the size of the cache: fine for
+    /// deduplication checks and small caches, too slow for hot paths. Access
+    /// statistics are left untouched.
+    pub async fn contains_value(&self, value: &V) -> bool
+    where
+        V: PartialEq,
+    {
+        let shards = self.entries.read_all().await;
+        let mut buckets = shards.iter().flat_map(|entries| entries.values());
+        buckets.any(|bucket| {
+            self.visible_latest(bucket)
+                .is_some_and(|e| e.value == *value)
+        })
+    }
+
+    /// Get every key whose latest value equals `value`
+    ///
+    /// Like `contains_value`, this scans the whole cache. The order is
+    /// unspecified.
+    pub async fn find_keys_by_value(&self, value: &V) -> Vec<K>
+    where
+        V: PartialEq,
+    {
+        let shards = self.entries.read_all().await;
+        shards
+            .iter()
+            .flat_map(|entries| entries.iter())
+            .filter(|(_, bucket)| {
+                self.visible_latest(bucket)
+                    .is_some_and(|e| e.value == *value)
+            })
+            .map(|(key, _)| key.clone())
+            .collect()
+    }
+
+    /// Aggregate statistics for a slice of cache entries
+    fn entry_vec_stats(
+        entry_vec: &[CacheEntry<K, V, M>],
+        now: DateTime<Utc>,
+    ) -> (usize, u64, usize, usize) {
+        entry_vec
+            .iter()
+            .fold((0, 0, 0, 0), |(count, access, expired, bytes), entry| {
+                (
+                    count + 1,
+                    access + entry.access_count(),
+                    expired + usize::from(entry.is_expired_at(now)),
+                    bytes + entry.estimated_size_bytes(),
+                )
+            })
+    }
+
+    /// Get cache statistics
+    pub async fn get_stats(&self) -> CacheStats {
+        let shards = self.entries.read_all().await;
+        let total_keys = shards.iter().map(|entries| entries.len()).sum();
+
+        let now = self.clock.now();
+        let (total_entries, total_access_count, expired_count, entry_bytes) = shards
+            .iter()
+            .flat_map(|entries| entries.values())
+            .fold((0, 0, 0, 0), |acc, entry_vec| {
+                let (e, a, exp, b) = Self::entry_vec_stats(entry_vec, now);
+                (acc.0 + e, acc.1 + a, acc.2 + exp, acc.3 + b)
+            });
+        let (oldest_entry, newest_entry) = shards
+            .iter()
+            .flat_map(|entries| entries.values().flatten())
+            .map(|entry| entry.timestamp)
+            .fold((None, None), |(oldest, newest), timestamp| {
+                (
+                    Some(oldest.map_or(timestamp, |t: DateTime<Utc>| t.min(timestamp))),
+                    Some(newest.map_or(timestamp, |t: DateTime<Utc>| t.max(timestamp))),
+                )
+            });
+        let savings = self
+            .dedup
+            .as_ref()
+            .map_or_else(DedupSavings::default, |dedup| {
+                dedup.savings(
+                    &mut shards
+                        .iter()
+                        .flat_map(|entries| entries.values().flatten())
+                        .map(|entry| &entry.value),
+                )
+            });
+        let average_access_count = if total_entries == 0 {
+            0.0
+        } else {
+            total_access_count as f64 / total_entries as f64
+        };
+
+        CacheStats {
+            total_entries,
+            total_keys,
+            total_access_count,
+            expired_count,
+            memory_usage_bytes: entry_bytes,
+            oldest_entry,
+            newest_entry,
+            average_access_count,
+            evictions: self.eviction_count.load(Ordering::Relaxed),
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            load_errors: self.load_errors.load(Ordering::Relaxed),
+            backend_healthy: self.breaker.is_closed(),
+            deduplicated_values: savings.shared_values,
+            dedup_bytes_saved: savings.bytes_saved,
+        }
+    }
+
+    /// Save the cache to the storage backend now
+    ///
+    /// Dropping the last handle to a cache also saves when `save_on_drop` is
+    /// set, but that save can only block on a multi-thread runtime; elsewhere
+    /// it runs as a background task that may not finish before the process
+    /// exits. Call `flush` before shutting down to be sure everything is
+    /// persisted.
+    pub async fn flush(&self) -> Result<()> {
+        self.sync().await
+    }
+
+    /// Save the cache to the storage backend regardless of `sync_interval`
+    ///
+    /// The operation counter restarts from zero, and the save waits its turn
+    /// behind any background save already in progress. This is a no-op when
+    /// persistence is disabled.
+    pub async fn sync(&self) -> Result<()> {
+        self.operation_count.store(0, Ordering::Release);
+        self.save_to_storage().await
+    }
+
+    /// Serialize every entry into one self-contained snapshot
+    ///
+    /// The snapshot uses `CacheConfig::snapshot_format` and can be restored
+    /// with `import_snapshot` into any cache with the same key, value and
+    /// metadata types, whatever its backend.
+    pub async fn export_snapshot(&self) -> Result<Vec<u8>>
+    where
+        K: Serialize,
+        V: Serialize,
+        M: Serialize,
+    {
+        let shards = self.entries.read_all().await;
+        // Buckets carry their key in every entry, so keys of any type round
+        // trip, even through formats that only support string map keys
+        let buckets: Vec<&Vec<CacheEntry<K, V, M>>> =
+            shards.iter().flat_map(|entries| entries.values()).collect();
+        self.snapshot_format()?.serialize(&buckets)
+    }
+
+    /// Restore entries from a snapshot made by `export_snapshot`
+    ///
+    /// With `merge`, keys in both the snapshot and the cache are resolved
+    /// with the default [`MergePolicy`] and other keys are kept; see
+    /// `merge_snapshot` to pick the policy. Without it, the cache and its
+    /// backend are cleared first. Capacity limits apply to the result, and a
+    /// snapshot that fails to decode leaves the cache untouched.
+    pub async fn import_snapshot(&self, data: &[u8], merge: bool) -> Result<()>
+    where
+        K: DeserializeOwned,
+        V: DeserializeOwned,
+        M: DeserializeOwned,
+    {
+        self.ensure_writable()?;
+        if merge {
+            return self.merge_snapshot(data, MergePolicy::default()).await;
+        }
+        let buckets = self.decode_snapshot(data)?;
+        {
+            let mut shards = self.entries.write_all().await;
+            for entries in shards.iter_mut() {
+                entries.clear();
+            }
+            self.call_backend(|| self.backend.clear()).await?;
+            for (key, bucket) in buckets {
+                let shard = self.entries.index_of(&key);
+                self.eviction_strategies[shard].on_insert(&key);
+                self.mark_dirty(&key);
+                shards[shard].insert(key, self.ordered(bucket));
+            }
+            for (shard, entries) in shards.iter_mut().enumerate() {
+                self.evict_if_needed(shard, entries).await;
+            }
+        }
+        self.increment_and_maybe_sync().await
+    }
+
+    /// Merge a snapshot made by `export_snapshot` into the cache, resolving
+    /// keys present on both sides with `policy`
+    pub async fn merge_snapshot(&self, data: &[u8], policy: MergePolicy) -> Result<()>
+    where
+        K: DeserializeOwned,
+        V: DeserializeOwned,
+        M: DeserializeOwned,
+    {
+        self.ensure_writable()?;
+        let buckets = self.decode_snapshot(data)?;
+        {
+            let mut shards = self.entries.write_all().await;
+            for (key, bucket) in buckets {
+                let shard = self.entries.index_of(&key);
+                self.eviction_strategies[shard].on_insert(&key);
+                self.mark_dirty(&key);
+                match shards[shard].entry(key) {
+                    std::collections::hash_map::Entry::Vacant(slot) => {
+                        slot.insert(self.ordered(bucket));
+                    }
+                    std::collections::hash_map::Entry::Occupied(mut slot) => {
+                        self.merge_bucket(slot.get_mut(), bucket, policy);
+                    }
+                }
+            }
+            for (shard, entries) in shards.iter_mut().enumerate() {
+                self.evict_if_needed(shard, entries).await;
+            }
+        }
+        self.increment_and_maybe_sync().await
+    }
+
+    /// Resolve an imported bucket against the one already stored for its key
+    fn merge_bucket(
+        &self,
+        existing: &mut Vec<CacheEntry<K, V, M>>,
+        imported: Vec<CacheEntry<K, V, M>>,
+        policy: MergePolicy,
+    ) {
+        let newest = |bucket: &[CacheEntry<K, V, M>]| bucket.iter().map(|e| e.timestamp).max();
+        match policy {
+            MergePolicy::KeepExisting => {}
+            MergePolicy::Overwrite => *existing = self.ordered(imported),
+            MergePolicy::KeepNewestByTimestamp => {
+                if newest(&imported) > newest(existing) {
+                    *existing = self.ordered(imported);
+                }
+            }
+            MergePolicy::Concat => {
+                existing.extend(imported);
+                *existing = self.ordered(std::mem::take(existing));
+                while existing.len() > self.config.max_entries_per_key {
+                    let index = Self::per_key_victim(self.config.per_key_eviction, existing);
+                    existing.remove(index);
+                }
+            }
+        }
+    }
+
+    /// Decode a snapshot into buckets keyed by their entries' key
+    fn decode_snapshot(&self, data: &[u8]) -> Result<crate::storage::EntryMap<K, V, M>>
+    where
+        K: DeserializeOwned,
+        V: DeserializeOwned,
+        M: DeserializeOwned,
+    {
+        let buckets: Vec<Vec<CacheEntry<K, V, M>>> = self.snapshot_format()?.deserialize(data)?;
+        Ok(buckets
+            .into_iter()
+            .filter_map(|bucket| Some((bucket.first()?.key.clone(), bucket)))
+            .collect())
+    }
+
+    /// Get the format snapshots are written and read in
+    fn snapshot_format(&self) -> Result<crate::storage::SerializationFormat> {
+        match self.config.snapshot_format {
+            Some(format) => Ok(format),
+            None => crate::storage::SerializationFormat::default_format(),
+        }
+    }
+
+    /// Get the storage backend, for operations the cache does not expose
+    ///
+    /// Writing to the backend directly bypasses the in-memory entries, which
+    /// overwrite it on the next sync.
+    pub fn backend(&self) -> &B {
+        &self.backend
+    }
+
+    /// Compact the storage backend
+    ///
+    /// Forwards to [`StorageBackend::compact`], which is a no-op for
+    /// backends without anything to compact. A read-only cache leaves the
+    /// backend alone and fails with [`CacheError::ReadOnly`].
+    pub async fn compact(&self) -> Result<()> {
+        self.ensure_writable()?;
+        self.backend.compact().await
+    }
+
+    /// Load only `keys` from the storage backend into memory
+    ///
+    /// Pair this with `load_on_startup = false` to warm a subset of a large
+    /// store. Each loaded key replaces whatever the cache held for it; keys
+    /// the backend does not have are left alone. With a backend whose save
+    /// replaces everything it holds, such as [`MemoryBackend`](crate::MemoryBackend), the next save
+    /// drops the keys that were not loaded.
+    pub async fn load_keys(&self, keys: &[K]) -> Result<()> {
+        self.ensure_writable()?;
+        let mut loaded = Vec::new();
+        for key in keys {
+            if let Some(entry_vec) = self.call_backend(|| self.backend.load_key(key)).await? {
+                loaded.push((key.clone(), entry_vec));
+            }
+        }
+
+        let mut shards = self.entries.write_all().await;
+        let mut touched = HashSet::new();
+        for (key, entry_vec) in loaded {
+            let shard = self.entries.index_of(&key);
+            self.eviction_strategies[shard].on_insert(&key);
+            shards[shard].insert(key, self.ordered(entry_vec));
+            touched.insert(shard);
+        }
+        for shard in touched {
+            self.evict_if_needed(shard, &mut shards[shard]).await;
+        }
+        Ok(())
+    }
+
+    /// Save cache to storage backend
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "cache.save",
+            level = "debug",
+            skip_all,
+            fields(keys = tracing::field::Empty),
+            err
+        )
+    )]
+    async fn save_to_storage(&self) -> Result<()> {
+        // A read-only cache never has anything new to save
+        if !self.config.persistence.enabled || self.config.read_only {
+            return Ok(());
+        }
+
+        let shared = self.config.persistence.save_limit.as_deref();
+        let Some(_permits) = acquire_save_permits(&self.save_semaphore, shared).await else {
+            return Err(CacheError::StorageBackend(
+                "save limit semaphore was closed".to_string(),
+            ));
+        };
+        save_shards(
+            self.backend.as_ref(),
+            &self.entries,
+            &self.dirty,
+            false,
+            self.config.retry.as_ref(),
+            &self.breaker,
+        )
+        .await
+    }
+
+    /// Save only the keys that changed since they were last saved
+    ///
+    /// Puts, removals, evictions, expiry and metadata updates mark a key as
+    /// changed, and every successful save, full or not, unmarks the keys it
+    /// wrote. The marked keys are written with
+    /// [`StorageBackend::save_keys`], so backends that can write single keys
+    /// do I/O for those alone, while the rest fall back to a full save. Reads
+    /// mark nothing, so access statistics of unchanged keys are only
+    /// persisted by full saves. This is a no-op when persistence is disabled
+    /// or nothing changed.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "cache.save_dirty",
+            level = "debug",
+            skip_all,
+            fields(keys = tracing::field::Empty),
+            err
+        )
+    )]
+    pub async fn save_dirty(&self) -> Result<()> {
+        if !self.config.persistence.enabled || self.config.read_only {
+            return Ok(());
+        }
+
+        let shared = self.config.persistence.save_limit.as_deref();
+        let Some(_permits) = acquire_save_permits(&self.save_semaphore, shared).await else {
+            return Err(CacheError::StorageBackend(
+                "save limit semaphore was closed".to_string(),
+            ));
+        };
+        save_shards(
+            self.backend.as_ref(),
+            &self.entries,
+            &self.dirty,
+            true,
+            self.config.retry.as_ref(),
+            &self.breaker,
+        )
+        .await
+    }
+
+    /// Call the backend through the circuit breaker, retrying as configured
+    async fn call_backend<T, F, Fut>(&self, op: F) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        self.breaker
+            .call(|| with_retry(self.config.retry.as_ref(), op))
+            .await
+    }
+
+    /// Load cache from storage backend
+    ///
+    /// Keys are inserted as the backend yields them. If the backend fails
+    /// partway and the error is retried, loading starts over from an empty
+    /// cache; otherwise the keys read before the error stay in the cache.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "cache.load",
+            level = "debug",
+            skip_all,
+            fields(keys = tracing::field::Empty),
+            err
+        )
+    )]
+    async fn load_from_storage(&self) -> Result<()> {
+        if !self.config.persistence.enabled {
+            return Ok(());
+        }
+
+        let mut shards = self.entries.write_all().await;
+        let retry = self.config.retry.as_ref();
+        let load = async {
+            let mut attempt = 1;
+            loop {
+                for entries in shards.iter_mut() {
+                    entries.clear();
+                }
+                let mut loaded = self.backend.load_stream();
+                let result = async {
+                    while let Some((key, entry_vec)) = loaded.try_next().await? {
+                        shards[self.entries.index_of(&key)].insert(key, self.ordered(entry_vec));
+                    }
+                    Ok(())
+                }
+                .await;
+                match result {
+                    Err(error) => match retry_delay(retry, &error, attempt) {
+                        Some(delay) => {
+                            tokio::time::sleep(delay).await;
+                            attempt += 1;
+                        }
+                        None => return Err(error),
+                    },
+                    Ok(()) => return Ok(()),
+                }
+            }
+        };
+        self.breaker.call(|| load).await?;
+        trace_record!("keys", shards.iter().map(|e| e.len()).sum::<usize>());
+        Ok(())
+    }
+
+    /// Increment operation count and sync if needed
+    ///
+    /// Without persistence there is nothing to sync, so no background save
+    /// is ever spawned and the cache needs no Tokio runtime. In write-through
+    /// mode every write saves the keys it changed before returning instead.
+    /// While the circuit breaker is open, writes stay in memory until a save
+    /// after the cooldown picks them up.
+    async fn increment_and_maybe_sync(&self) -> Result<()> {
+        if !self.config.persistence.enabled {
+            return Ok(());
+        }
+        if self.config.mode == CacheMode::WriteThrough && self.breaker.allows_call() {
+            return self.save_dirty().await;
+        }
+        let Some(ops) = self.config.persistence.effective_sync_strategy().ops() else {
+            // Only the sync timer reads the count
+            self.operation_count.fetch_add(1, Ordering::AcqRel);
+            return Ok(());
+        };
+
+        // Whichever write reaches the threshold resets the count in the same
+        // atomic step, so each crossing spawns exactly one save
+        let previous = self
+            .operation_count
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |count| {
+                Some(if count + 1 >= ops { 0 } else { count + 1 })
+            })
+            .unwrap_or_else(|count| count);
+        if previous + 1 >= ops && self.breaker.allows_call() {
+            // Spawn background save
+            let cache = self.clone();
+            tokio::spawn(async move {
+                let _ = cache.save_to_storage().await;
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Save every `period` while there are writes the last save missed
+    ///
+    /// The timer only holds a weak reference to the entries between ticks,
+    /// so it does not keep the cache alive, and it holds no handle, so the
+    /// last handle's drop saves even while a tick is saving. It ends at the
+    /// first tick after the cache is gone.
+    fn spawn_sync_timer(&self, period: std::time::Duration) {
+        let entries = Arc::downgrade(&self.entries);
+        let backend = Arc::clone(&self.backend);
+        let save_semaphore = Arc::clone(&self.save_semaphore);
+        let save_limit = self.config.persistence.save_limit.clone();
+        let operation_count = Arc::clone(&self.operation_count);
+        let dirty = Arc::clone(&self.dirty);
+        let retry = self.config.retry;
+        let breaker = Arc::clone(&self.breaker);
+
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(period);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            // The first tick completes immediately
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                let Some(entries) = entries.upgrade() else {
+                    return;
+                };
+                if operation_count.swap(0, Ordering::AcqRel) == 0 {
+                    continue;
+                }
+
+                let Some(_permits) =
+                    acquire_save_permits(&save_semaphore, save_limit.as_deref()).await
+                else {
+                    return;
+                };
+                let _ = save_shards(
+                    backend.as_ref(),
+                    &entries,
+                    &dirty,
+                    false,
+                    retry.as_ref(),
+                    &breaker,
+                )
+                .await;
+            }
+        });
+    }
+}
+
+/// Values shared behind an [`Arc`]
+///
+/// Every read of a `Cache<K, V>` clones the value it returns, which is
+/// costly for values of megabytes. Storing `Arc<T>` instead keeps one copy
+/// of each value and makes a read a reference count bump; [`ArcCache`]
+/// names that type. Backends persist `Arc<T>` exactly as they would `T`,
+/// so existing data loads unchanged.
+impl<K, T, M, B> Cache<K, Arc<T>, M, B>
+where
+    K: CacheKey,
+    T: Send + Sync + 'static,
+    M: EntryMetadata + Default,
+    B: StorageBackend<Key = K, Value = Arc<T>, Metadata = M>,
+{
+    /// Get the latest value for a key without copying it
+    ///
+    /// This counts as an access like [`Cache::get_latest`], but clones only
+    /// the value's `Arc`, not the whole entry. Like `get_latest`, it does not
+    /// fall back to the loader or backend on a miss.
+    pub async fn get_arc(&self, key: &K) -> Option<Arc<T>> {
+        trace_key!(self, key);
+        let value = self
+            .lookup(key, |bucket| {
+                self.latest(bucket).map(|e| {
+                    self.record_access(e);
+                    Arc::clone(&e.value)
+                })
+            })
+            .await;
+        self.record_lookup(key, value.is_some());
+        value
+    }
+
+    /// Store identical values once, however many keys hold them
+    ///
+    /// Each value put from now on is hashed by its JSON form; if an equal
+    /// value is already stored, the entry gets that value's `Arc` and the new
+    /// copy is dropped. The cache only keeps weak references for this, so a
+    /// shared value is freed once every key holding it is removed or
+    /// evicted. [`CacheStats::deduplicated_values`] and
+    /// [`CacheStats::dedup_bytes_saved`] report how much is shared. Values
+    /// loaded from the backend when the cache was created are not
+    /// deduplicated.
+    #[cfg(feature = "json-serialization")]
+    pub fn with_value_dedup(mut self) -> Self
+    where
+        T: Serialize + PartialEq,
+    {
+        self.dedup = Some(Arc::new(crate::dedup::ArcInterner::new()));
+        self
+    }
+}
+
+/// Cache holding each value behind an [`Arc`], so reads do not copy values
+pub type ArcCache<K, V, M = (), B = crate::backends::memory::MemoryBackend<K, Arc<V>, M>> =
+    Cache<K, Arc<V>, M, B>;
+
+impl_cache_common!(
+    Clone,
+    fn clone(&self) -> Self {
+        Self {
+            entries: Arc::clone(&self.entries),
+            config: self.config.clone(),
+            backend: Arc::clone(&self.backend),
+            save_semaphore: Arc::clone(&self.save_semaphore),
+            operation_count: Arc::clone(&self.operation_count),
+            eviction_strategies: Arc::clone(&self.eviction_strategies),
+            on_evict: self.on_evict.clone(),
+            eviction_count: Arc::clone(&self.eviction_count),
+            hits: Arc::clone(&self.hits),
+            misses: Arc::clone(&self.misses),
+            load_errors: Arc::clone(&self.load_errors),
+            loader: self.loader.clone(),
+            loads_in_flight: Arc::clone(&self.loads_in_flight),
+            events: self.events.clone(),
+            watchers: Arc::clone(&self.watchers),
+            breaker: Arc::clone(&self.breaker),
+            clock: Arc::clone(&self.clock),
+            value_size: self.value_size,
+            dedup: self.dedup.clone(),
+            ttl_jitter_seed: self.ttl_jitter_seed.clone(),
+            dirty: Arc::clone(&self.dirty),
+            handles: Arc::clone(&self.handles),
+            #[cfg(feature = "tracing")]
+            key_display: self.key_display,
+        }
+    }
+);
+
+impl_cache_common!(#[async_trait] AsyncCache<K, V>,
+    type Error = CacheError;
+
+    async fn get(&self, key: &K) -> std::result::Result<Option<V>, Self::Error> {
+        self.get_value(key).await
+    }
+
+    async fn put(&self, key: K, value: V) -> std::result::Result<(), Self::Error> {
+        // For AsyncCache trait, replace existing entries rather than add
+        self.put_value(key, value).await
+    }
+
+    async fn remove(&self, key: &K) -> std::result::Result<Option<V>, Self::Error> {
+        self.remove_key(key).await
+    }
+
+    async fn clear(&self) -> std::result::Result<(), Self::Error> {
+        self.clear_all().await
+    }
+
+    async fn contains(&self, key: &K) -> std::result::Result<bool, Self::Error> {
+        Ok(self.contains_key(key).await)
+    }
+
+    async fn len(&self) -> std::result::Result<usize, Self::Error> {
+        Ok(self.entry_count().await)
+    }
+
+    async fn get_or_insert_with<F, Fut>(&self, key: K, f: F) -> std::result::Result<V, Self::Error>
+    where
+        F: FnOnce() -> Fut + Send,
+        Fut: Future<Output = V> + Send,
+    {
+        if self.config.read_only {
+            return match self.get_value(&key).await? {
+                Some(value) => Ok(value),
+                None => Err(CacheError::ReadOnly),
+            };
+        }
+        // Hold the write lock while computing so only one closure runs per key
+        let shard = self.entries.index_of(&key);
+        let mut entries = self.entries.shard(shard).write().await;
+        if let Some(entry) = self
+            .live_bucket(&mut entries, &key)
+            .and_then(|entries| self.latest_mut(entries))
+        {
+            self.record_access(entry);
+            let value = entry.value.clone();
+            self.eviction_strategies[shard].on_access(&key);
+            self.record_lookup(&key, true);
+            return Ok(value);
+        }
+        self.record_lookup(&key, false);
+
+        // The bucket is only created once the value exists, so a panicking
+        // closure never leaves an empty bucket behind
+        let value = f().await;
+        self.replace_entry(shard, &mut entries, self.new_entry(key, value.clone()));
+        self.evict_if_needed(shard, &mut entries).await;
+        drop(entries);
+
+        self.increment_and_maybe_sync().await?;
+        Ok(value)
+    }
+);
+
+impl_cache_common!(
+    Drop,
+    fn drop(&mut self) {
+        if !(self.config.persistence.enabled && self.config.persistence.save_on_drop)
+            || self.config.read_only
+        {
+            return;
+        }
+        // Clones share the same entries, so only the last handle saves.
+        // Handles are counted apart from the entries, which the sync timer
+        // also holds while it saves.
+        if Arc::strong_count(&self.handles) > 1 {
+            return;
+        }
+        let Ok(handle) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+
+        let entries = self.entries.clone();
+        let backend = self.backend.clone();
+        let save_semaphore = Arc::clone(&self.save_semaphore);
+        let save_limit = self.config.persistence.save_limit.clone();
+        let dirty = Arc::clone(&self.dirty);
+        let retry = self.config.retry;
+        let breaker = Arc::clone(&self.breaker);
+        let save = async move {
+            // Wait for an in-flight background save rather than interleave
+            // with it
+            let Some(_permits) = acquire_save_permits(&save_semaphore, save_limit.as_deref()).await
+            else {
+                return;
+            };
+            let _ = save_shards(
+                backend.as_ref(),
+                &entries,
+                &dirty,
+                false,
+                retry.as_ref(),
+                &breaker,
+            )
+            .await;
+        };
+
+        // Block until saved where the runtime allows it, otherwise fall back
+        // to a task that may not complete before shutdown. Wasm has no
+        // multi-threaded runtime to block on.
+        match handle.runtime_flavor() {
+            #[cfg(not(target_arch = "wasm32"))]
+            tokio::runtime::RuntimeFlavor::MultiThread => {
+                let timeout = self.config.persistence.drop_flush_timeout;
+                tokio::task::block_in_place(|| match timeout {
+                    Some(timeout) => {
+                        if handle
+                            .block_on(tokio::time::timeout(timeout, save))
+                            .is_err()
+                        {
+                            #[cfg(feature = "tracing")]
+                            tracing::warn!(?timeout, "cache save on drop timed out");
+                            #[cfg(not(feature = "tracing"))]
+                            eprintln!("Cache save on drop timed out after {timeout:?}");
+                        }
+                    }
+                    None => handle.block_on(save),
+                });
+            }
+            _ => {
+                handle.spawn(save);
+            }
+        }
+    }
+);
+
+/// What a [`Cache::put_reporting`] did besides storing the value
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PutOutcome<K> {
+    /// Keys that lost entries to eviction, in the order they were evicted
+    pub evicted_keys: Vec<K>,
+    /// Whether the key already held a value, not counting expired entries
+    /// when `expire_on_access` is enabled
+    pub replaced_existing: bool,
+}
 
 /// Cache statistics
 #[derive(Debug, Clone, Default)]
@@ -392,146 +2604,2680 @@ pub struct CacheStats {
     pub expired_count: usize,
     /// Approximate memory usage in bytes
     pub memory_usage_bytes: usize,
+    /// Timestamp of the oldest entry, expired entries included
+    pub oldest_entry: Option<DateTime<Utc>>,
+    /// Timestamp of the newest entry, expired entries included
+    pub newest_entry: Option<DateTime<Utc>>,
+    /// Mean access count per entry, or `0.0` for an empty cache
+    pub average_access_count: f64,
+    /// Number of keys removed by the eviction strategy since the cache was created
+    pub evictions: u64,
+    /// Number of lookups that found a value
+    pub hits: u64,
+    /// Number of lookups that found nothing
+    pub misses: u64,
+    /// Number of failed loads from storage that were ignored
+    pub load_errors: u64,
+    /// False while the circuit breaker is open and the backend is not
+    /// being called
+    pub backend_healthy: bool,
+    /// Number of stored values that share memory with an identical value
+    /// instead of holding their own copy
+    pub deduplicated_values: usize,
+    /// JSON size of the copies that sharing values avoids, as an estimate
+    /// of the memory saved
+    pub dedup_bytes_saved: u64,
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::backends::memory::MemoryBackend;
-    use crate::SearchQuery;
+impl CacheStats {
+    /// Fraction of lookups that were hits, or `0.0` if there were none
+    pub fn hit_rate(&self) -> f64 {
+        let lookups = self.hits + self.misses;
+        if lookups == 0 {
+            0.0
+        } else {
+            self.hits as f64 / lookups as f64
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backends::memory::MemoryBackend;
+    use crate::SearchQuery;
+
+    async fn create_cache() -> Cache<String, String> {
+        let config = CacheConfig::default();
+        let backend = MemoryBackend::new();
+        Cache::new(config, backend).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_cache_basic_operations() {
+        let cache = create_cache().await;
+
+        // Test put and get
+        cache
+            .put("key1".to_string(), "value1".to_string())
+            .await
+            .unwrap();
+        let value = cache.get(&"key1".to_string()).await.unwrap();
+        assert_eq!(value, Some("value1".to_string()));
+
+        // Test contains
+        assert!(cache.contains(&"key1".to_string()).await.unwrap());
+        assert!(!cache.contains(&"key2".to_string()).await.unwrap());
+
+        // Test len
+        assert_eq!(cache.len().await.unwrap(), 1);
+
+        // Test remove
+        let removed = cache.remove(&"key1".to_string()).await.unwrap();
+        assert_eq!(removed, Some("value1".to_string()));
+        assert_eq!(cache.len().await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_cache_clear() {
+        let cache = create_cache().await;
+
+        cache
+            .put("key1".to_string(), "value1".to_string())
+            .await
+            .unwrap();
+        cache
+            .put("key2".to_string(), "value2".to_string())
+            .await
+            .unwrap();
+
+        assert_eq!(cache.len().await.unwrap(), 2);
+
+        cache.clear().await.unwrap();
+        assert_eq!(cache.len().await.unwrap(), 0);
+        assert!(!cache.contains(&"key1".to_string()).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_entry_limits_and_eviction() {
+        let config = CacheConfig {
+            max_entries_per_key: 2,
+            max_total_entries: 3,
+            ..CacheConfig::default()
+        };
+        let backend: MemoryBackend<String, String> = MemoryBackend::new();
+        let cache: Cache<String, String> = Cache::new(config, backend).await.unwrap();
+
+        cache
+            .add_entry(CacheEntry::new("k1".to_string(), "v1".to_string()))
+            .await
+            .unwrap();
+        cache
+            .add_entry(CacheEntry::new("k1".to_string(), "v2".to_string()))
+            .await
+            .unwrap();
+        cache
+            .add_entry(CacheEntry::new("k1".to_string(), "v3".to_string()))
+            .await
+            .unwrap();
+
+        let k1_entries = cache.get_entries(&"k1".to_string()).await.unwrap();
+        assert_eq!(k1_entries.len(), 2);
+        assert_eq!(k1_entries[0].value, "v2");
+        assert_eq!(k1_entries[1].value, "v3");
+
+        cache
+            .add_entry(CacheEntry::new("k2".to_string(), "v".to_string()))
+            .await
+            .unwrap();
+        cache
+            .add_entry(CacheEntry::new("k3".to_string(), "v".to_string()))
+            .await
+            .unwrap();
+
+        assert!(cache.len().await.unwrap() <= 3);
+    }
+
+    #[tokio::test]
+    async fn test_max_total_bytes_evicts_until_under_budget() {
+        use crate::entry::BasicMetadata;
+
+        let config = CacheConfig::default()
+            .with_max_total_bytes(1000)
+            .with_eviction_policy(crate::EvictionPolicy::Fifo);
+        let cache: Cache<String, String, BasicMetadata> =
+            Cache::new(config, MemoryBackend::new()).await.unwrap();
+
+        let sized = |key: &str, size: u64| {
+            let metadata = BasicMetadata {
+                size_bytes: Some(size),
+                ..Default::default()
+            };
+            CacheEntry::with_metadata(key.to_string(), "v".to_string(), metadata)
+        };
+
+        for key in ["a", "b", "c"] {
+            cache.add_entry(sized(key, 300)).await.unwrap();
+            tokio::time::sleep(std::time::Duration::from_millis(2)).await;
+        }
+        assert_eq!(cache.len().await.unwrap(), 3);
+
+        // 900 + 700 bytes forces the two oldest keys out
+        cache.add_entry(sized("big", 700)).await.unwrap();
+        let mut keys = cache.keys().await;
+        keys.sort();
+        assert_eq!(keys, ["big", "c"]);
+        assert_eq!(cache.get_stats().await.evictions, 2);
+    }
+
+    #[cfg(feature = "json-serialization")]
+    #[tokio::test]
+    async fn test_measured_sizes_count_towards_byte_budget() {
+        use crate::MeasuredMetadata;
+
+        let config = CacheConfig::default()
+            .with_max_total_bytes(100)
+            .with_eviction_policy(crate::EvictionPolicy::Fifo);
+        let cache: Cache<String, String, MeasuredMetadata> =
+            Cache::new(config, MemoryBackend::new())
+                .await
+                .unwrap()
+                .with_measured_sizes();
+
+        // Each value serializes to 42 bytes: 40 characters plus quotes
+        for key in ["a", "b", "c"] {
+            cache.put(key.to_string(), "x".repeat(40)).await.unwrap();
+            tokio::time::sleep(std::time::Duration::from_millis(2)).await;
+        }
+        let entry = cache.get_latest(&"c".to_string()).await.unwrap();
+        assert_eq!(entry.metadata.size_bytes(), Some(42));
+
+        let mut keys = cache.keys().await;
+        keys.sort();
+        assert_eq!(keys, ["b", "c"]);
+
+        // An explicit size is left alone
+        let metadata = MeasuredMetadata {
+            size_bytes: Some(1),
+            ..Default::default()
+        };
+        cache
+            .add_entry(CacheEntry::with_metadata(
+                "d".to_string(),
+                "x".repeat(40),
+                metadata,
+            ))
+            .await
+            .unwrap();
+        let entry = cache.get_latest(&"d".to_string()).await.unwrap();
+        assert_eq!(entry.metadata.size_bytes(), Some(1));
+    }
+
+    #[tokio::test]
+    async fn test_arc_outperforms_lru_on_scans() {
+        async fn hot_hits(policy: crate::EvictionPolicy) -> u64 {
+            let config = CacheConfig::default()
+                .with_max_total_entries(10)
+                .with_eviction_policy(policy);
+            let cache: Cache<String, String> =
+                Cache::new(config, MemoryBackend::new()).await.unwrap();
+
+            // A small hot set interleaved with one-off scans larger than the
+            // space the hot set leaves free
+            for round in 0..20 {
+                let hot = (0..5).map(|i| format!("hot{i}"));
+                let scan = (0..8).map(|i| format!("scan{round}-{i}"));
+                for key in hot.chain(scan) {
+                    if cache.get(&key).await.unwrap().is_none() {
+                        cache.put(key, "v".to_string()).await.unwrap();
+                    }
+                }
+            }
+            cache.get_stats().await.hits
+        }
+
+        let arc = hot_hits(crate::EvictionPolicy::Arc).await;
+        let lru = hot_hits(crate::EvictionPolicy::Lru).await;
+        assert!(arc > lru, "ARC hits {arc} should beat LRU hits {lru}");
+    }
+
+    #[tokio::test]
+    async fn test_tiny_lfu_beats_lru_on_zipfian_keys() {
+        /// Keys 0..1000 drawn with probability proportional to 1 / rank
+        fn zipf_keys(count: usize) -> Vec<usize> {
+            let weights: Vec<f64> = (1..=1000).map(|rank| 1.0 / rank as f64).collect();
+            let total: f64 = weights.iter().sum();
+            let cumulative: Vec<f64> = weights
+                .iter()
+                .scan(0.0, |sum, weight| {
+                    *sum += weight / total;
+                    Some(*sum)
+                })
+                .collect();
+            let mut seed = 0x2545_f491_4f6c_dd1d_u64;
+            (0..count)
+                .map(|_| {
+                    seed ^= seed << 13;
+                    seed ^= seed >> 7;
+                    seed ^= seed << 17;
+                    let random = (seed >> 11) as f64 / (1u64 << 53) as f64;
+                    cumulative.partition_point(|&p| p < random).min(999)
+                })
+                .collect()
+        }
+
+        async fn hit_rate(policy: crate::EvictionPolicy, keys: &[usize]) -> f64 {
+            let config = CacheConfig::default()
+                .with_max_total_entries(50)
+                .with_eviction_policy(policy);
+            let cache: Cache<String, String> =
+                Cache::new(config, MemoryBackend::new()).await.unwrap();
+            for key in keys {
+                let key = key.to_string();
+                if cache.get(&key).await.unwrap().is_none() {
+                    cache.put(key, "v".to_string()).await.unwrap();
+                }
+            }
+            cache.get_stats().await.hits as f64 / keys.len() as f64
+        }
+
+        let keys = zipf_keys(20_000);
+        let tiny_lfu = hit_rate(crate::EvictionPolicy::TinyLfu, &keys).await;
+        let lru = hit_rate(crate::EvictionPolicy::Lru, &keys).await;
+        assert!(
+            tiny_lfu > lru + 0.05,
+            "TinyLFU hit rate {tiny_lfu} should clearly beat LRU hit rate {lru}"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_put_reporting_lists_evicted_keys() {
+        let config = CacheConfig::default()
+            .with_max_total_entries(2)
+            .with_eviction_policy(crate::EvictionPolicy::Fifo);
+        let cache: Cache<String, String> = Cache::new(config, MemoryBackend::new()).await.unwrap();
+
+        for key in ["a", "b"] {
+            let outcome = cache
+                .put_reporting(key.to_string(), "v".to_string())
+                .await
+                .unwrap();
+            assert_eq!(
+                outcome,
+                PutOutcome {
+                    evicted_keys: vec![],
+                    replaced_existing: false,
+                }
+            );
+            tokio::time::sleep(std::time::Duration::from_millis(2)).await;
+        }
+
+        let outcome = cache
+            .put_reporting("b".to_string(), "w".to_string())
+            .await
+            .unwrap();
+        assert!(outcome.replaced_existing);
+        assert!(outcome.evicted_keys.is_empty());
+
+        let outcome = cache
+            .put_reporting("c".to_string(), "v".to_string())
+            .await
+            .unwrap();
+        assert_eq!(outcome.evicted_keys, ["a"]);
+        assert!(!outcome.replaced_existing);
+    }
+
+    #[tokio::test]
+    async fn test_new_rejects_zero_limits() {
+        let config = CacheConfig::default().with_max_total_entries(0);
+        let result: Result<Cache<String, String>> = Cache::new(config, MemoryBackend::new()).await;
+        assert!(matches!(result, Err(CacheError::InvalidConfiguration(_))));
+
+        // The smallest valid limits keep the newest entry
+        let config = CacheConfig::default()
+            .with_max_total_entries(1)
+            .with_max_entries_per_key(1);
+        let cache: Cache<String, String> = Cache::new(config, MemoryBackend::new()).await.unwrap();
+        for value in ["a", "b"] {
+            cache
+                .add_entry(CacheEntry::new("k".to_string(), value.to_string()))
+                .await
+                .unwrap();
+        }
+        let entries = cache.get_entries(&"k".to_string()).await.unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].value, "b");
+    }
+
+    #[tokio::test]
+    async fn test_per_key_eviction_modes() {
+        async fn remaining(eviction: PerKeyEviction) -> Vec<String> {
+            let config = CacheConfig::default()
+                .with_max_entries_per_key(3)
+                .with_per_key_eviction(eviction);
+            let cache: Cache<String, String> =
+                Cache::new(config, MemoryBackend::new()).await.unwrap();
+            let key = "k".to_string();
+
+            for value in ["v1", "v2", "v3"] {
+                cache
+                    .add_entry(CacheEntry::new(key.clone(), value.to_string()))
+                    .await
+                    .unwrap();
+                tokio::time::sleep(std::time::Duration::from_millis(2)).await;
+            }
+            // Touch v1 and v3 so v2 is the least recently accessed entry
+            {
+                let entries = cache.entries.for_key(&key).read().await;
+                entries[&key][0].record_access();
+                entries[&key][2].record_access();
+            }
+            cache
+                .add_entry(CacheEntry::new(key.clone(), "v4".to_string()))
+                .await
+                .unwrap();
+
+            let entries = cache.get_entries(&key).await.unwrap();
+            entries.into_iter().map(|e| e.value).collect()
+        }
+
+        assert_eq!(remaining(PerKeyEviction::Oldest).await, ["v2", "v3", "v4"]);
+        assert_eq!(remaining(PerKeyEviction::Newest).await, ["v1", "v2", "v4"]);
+        assert_eq!(
+            remaining(PerKeyEviction::LeastAccessed).await,
+            ["v1", "v3", "v4"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cache_entries_search_stats() {
+        let cache = create_cache().await;
+
+        cache
+            .add_entry(CacheEntry::new("key".to_string(), "v1".to_string()))
+            .await
+            .unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        cache
+            .add_entry(CacheEntry::new("key".to_string(), "v2".to_string()))
+            .await
+            .unwrap();
+
+        let entries = cache.get_entries(&"key".to_string()).await.unwrap();
+        assert_eq!(entries.len(), 2);
+        let latest = cache.get_latest(&"key".to_string()).await.unwrap();
+        assert_eq!(latest.value, "v2");
+
+        let results = cache.search(&SearchQuery::new().with_pattern("key")).await;
+        assert_eq!(results.len(), 2);
+
+        // Add expired entry for stats
+        let expired = CacheEntry::new("expired".to_string(), "v".to_string())
+            .with_ttl(chrono::Duration::seconds(-1));
+        cache.add_entry(expired).await.unwrap();
+
+        let stats = cache.get_stats().await;
+        assert_eq!(stats.total_entries, 3);
+        assert_eq!(stats.expired_count, 1);
+        assert_eq!(stats.total_access_count, 3); // accesses from get_entries/get_latest
+    }
+
+    #[tokio::test]
+    async fn test_stats_report_entry_ages_and_average_access() {
+        let cache = create_cache().await;
+        cache
+            .add_entry(CacheEntry::new("key".to_string(), "v1".to_string()))
+            .await
+            .unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        cache
+            .add_entry(CacheEntry::new("key".to_string(), "v2".to_string()))
+            .await
+            .unwrap();
+        let entries = cache.get_entries(&"key".to_string()).await.unwrap();
+
+        let stats = cache.get_stats().await;
+        assert!((stats.average_access_count - 1.0).abs() < f64::EPSILON);
+        let oldest = stats.oldest_entry.unwrap();
+        assert!(oldest < stats.newest_entry.unwrap());
+        assert_eq!(oldest, entries[0].timestamp);
+    }
+
+    #[tokio::test]
+    async fn test_empty_cache_stats() {
+        let cache = create_cache().await;
+        let stats = cache.get_stats().await;
+        assert_eq!(stats.total_entries, 0);
+        assert_eq!(stats.total_keys, 0);
+        assert_eq!(stats.total_access_count, 0);
+        assert_eq!(stats.expired_count, 0);
+    }
+
+    #[tokio::test]
+    async fn test_empty_cache_reports_no_usage() {
+        let stats = create_cache().await.get_stats().await;
+        assert_eq!(stats.evictions, 0);
+        assert_eq!(stats.memory_usage_bytes, 0);
+        assert_eq!(stats.oldest_entry, None);
+        assert_eq!(stats.newest_entry, None);
+        assert_eq!(stats.average_access_count, 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_memory_usage_grows_with_payload() {
+        use crate::entry::BasicMetadata;
+
+        let cache: Cache<String, String, BasicMetadata, MemoryBackend<_, _, BasicMetadata>> =
+            Cache::new(CacheConfig::default(), MemoryBackend::new())
+                .await
+                .unwrap();
+
+        cache
+            .put("small".to_string(), "v".to_string())
+            .await
+            .unwrap();
+        let small = cache.get_stats().await.memory_usage_bytes;
+        assert!(small > 0);
+
+        let blob = "x".repeat(1 << 20);
+        let metadata = BasicMetadata {
+            size_bytes: Some(blob.len() as u64),
+            ..Default::default()
+        };
+        cache
+            .add_entry(CacheEntry::with_metadata(
+                "blob".to_string(),
+                blob,
+                metadata,
+            ))
+            .await
+            .unwrap();
+        let large = cache.get_stats().await.memory_usage_bytes;
+        assert!(large >= small + (1 << 20));
+    }
+
+    #[tokio::test]
+    async fn test_loader_fills_misses_once() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        /// Loader knowing only the key "known"
+        #[derive(Default)]
+        struct CountingLoader {
+            calls: AtomicUsize,
+        }
+
+        #[async_trait]
+        impl Loader<String, String> for CountingLoader {
+            async fn load(&self, key: &String) -> Result<Option<String>> {
+                self.calls.fetch_add(1, Ordering::SeqCst);
+                tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+                Ok((key == "known").then(|| "loaded".to_string()))
+            }
+        }
+
+        let loader = Arc::new(CountingLoader::default());
+        let cache = create_cache().await.with_loader(loader.clone());
+        let key = "known".to_string();
+
+        let (a, b) = tokio::join!(cache.get(&key), cache.get(&key));
+        assert_eq!(a.unwrap().as_deref(), Some("loaded"));
+        assert_eq!(b.unwrap().as_deref(), Some("loaded"));
+        assert_eq!(loader.calls.load(Ordering::SeqCst), 1);
+
+        // Loaded values are stored, so the next lookup is a plain hit
+        assert_eq!(cache.get(&key).await.unwrap().as_deref(), Some("loaded"));
+        assert_eq!(loader.calls.load(Ordering::SeqCst), 1);
+
+        // Values the loader lacks are not cached
+        let missing = "missing".to_string();
+        assert!(cache.get(&missing).await.unwrap().is_none());
+        assert!(cache.get(&missing).await.unwrap().is_none());
+        assert_eq!(loader.calls.load(Ordering::SeqCst), 3);
+        assert!(!cache.contains(&missing).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_get_or_insert_with_computes_once() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let cache = create_cache().await;
+        let calls = AtomicUsize::new(0);
+        let compute = || async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            "computed".to_string()
+        };
+
+        let (a, b) = tokio::join!(
+            cache.get_or_insert_with("key".to_string(), compute),
+            cache.get_or_insert_with("key".to_string(), compute)
+        );
+        assert_eq!(a.unwrap(), "computed");
+        assert_eq!(b.unwrap(), "computed");
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(cache.len().await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_get_or_insert_with_panic_leaves_no_bucket() {
+        let cache = create_cache().await;
+
+        let task_cache = cache.clone();
+        let result = tokio::spawn(async move {
+            task_cache
+                .get_or_insert_with("key".to_string(), || async { panic!("compute failed") })
+                .await
+        })
+        .await;
+        assert!(result.is_err());
+
+        assert!(!cache.contains(&"key".to_string()).await.unwrap());
+        let value = cache
+            .get_or_insert_with("key".to_string(), || async { "v".to_string() })
+            .await
+            .unwrap();
+        assert_eq!(value, "v");
+    }
+
+    #[tokio::test]
+    async fn test_get_or_insert_with_respects_max_total_entries() {
+        let config = CacheConfig::default()
+            .with_max_total_entries(2)
+            .with_eviction_policy(crate::EvictionPolicy::Fifo);
+        let cache: Cache<String, String> = Cache::new(config, MemoryBackend::new()).await.unwrap();
+        for key in ["a", "b", "c", "d"] {
+            cache
+                .get_or_insert_with(key.to_string(), || async { "v".to_string() })
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(cache.len().await.unwrap(), 2);
+        assert!(cache.contains(&"d".to_string()).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_put_many_get_many() {
+        let cache = create_cache().await;
+
+        cache
+            .put_many(vec![
+                ("a".to_string(), "1".to_string()),
+                ("b".to_string(), "2".to_string()),
+                ("a".to_string(), "3".to_string()),
+            ])
+            .await
+            .unwrap();
+
+        // Later items for the same key replace earlier ones, like `put`
+        assert_eq!(cache.len().await.unwrap(), 2);
+
+        let values = cache
+            .get_many(&["a".to_string(), "missing".to_string(), "b".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(
+            values,
+            vec![Some("3".to_string()), None, Some("2".to_string())]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_contains_many_matches_contains() {
+        let config = CacheConfig::default().with_expire_on_access(true);
+        let cache: Cache<String, String> = Cache::new(config, MemoryBackend::new()).await.unwrap();
+        cache.put("a".to_string(), "1".to_string()).await.unwrap();
+        cache
+            .add_entry(
+                CacheEntry::new("expired".to_string(), "x".to_string())
+                    .with_ttl(chrono::Duration::seconds(-1)),
+            )
+            .await
+            .unwrap();
+
+        let keys = ["missing", "a", "expired", "a"].map(String::from);
+        assert_eq!(cache.contains_many(&keys).await, [false, true, false, true]);
+        for (key, expected) in keys.iter().zip(cache.contains_many(&keys).await) {
+            assert_eq!(cache.contains(key).await.unwrap(), expected);
+        }
+        assert!(cache.contains_many(&[]).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_put_many_evicts_and_syncs_once() {
+        use crate::test_utils::TestBackend;
+
+        let mut config = CacheConfig::default().with_max_total_entries(5);
+        config.persistence.enabled = true;
+        config.persistence.load_on_startup = false;
+        config.persistence.sync_interval = 2;
+
+        let backend = TestBackend::default();
+        let cache: Cache<String, String, (), TestBackend> =
+            Cache::new(config, backend.clone()).await.unwrap();
+
+        let items = (0..10)
+            .map(|i| (format!("k{i}"), format!("v{i}")))
+            .collect();
+        cache.put_many(items).await.unwrap();
+        assert_eq!(cache.len().await.unwrap(), 5);
+
+        // A single batch counts as one operation, so no sync is triggered yet
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        assert_eq!(*backend.save_calls.read().await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_put_with_ttl() {
+        let cache = create_cache().await;
+
+        cache
+            .add_entry(CacheEntry::new("key".to_string(), "old".to_string()))
+            .await
+            .unwrap();
+        cache
+            .put_with_ttl(
+                "key".to_string(),
+                "new".to_string(),
+                chrono::Duration::seconds(60),
+            )
+            .await
+            .unwrap();
+
+        let entries = cache.get_entries(&"key".to_string()).await.unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].value, "new");
+        assert!(entries[0].expiry.is_some());
+        assert!(!entries[0].is_expired());
+
+        cache
+            .put_with_ttl(
+                "expired".to_string(),
+                "v".to_string(),
+                chrono::Duration::seconds(-1),
+            )
+            .await
+            .unwrap();
+        let latest = cache.get_latest(&"expired".to_string()).await.unwrap();
+        assert!(latest.is_expired());
+    }
+
+    #[tokio::test]
+    async fn test_default_ttl() {
+        let config = CacheConfig::default().with_default_ttl(std::time::Duration::from_secs(60));
+        let cache: Cache<String, String> = Cache::new(config, MemoryBackend::new()).await.unwrap();
+
+        cache.put("put".to_string(), "v".to_string()).await.unwrap();
+        cache
+            .add_entry(CacheEntry::new("added".to_string(), "v".to_string()))
+            .await
+            .unwrap();
+        for key in ["put", "added"] {
+            let entry = cache.get_latest(&key.to_string()).await.unwrap();
+            assert_eq!(
+                entry.expiry,
+                Some(entry.timestamp + chrono::Duration::seconds(60))
+            );
+        }
+
+        // An explicit TTL is kept rather than replaced by the default
+        let explicit = CacheEntry::new("explicit".to_string(), "v".to_string())
+            .with_ttl(chrono::Duration::seconds(5));
+        let expected = explicit.expiry;
+        cache.add_entry(explicit).await.unwrap();
+        cache
+            .put_with_ttl(
+                "put_ttl".to_string(),
+                "v".to_string(),
+                chrono::Duration::seconds(5),
+            )
+            .await
+            .unwrap();
+
+        let entry = cache.get_latest(&"explicit".to_string()).await.unwrap();
+        assert_eq!(entry.expiry, expected);
+        let entry = cache.get_latest(&"put_ttl".to_string()).await.unwrap();
+        assert_eq!(
+            entry.expiry,
+            Some(entry.timestamp + chrono::Duration::seconds(5))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sorted_buckets_place_skewed_entries_by_timestamp() {
+        let clock = Arc::new(crate::ManualClock::default());
+        let start = clock.now();
+        let config = CacheConfig::default()
+            .with_max_entries_per_key(3)
+            .with_sorted_buckets(true);
+        let cache: Cache<String, String> = Cache::new(config, MemoryBackend::new())
+            .await
+            .unwrap()
+            .with_clock(clock.clone());
+        let key = "versions".to_string();
+
+        // The clock jumps back after the first insert
+        for (offset, value) in [(10, "ten"), (5, "five"), (7, "seven")] {
+            clock.set(start + chrono::Duration::seconds(offset));
+            cache
+                .add_entry(cache.new_entry(key.clone(), value.to_string()))
+                .await
+                .unwrap();
+        }
+        let values = |entries: Vec<CacheEntry<String, String>>| {
+            entries.into_iter().map(|e| e.value).collect::<Vec<_>>()
+        };
+        assert_eq!(
+            values(cache.get_entries(&key).await.unwrap()),
+            ["five", "seven", "ten"]
+        );
+        assert_eq!(cache.get_latest(&key).await.unwrap().value, "ten");
+
+        // The oldest entry makes room, wherever the new one lands
+        clock.set(start + chrono::Duration::seconds(6));
+        cache
+            .add_entry(cache.new_entry(key.clone(), "six".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(
+            values(cache.get_entries(&key).await.unwrap()),
+            ["six", "seven", "ten"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_from_entries_and_extend() {
+        let cache: Cache<String, String> = Cache::from_entries(
+            CacheConfig::default(),
+            MemoryBackend::new(),
+            (0..3).map(|i| (format!("k{i}"), i.to_string())),
+        )
+        .await
+        .unwrap();
+        assert_eq!(cache.len().await.unwrap(), 3);
+        assert_eq!(
+            cache.get(&"k2".to_string()).await.unwrap().as_deref(),
+            Some("2")
+        );
+
+        let more = HashMap::from([
+            ("k2".to_string(), "two".to_string()),
+            ("k3".to_string(), "3".to_string()),
+        ]);
+        cache.extend(more).await.unwrap();
+        assert_eq!(cache.len().await.unwrap(), 4);
+        assert_eq!(
+            cache.get(&"k2".to_string()).await.unwrap().as_deref(),
+            Some("two")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_read_only_cache_refuses_writes_and_leaves_entries_alone() {
+        let clock = Arc::new(crate::ManualClock::default());
+        let config = persistent_config()
+            .with_read_only(true)
+            .with_expire_on_access(true)
+            .with_default_ttl(std::time::Duration::from_secs(60));
+        let backend = MemoryBackend::new();
+        let cache: Cache<String, String> = Cache::from_entries(
+            config,
+            backend.clone(),
+            [("a".to_string(), "1".to_string())],
+        )
+        .await
+        .unwrap()
+        .with_clock(clock.clone());
+        let key = "a".to_string();
+
+        assert!(matches!(
+            cache.put("b".to_string(), "2".to_string()).await,
+            Err(CacheError::ReadOnly)
+        ));
+        assert!(matches!(
+            cache
+                .add_entry(CacheEntry::new(key.clone(), "x".to_string()))
+                .await,
+            Err(CacheError::ReadOnly)
+        ));
+        assert!(matches!(
+            cache.remove(&key).await,
+            Err(CacheError::ReadOnly)
+        ));
+        assert!(matches!(cache.clear().await, Err(CacheError::ReadOnly)));
+        assert!(matches!(cache.compact().await, Err(CacheError::ReadOnly)));
+
+        assert_eq!(cache.get(&key).await.unwrap().as_deref(), Some("1"));
+        let entry = cache.peek_entry(&key).await.unwrap();
+        assert_eq!(entry.access_count(), 0);
+
+        // Expired entries are hidden from reads but not dropped
+        clock.advance(chrono::Duration::seconds(61));
+        assert_eq!(cache.get(&key).await.unwrap(), None);
+        assert_eq!(cache.len().await.unwrap(), 1);
+
+        cache.flush().await.unwrap();
+        assert!(backend.load().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_get_arc_shares_the_stored_value() {
+        let backend = MemoryBackend::new();
+        let cache: ArcCache<String, String> = Cache::new(persistent_config(), backend.clone())
+            .await
+            .unwrap();
+        let blob = Arc::new("x".repeat(1 << 20));
+        cache
+            .put("blob".to_string(), Arc::clone(&blob))
+            .await
+            .unwrap();
+
+        let first = cache.get_arc(&"blob".to_string()).await.unwrap();
+        let second = cache.get_arc(&"blob".to_string()).await.unwrap();
+        assert!(Arc::ptr_eq(&first, &blob));
+        assert!(Arc::ptr_eq(&first, &second));
+        assert!(cache.get_arc(&"missing".to_string()).await.is_none());
+        assert_eq!(cache.get_stats().await.hits, 2);
+
+        // Persisted as the plain value
+        cache.flush().await.unwrap();
+        let mut config = persistent_config();
+        config.persistence.load_on_startup = true;
+        let reloaded: ArcCache<String, String> = Cache::new(config, backend).await.unwrap();
+        let loaded = reloaded.get_arc(&"blob".to_string()).await.unwrap();
+        assert_eq!(loaded, blob);
+    }
+
+    #[cfg(feature = "json-serialization")]
+    #[tokio::test]
+    async fn test_value_dedup_shares_identical_values() {
+        let cache: ArcCache<String, String> =
+            Cache::new(CacheConfig::default(), MemoryBackend::new())
+                .await
+                .unwrap()
+                .with_value_dedup();
+        let report = "r".repeat(1000);
+        for key in ["a", "b", "c"] {
+            cache
+                .put(key.to_string(), Arc::new(report.clone()))
+                .await
+                .unwrap();
+        }
+        cache
+            .put("d".to_string(), Arc::new("other".to_string()))
+            .await
+            .unwrap();
+
+        let a = cache.get_arc(&"a".to_string()).await.unwrap();
+        let c = cache.get_arc(&"c".to_string()).await.unwrap();
+        assert!(Arc::ptr_eq(&a, &c));
+        let stats = cache.get_stats().await;
+        assert_eq!(stats.deduplicated_values, 2);
+        assert_eq!(stats.dedup_bytes_saved, 2 * 1002);
+
+        // Removing a key releases its reference
+        cache.remove(&"b".to_string()).await.unwrap();
+        assert_eq!(cache.get_stats().await.deduplicated_values, 1);
+
+        // Without dedup, equal values keep their own copies
+        let plain: ArcCache<String, String> =
+            Cache::new(CacheConfig::default(), MemoryBackend::new())
+                .await
+                .unwrap();
+        plain
+            .put("a".to_string(), Arc::new(report.clone()))
+            .await
+            .unwrap();
+        plain.put("b".to_string(), Arc::new(report)).await.unwrap();
+        assert_eq!(plain.get_stats().await.deduplicated_values, 0);
+    }
+
+    #[tokio::test]
+    async fn test_ttl_jitter_spreads_expiries_reproducibly() {
+        let config = CacheConfig::default()
+            .with_default_ttl(std::time::Duration::from_secs(60))
+            .with_ttl_jitter(std::time::Duration::from_secs(10));
+        let mut runs = Vec::new();
+        for _ in 0..2 {
+            let cache: Cache<String, String> = Cache::new(config.clone(), MemoryBackend::new())
+                .await
+                .unwrap()
+                .with_ttl_jitter_seed(42);
+            let mut ttls = Vec::new();
+            for i in 0..50 {
+                let key = format!("key-{i}");
+                cache.put(key.clone(), "v".to_string()).await.unwrap();
+                let entry = cache.get_latest(&key).await.unwrap();
+                let ttl = entry.expiry.unwrap() - entry.timestamp;
+                assert!(ttl >= chrono::Duration::seconds(50));
+                assert!(ttl <= chrono::Duration::seconds(70));
+                ttls.push(ttl);
+            }
+            runs.push(ttls);
+        }
+        assert_eq!(runs[0], runs[1]);
+        assert!(runs[0]
+            .iter()
+            .any(|ttl| *ttl < chrono::Duration::seconds(59)));
+        assert!(runs[0]
+            .iter()
+            .any(|ttl| *ttl > chrono::Duration::seconds(61)));
+
+        // Without a jitter nothing is drawn from the sequence
+        let cache: Cache<String, String> = Cache::new(
+            CacheConfig::default().with_default_ttl(std::time::Duration::from_secs(60)),
+            MemoryBackend::new(),
+        )
+        .await
+        .unwrap()
+        .with_ttl_jitter_seed(42);
+        cache.put("k".to_string(), "v".to_string()).await.unwrap();
+        let state = cache.ttl_jitter_seed.as_ref().unwrap();
+        assert_eq!(state.load(Ordering::Relaxed), 42);
+    }
+
+    #[tokio::test]
+    async fn test_reads_record_access_under_read_lock() {
+        let cache = create_cache().await;
+        cache.put("key".to_string(), "v".to_string()).await.unwrap();
+
+        // A concurrent reader must not block lookups
+        let guard = cache.entries.for_key(&"key".to_string()).read().await;
+        let latest = tokio::time::timeout(
+            std::time::Duration::from_secs(1),
+            cache.get_latest(&"key".to_string()),
+        )
+        .await
+        .expect("get_latest waited for the write lock")
+        .unwrap();
+        assert_eq!(latest.access_count(), 1);
+        assert_eq!(guard["key"][0].access_count(), 1);
+        drop(guard);
+
+        cache.get_entries(&"key".to_string()).await.unwrap();
+        let latest = cache.get_latest(&"key".to_string()).await.unwrap();
+        assert_eq!(latest.access_count(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_replace_if() {
+        let cache = create_cache().await;
+        let key = "key".to_string();
+
+        assert!(!cache
+            .replace_if(&key, &"v1".to_string(), "v2".to_string())
+            .await
+            .unwrap());
+        assert!(!cache.contains(&key).await.unwrap());
+
+        cache.put(key.clone(), "v1".to_string()).await.unwrap();
+        assert!(!cache
+            .replace_if(&key, &"other".to_string(), "v2".to_string())
+            .await
+            .unwrap());
+        assert_eq!(cache.get(&key).await.unwrap(), Some("v1".to_string()));
+
+        assert!(cache
+            .replace_if(&key, &"v1".to_string(), "v2".to_string())
+            .await
+            .unwrap());
+        assert_eq!(cache.get(&key).await.unwrap(), Some("v2".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_replace_if_concurrent_single_winner() {
+        let cache = create_cache().await;
+        let key = "key".to_string();
+        cache.put(key.clone(), "start".to_string()).await.unwrap();
+
+        let tasks: Vec<_> = (0..16)
+            .map(|i| {
+                let cache = cache.clone();
+                let key = key.clone();
+                tokio::spawn(async move {
+                    cache
+                        .replace_if(&key, &"start".to_string(), format!("w{i}"))
+                        .await
+                        .unwrap()
+                })
+            })
+            .collect();
+
+        let mut winners = 0;
+        for task in tasks {
+            winners += usize::from(task.await.unwrap());
+        }
+        assert_eq!(winners, 1);
+    }
+
+    #[tokio::test]
+    async fn test_update_metadata_keeps_access_stats() {
+        use crate::entry::BasicMetadata;
+
+        let cache: Cache<String, String, BasicMetadata> =
+            Cache::with_config(CacheConfig::default()).await.unwrap();
+        let key = "key".to_string();
+        assert!(!cache
+            .update_metadata(&key, |metadata| metadata.tags.push("x".to_string()))
+            .await
+            .unwrap());
+
+        cache.put(key.clone(), "value".to_string()).await.unwrap();
+        cache.get(&key).await.unwrap();
+        assert!(cache
+            .update_metadata(&key, |metadata| metadata.tags.push("hot".to_string()))
+            .await
+            .unwrap());
+
+        let latest = cache.get_latest(&key).await.unwrap();
+        assert_eq!(latest.metadata.tags, ["hot"]);
+        assert_eq!(latest.value, "value");
+        assert_eq!(latest.access_count(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_touch_refreshes_access_and_ttl() {
+        let cache = create_cache().await;
+        let key = "key".to_string();
+        assert!(!cache.touch(&key, None).await.unwrap());
+
+        cache
+            .put_with_ttl(
+                key.clone(),
+                "value".to_string(),
+                chrono::Duration::seconds(1),
+            )
+            .await
+            .unwrap();
+        let before = cache.get_entries(&key).await.unwrap().remove(0);
+
+        assert!(cache
+            .touch(&key, Some(chrono::Duration::hours(1)))
+            .await
+            .unwrap());
+        let entries = cache.get_entries(&key).await.unwrap();
+        assert_eq!(entries[0].access_count(), 3);
+        assert!(entries[0].last_accessed() > before.last_accessed());
+        assert!(entries[0].expiry.unwrap() > chrono::Utc::now() + chrono::Duration::minutes(59));
+        // Touching does not count as a lookup
+        assert_eq!(cache.get_stats().await.hits, 0);
+    }
+
+    #[tokio::test]
+    async fn test_remove_where() {
+        use crate::test_utils::TestBackend;
+
+        let backend = TestBackend::default();
+        let cache: Cache<String, String, (), TestBackend> =
+            Cache::new(persistent_config(), backend.clone())
+                .await
+                .unwrap();
+        for (key, value) in [
+            ("a", "stale"),
+            ("a", "fresh"),
+            ("b", "stale"),
+            ("c", "fresh"),
+        ] {
+            cache
+                .add_entry(CacheEntry::new(key.to_string(), value.to_string()))
+                .await
+                .unwrap();
+        }
+        cache.sync().await.unwrap();
+        backend
+            .entries
+            .write()
+            .await
+            .insert("b".to_string(), Vec::new());
+
+        let removed = cache
+            .remove_where(|_, entry| entry.value == "stale")
+            .await
+            .unwrap();
+        assert_eq!(removed, 2);
+        assert!(!cache.contains(&"b".to_string()).await.unwrap());
+        assert!(!backend.entries.read().await.contains_key("b"));
+        assert_eq!(cache.get_entries(&"a".to_string()).await.unwrap().len(), 1);
+        assert_eq!(cache.len().await.unwrap(), 2);
+
+        assert_eq!(cache.remove_where(|_, _| false).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_remove_where_releases_shards_before_backend_removes() {
+        use crate::test_utils::TestBackend;
+
+        let backend = TestBackend::default();
+        let cache: Cache<String, String, (), TestBackend> =
+            Cache::new(persistent_config(), backend.clone())
+                .await
+                .unwrap();
+        for key in ["a", "b"] {
+            cache.put(key.to_string(), "v".to_string()).await.unwrap();
+        }
+
+        let gate = backend.remove_gate.lock().await;
+        let task_cache = cache.clone();
+        let removing =
+            tokio::spawn(
+                async move { task_cache.remove_where(|key, _| key.as_str() == "a").await },
+            );
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+
+        // The stalled backend remove does not block other callers
+        let key = "b".to_string();
+        let read = tokio::time::timeout(std::time::Duration::from_secs(1), cache.get(&key));
+        assert_eq!(read.await.unwrap().unwrap().as_deref(), Some("v"));
+        assert!(!removing.is_finished());
+
+        drop(gate);
+        assert_eq!(removing.await.unwrap().unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_remove_where_keeps_keys_put_back_during_backend_removes() {
+        use crate::test_utils::TestBackend;
+
+        let backend = TestBackend::default();
+        let cache: Cache<String, String, (), TestBackend> =
+            Cache::new(persistent_config(), backend.clone())
+                .await
+                .unwrap();
+        cache.put("a".to_string(), "old".to_string()).await.unwrap();
+
+        let gate = backend.remove_gate.lock().await;
+        let task_cache = cache.clone();
+        let removing = tokio::spawn(async move { task_cache.remove_where(|_, _| true).await });
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+
+        // Put back and saved before the stalled backend remove goes through
+        cache.put("a".to_string(), "new".to_string()).await.unwrap();
+        cache.save_dirty().await.unwrap();
+        drop(gate);
+        assert_eq!(removing.await.unwrap().unwrap(), 1);
+        assert!(!backend.entries.read().await.contains_key("a"));
+
+        cache.save_dirty().await.unwrap();
+        assert_eq!(backend.entries.read().await["a"][0].value, "new");
+    }
+
+    #[tokio::test]
+    async fn test_remove_and_clear_keep_keys_put_back_during_backend_calls() {
+        use crate::test_utils::TestBackend;
+
+        let backend = TestBackend::default();
+        let cache: Cache<String, String, (), TestBackend> =
+            Cache::new(persistent_config(), backend.clone())
+                .await
+                .unwrap();
+        let key = "a".to_string();
+        let put_back = |value: &'static str| {
+            let cache = cache.clone();
+            async move {
+                let put = cache.put("a".to_string(), value.to_string());
+                tokio::time::timeout(std::time::Duration::from_secs(1), put)
+                    .await
+                    .expect("the shard stayed locked during the backend call")
+                    .unwrap();
+                cache.save_dirty().await.unwrap();
+            }
+        };
+
+        for clear in [false, true] {
+            cache.put(key.clone(), "old".to_string()).await.unwrap();
+            cache.save_dirty().await.unwrap();
+
+            let gate = backend.remove_gate.lock().await;
+            let task_cache = cache.clone();
+            let task_key = key.clone();
+            let removing = tokio::spawn(async move {
+                if clear {
+                    task_cache.clear().await
+                } else {
+                    task_cache.remove(&task_key).await.map(|_| ())
+                }
+            });
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+
+            // Put back and saved before the stalled backend call goes through
+            put_back("new").await;
+            drop(gate);
+            removing.await.unwrap().unwrap();
+            assert!(!backend.entries.read().await.contains_key("a"));
+
+            cache.save_dirty().await.unwrap();
+            assert_eq!(backend.entries.read().await["a"][0].value, "new");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_purge_older_than() {
+        let cache = create_cache().await;
+        let now = chrono::Utc::now();
+        for (key, days) in [("a", 40), ("a", 1), ("b", 31), ("c", 0)] {
+            let mut entry = CacheEntry::new(key.to_string(), format!("{days}d"));
+            entry.timestamp = now - chrono::Duration::days(days);
+            cache.add_entry(entry).await.unwrap();
+        }
+
+        let removed = cache
+            .purge_older_than(chrono::Duration::days(30))
+            .await
+            .unwrap();
+        assert_eq!(removed, 2);
+        assert_eq!(cache.version_count(&"a".to_string()).await, 1);
+        assert!(!cache.contains(&"b".to_string()).await.unwrap());
+        assert!(cache.contains(&"c".to_string()).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_search_pagination() {
+        let cache = create_cache().await;
+        for i in 0..5 {
+            cache.put(format!("k{i}"), format!("v{i}")).await.unwrap();
+            tokio::time::sleep(std::time::Duration::from_millis(2)).await;
+        }
+
+        let values = |entries: Vec<CacheEntry<String, String>>| {
+            entries.into_iter().map(|e| e.value).collect::<Vec<_>>()
+        };
+
+        let all = cache.search(&SearchQuery::new()).await;
+        assert_eq!(values(all), ["v4", "v3", "v2", "v1", "v0"]);
+
+        let page = cache
+            .search(&SearchQuery::new().with_offset(1).with_limit(2))
+            .await;
+        assert_eq!(values(page), ["v3", "v2"]);
+
+        let past_end = cache.search(&SearchQuery::new().with_offset(10)).await;
+        assert!(past_end.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_search_for_each_stops_early() {
+        let cache = create_cache().await;
+        for i in 0..10 {
+            cache.put(format!("k{i}"), format!("v{i}")).await.unwrap();
+        }
+        cache
+            .put("other".to_string(), "x".to_string())
+            .await
+            .unwrap();
+        let query = SearchQuery::new().with_pattern("k");
+
+        let mut seen = Vec::new();
+        let visited = cache
+            .search_for_each(&query, |entry| {
+                seen.push(entry.value.clone());
+                ControlFlow::Continue(())
+            })
+            .await;
+        assert_eq!(visited, 10);
+        assert_eq!(seen.len(), 10);
+        assert!(seen.iter().all(|value| value.starts_with('v')));
+
+        let visited = cache
+            .search_for_each(&query, |_| ControlFlow::Break(()))
+            .await;
+        assert_eq!(visited, 1);
+    }
+
+    #[tokio::test]
+    async fn test_watch_follows_one_key() {
+        let cache = create_cache().await;
+        cache.put("k".to_string(), "v1".to_string()).await.unwrap();
+
+        let mut watcher = cache.watch(&"k".to_string()).await;
+        assert_eq!(watcher.borrow_and_update().as_deref(), Some("v1"));
+        let mut absent = cache.watch(&"other".to_string()).await;
+        assert_eq!(*absent.borrow_and_update(), None);
+
+        cache.put("k".to_string(), "v2".to_string()).await.unwrap();
+        assert!(watcher.has_changed().unwrap());
+        assert_eq!(watcher.borrow_and_update().as_deref(), Some("v2"));
+
+        cache
+            .add_entry(CacheEntry::new("k".to_string(), "v3".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(watcher.borrow_and_update().as_deref(), Some("v3"));
+        assert!(!absent.has_changed().unwrap());
+
+        cache.remove(&"k".to_string()).await.unwrap();
+        assert_eq!(*watcher.borrow_and_update(), None);
+
+        // Senders go once their receivers do
+        drop(watcher);
+        drop(absent);
+        cache.put("k".to_string(), "v4".to_string()).await.unwrap();
+        let watcher = cache.watch(&"k".to_string()).await;
+        assert_eq!(watcher.borrow().as_deref(), Some("v4"));
+        assert_eq!(cache.watchers.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_keys_and_iter() {
+        let cache = create_cache().await;
+        cache
+            .add_entry(CacheEntry::new("a".to_string(), "old".to_string()))
+            .await
+            .unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(2)).await;
+        cache
+            .add_entry(CacheEntry::new("a".to_string(), "new".to_string()))
+            .await
+            .unwrap();
+        cache.put("b".to_string(), "v".to_string()).await.unwrap();
+
+        let mut keys = cache.keys().await;
+        keys.sort();
+        assert_eq!(keys, vec!["a".to_string(), "b".to_string()]);
+
+        let mut items = cache.iter().await;
+        items.sort_by(|x, y| x.0.cmp(&y.0));
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].1.value, "new");
+        assert_eq!(items[1].1.value, "v");
+
+        // Enumeration is not an access
+        assert_eq!(cache.get_stats().await.total_access_count, 0);
+    }
+
+    #[tokio::test]
+    async fn test_reverse_lookup_by_value() {
+        let cache = create_cache().await;
+        cache.put("a".to_string(), "dup".to_string()).await.unwrap();
+        cache.put("b".to_string(), "dup".to_string()).await.unwrap();
+        cache.put("c".to_string(), "dup".to_string()).await.unwrap();
+        // Only the latest value of a key counts
+        cache
+            .add_entry(CacheEntry::new("c".to_string(), "other".to_string()))
+            .await
+            .unwrap();
+
+        assert!(cache.contains_value(&"dup".to_string()).await);
+        assert!(!cache.contains_value(&"missing".to_string()).await);
+        let mut keys = cache.find_keys_by_value(&"dup".to_string()).await;
+        keys.sort();
+        assert_eq!(keys, ["a", "b"]);
+    }
+
+    #[tokio::test]
+    async fn test_replace_returns_previous_value() {
+        let cache = create_cache().await;
+        let key = "key".to_string();
+        assert!(cache
+            .replace(key.clone(), "v1".to_string())
+            .await
+            .unwrap()
+            .is_none());
+        cache
+            .add_entry(CacheEntry::new(key.clone(), "v2".to_string()))
+            .await
+            .unwrap();
+
+        let previous = cache.replace(key.clone(), "v3".to_string()).await.unwrap();
+        assert_eq!(previous.as_deref(), Some("v2"));
+        assert_eq!(cache.version_count(&key).await, 1);
+        assert_eq!(cache.peek(&key).await.unwrap().as_deref(), Some("v3"));
+    }
+
+    #[tokio::test]
+    async fn test_peek_leaves_access_stats_untouched() {
+        let cache = create_cache().await;
+        cache.put("a".to_string(), "v".to_string()).await.unwrap();
+
+        assert_eq!(
+            cache.peek(&"a".to_string()).await.unwrap().as_deref(),
+            Some("v")
+        );
+        let entry = cache.peek_entry(&"a".to_string()).await.unwrap();
+        assert_eq!(entry.access_count(), 0);
+        assert!(cache.peek(&"missing".to_string()).await.unwrap().is_none());
+
+        let stats = cache.get_stats().await;
+        assert_eq!(stats.total_access_count, 0);
+        assert_eq!((stats.hits, stats.misses), (0, 0));
+    }
+
+    #[tokio::test]
+    async fn test_get_or_default_does_not_insert() {
+        let cache = create_cache().await;
+        cache.put("a".to_string(), "v".to_string()).await.unwrap();
+
+        assert_eq!(cache.get_or_default(&"a".to_string()).await.unwrap(), "v");
+        assert_eq!(cache.get_or_default(&"b".to_string()).await.unwrap(), "");
+        assert!(!cache.contains(&"b".to_string()).await.unwrap());
+        assert_eq!(cache.get_stats().await.misses, 1);
+    }
+
+    #[tokio::test]
+    async fn test_version_count() {
+        let config = CacheConfig::default().with_expire_on_access(true);
+        let cache: Cache<String, String> = Cache::new(config, MemoryBackend::new()).await.unwrap();
+        let key = "key".to_string();
+        assert_eq!(cache.version_count(&key).await, 0);
+
+        for value in ["v1", "v2"] {
+            cache
+                .add_entry(CacheEntry::new(key.clone(), value.to_string()))
+                .await
+                .unwrap();
+        }
+        cache
+            .add_entry(
+                CacheEntry::new(key.clone(), "old".to_string())
+                    .with_ttl(chrono::Duration::seconds(-1)),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(cache.version_count(&key).await, 2);
+        assert_eq!(cache.get_stats().await.total_access_count, 0);
+    }
+
+    #[tokio::test]
+    async fn test_expire_on_access() {
+        let config = CacheConfig::default().with_expire_on_access(true);
+        let cache: Cache<String, String> = Cache::new(config, MemoryBackend::new()).await.unwrap();
+
+        let expired = CacheEntry::new("key".to_string(), "old".to_string())
+            .with_ttl(chrono::Duration::seconds(-1));
+        cache.add_entry(expired).await.unwrap();
+        cache
+            .add_entry(CacheEntry::new("key".to_string(), "live".to_string()))
+            .await
+            .unwrap();
+
+        // Expired versions are skipped and dropped from the bucket
+        let entries = cache.get_entries(&"key".to_string()).await.unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].value, "live");
+
+        cache
+            .put_with_ttl(
+                "gone".to_string(),
+                "v".to_string(),
+                chrono::Duration::seconds(-1),
+            )
+            .await
+            .unwrap();
+        assert!(!cache.contains(&"gone".to_string()).await.unwrap());
+        assert_eq!(cache.get(&"gone".to_string()).await.unwrap(), None);
+
+        // A fully expired bucket is removed on access
+        assert_eq!(cache.len().await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_manual_clock_expires_without_sleeping() {
+        let clock = Arc::new(crate::ManualClock::default());
+        let config = CacheConfig::default()
+            .with_expire_on_access(true)
+            .with_max_total_entries(2)
+            .with_eviction_policy(crate::EvictionPolicy::Ttl);
+        let cache: Cache<String, String> = Cache::new(config, MemoryBackend::new())
+            .await
+            .unwrap()
+            .with_clock(clock.clone());
+
+        let ttl = chrono::Duration::minutes(10);
+        cache.put("old".to_string(), "v".to_string()).await.unwrap();
+        cache
+            .put_with_ttl("a".to_string(), "v".to_string(), ttl)
+            .await
+            .unwrap();
+        let entry = cache.get_latest(&"a".to_string()).await.unwrap();
+        assert_eq!(entry.timestamp, clock.now());
+        assert_eq!(entry.expiry, Some(clock.now() + ttl));
+
+        clock.advance(ttl + chrono::Duration::seconds(1));
+        assert_eq!(cache.get_stats().await.expired_count, 1);
+
+        // The TTL strategy sees the expiry too, so "a" goes before the
+        // older "old"
+        cache.put("c".to_string(), "v".to_string()).await.unwrap();
+        let mut keys = cache.keys().await;
+        keys.sort();
+        assert_eq!(keys, ["c", "old"]);
+        assert_eq!(cache.get(&"a".to_string()).await.unwrap(), None);
+    }
+
+    #[cfg(feature = "tracing")]
+    #[tokio::test]
+    async fn test_tracing_spans_record_outcomes() {
+        use crate::test_utils::RecordingSubscriber;
+
+        let subscriber = Arc::new(RecordingSubscriber::default());
+        let _guard = tracing::subscriber::set_default(Arc::clone(&subscriber));
+
+        let config = CacheConfig::default()
+            .with_max_total_entries(1)
+            .with_eviction_policy(crate::EvictionPolicy::Fifo);
+        let cache: Cache<String, String> = Cache::new(config, MemoryBackend::new())
+            .await
+            .unwrap()
+            .with_key_tracing();
+        cache.put("a".to_string(), "1".to_string()).await.unwrap();
+        cache.put("b".to_string(), "2".to_string()).await.unwrap();
+        cache.get(&"b".to_string()).await.unwrap();
+        cache.get(&"a".to_string()).await.unwrap();
+
+        let puts = subscriber.spans_named("cache.put");
+        assert_eq!(puts.len(), 2);
+        assert_eq!(puts[1].fields["key"], "b");
+        assert_eq!(puts[1].fields["keys"], "1");
+
+        let gets = subscriber.spans_named("cache.get");
+        assert_eq!(gets[0].fields["hit"], "true");
+        assert_eq!(gets[1].fields["key"], "a");
+        assert_eq!(gets[1].fields["hit"], "false");
+
+        let evictions = subscriber.spans_named("cache.evict");
+        assert_eq!(evictions[1].fields["evicted"], "1");
+        assert_eq!(evictions[1].fields["entries"], "1");
+    }
+
+    #[tokio::test]
+    async fn test_custom_eviction_strategy_survives_clone() {
+        use crate::eviction::Evicted;
+
+        /// Evicts the key holding the longest value
+        struct LongestValue;
+
+        #[async_trait]
+        impl EvictionStrategy<String, String, ()> for LongestValue {
+            async fn evict(
+                &self,
+                entries: &mut HashMap<String, Vec<CacheEntry<String, String>>>,
+                _context: &EvictionContext,
+            ) -> Evicted<String, String, ()> {
+                let victim = entries
+                    .iter()
+                    .max_by_key(|(_, bucket)| bucket.iter().map(|e| e.value.len()).max())
+                    .map(|(key, _)| key.clone());
+                victim
+                    .and_then(|key| entries.remove_entry(&key))
+                    .into_iter()
+                    .collect()
+            }
+        }
+
+        let config = CacheConfig::default()
+            .with_max_total_entries(2)
+            .with_eviction_policy(crate::EvictionPolicy::Fifo);
+        let cache: Cache<String, String> = Cache::new(config, MemoryBackend::new())
+            .await
+            .unwrap()
+            .with_eviction_strategy(Box::new(LongestValue));
+        let clone = cache.clone();
+
+        clone
+            .put("long".to_string(), "xxxxxxxx".to_string())
+            .await
+            .unwrap();
+        clone.put("a".to_string(), "x".to_string()).await.unwrap();
+        clone.put("b".to_string(), "xx".to_string()).await.unwrap();
+
+        assert!(!cache.contains(&"long".to_string()).await.unwrap());
+        assert!(cache.contains(&"a".to_string()).await.unwrap());
+        assert!(cache.contains(&"b".to_string()).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_on_evict_callback() {
+        use std::sync::Mutex;
+
+        let evicted = Arc::new(Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&evicted);
+
+        let config = CacheConfig::default()
+            .with_max_total_entries(2)
+            .with_eviction_policy(crate::EvictionPolicy::Fifo);
+        let cache: Cache<String, String> = Cache::new(config, MemoryBackend::new())
+            .await
+            .unwrap()
+            .with_on_evict(
+                move |key: &String, entries: &[CacheEntry<String, String>]| {
+                    recorded
+                        .lock()
+                        .unwrap()
+                        .push((key.clone(), entries[0].value.clone()));
+                },
+            );
+
+        for i in 0..3 {
+            cache
+                .add_entry(CacheEntry::new(format!("k{i}"), format!("v{i}")))
+                .await
+                .unwrap();
+            tokio::time::sleep(std::time::Duration::from_millis(2)).await;
+        }
+
+        let evicted = evicted.lock().unwrap();
+        assert_eq!(*evicted, vec![("k0".to_string(), "v0".to_string())]);
+    }
+
+    #[tokio::test]
+    async fn test_eviction_count_shared_across_clones() {
+        let config = CacheConfig::default().with_max_total_entries(2);
+        let cache: Cache<String, String> = Cache::new(config, MemoryBackend::new()).await.unwrap();
+        let clone = cache.clone();
+
+        for i in 0..5 {
+            clone.put(format!("k{i}"), "v".to_string()).await.unwrap();
+        }
+
+        assert_eq!(cache.get_stats().await.evictions, 3);
+        assert_eq!(clone.get_stats().await.evictions, 3);
+    }
+
+    #[tokio::test]
+    async fn test_hit_miss_stats() {
+        let cache = create_cache().await;
+        cache.put("k".to_string(), "v".to_string()).await.unwrap();
+
+        cache.get(&"k".to_string()).await.unwrap();
+        cache.get(&"k".to_string()).await.unwrap();
+        cache.get(&"k".to_string()).await.unwrap();
+        cache.get(&"missing".to_string()).await.unwrap();
+
+        let stats = cache.clone().get_stats().await;
+        assert_eq!(stats.hits, 3);
+        assert_eq!(stats.misses, 1);
+        assert!((stats.hit_rate() - 0.75).abs() < f64::EPSILON);
+
+        // Clearing entries keeps lifetime stats
+        cache.clear().await.unwrap();
+        assert_eq!(cache.get_stats().await.hits, 3);
+
+        cache.reset_stats();
+        let stats = cache.get_stats().await;
+        assert_eq!(stats.hits, 0);
+        assert_eq!(stats.misses, 0);
+        assert_eq!(stats.hit_rate(), 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_reset_also_zeroes_counters() {
+        use crate::test_utils::TestBackend;
+
+        let backend = TestBackend::default();
+        let cache: Cache<String, String, (), TestBackend> =
+            Cache::new(persistent_config(), backend.clone())
+                .await
+                .unwrap();
+        cache.put("k".to_string(), "v".to_string()).await.unwrap();
+        cache.get(&"k".to_string()).await.unwrap();
+        cache.get(&"missing".to_string()).await.unwrap();
+        cache.flush().await.unwrap();
+
+        cache.clear().await.unwrap();
+        let stats = cache.get_stats().await;
+        assert_eq!((stats.hits, stats.misses), (1, 1));
+        cache.put("k".to_string(), "v".to_string()).await.unwrap();
+        assert_eq!(cache.operation_count.load(Ordering::Acquire), 1);
+
+        cache.reset().await.unwrap();
+        let stats = cache.get_stats().await;
+        assert_eq!((stats.hits, stats.misses, stats.total_keys), (0, 0, 0));
+        assert_eq!(cache.operation_count.load(Ordering::Acquire), 0);
+        assert!(backend.entries.read().await.is_empty());
+    }
+
+    fn persistent_config() -> CacheConfig {
+        let mut config = CacheConfig::default();
+        config.persistence.enabled = true;
+        config.persistence.load_on_startup = false;
+        config
+    }
+
+    #[tokio::test]
+    async fn test_save_dirty_writes_only_changed_keys() {
+        use crate::test_utils::TestBackend;
+
+        let backend = TestBackend::default();
+        let cache: Cache<String, String, (), TestBackend> =
+            Cache::new(persistent_config(), backend.clone())
+                .await
+                .unwrap();
+        for key in ["a", "b", "c"] {
+            cache.put(key.to_string(), "1".to_string()).await.unwrap();
+        }
+        cache.flush().await.unwrap();
+        cache.save_dirty().await.unwrap();
+        assert!(backend.saved_keys.read().await.is_empty());
+
+        cache.put("b".to_string(), "2".to_string()).await.unwrap();
+        cache
+            .update_metadata(&"c".to_string(), |_| {})
+            .await
+            .unwrap();
+        cache.get(&"a".to_string()).await.unwrap();
+        cache.save_dirty().await.unwrap();
+        assert_eq!(*backend.saved_keys.read().await, [["b", "c"]]);
+        assert_eq!(backend.entries.read().await["b"][0].value, "2");
+        assert_eq!(*backend.save_calls.read().await, 1);
+
+        // A failed save keeps the keys for the next one
+        cache.put("a".to_string(), "2".to_string()).await.unwrap();
+        *backend.failures.write().await = 1;
+        cache.save_dirty().await.unwrap_err();
+        cache.save_dirty().await.unwrap();
+        assert_eq!(backend.saved_keys.read().await[2], ["a"]);
+        assert_eq!(backend.entries.read().await["a"][0].value, "2");
+    }
+
+    #[tokio::test]
+    async fn test_flush_saves_immediately() {
+        use crate::test_utils::TestBackend;
+
+        let backend = TestBackend::default();
+        let cache: Cache<String, String, (), TestBackend> =
+            Cache::new(persistent_config(), backend.clone())
+                .await
+                .unwrap();
+        cache.put("k".to_string(), "v".to_string()).await.unwrap();
+        assert_eq!(*backend.save_calls.read().await, 0);
+
+        cache.flush().await.unwrap();
+        assert_eq!(*backend.save_calls.read().await, 1);
+        assert!(backend.entries.read().await.contains_key("k"));
+    }
+
+    #[tokio::test]
+    async fn test_backend_errors_are_retried() {
+        use crate::test_utils::TestBackend;
+        use crate::RetryConfig;
+
+        let backend = TestBackend::default();
+        backend.save(&HashMap::new()).await.unwrap();
+        *backend.failures.write().await = 2;
+        let mut config = persistent_config()
+            .with_retry(RetryConfig::new(3, std::time::Duration::from_millis(1)));
+        config.persistence.load_on_startup = true;
+        config.fail_on_load_error = true;
+        let cache: Cache<String, String, (), TestBackend> =
+            Cache::new(config, backend.clone()).await.unwrap();
+        assert_eq!(*backend.load_calls.read().await, 3);
+
+        cache.put("k".to_string(), "v".to_string()).await.unwrap();
+        *backend.failures.write().await = 2;
+        cache.flush().await.unwrap();
+        assert_eq!(*backend.save_calls.read().await, 4);
+        assert!(backend.entries.read().await.contains_key("k"));
+
+        *backend.failures.write().await = 1;
+        cache.remove(&"k".to_string()).await.unwrap();
+        assert!(!backend.entries.read().await.contains_key("k"));
+
+        // Running out of attempts returns the last error
+        *backend.failures.write().await = 5;
+        assert!(matches!(
+            cache.flush().await,
+            Err(CacheError::StorageBackend(_))
+        ));
+        assert_eq!(*backend.failures.read().await, 2);
+    }
+
+    #[cfg(feature = "json-serialization")]
+    #[tokio::test]
+    async fn test_clear_and_import_retry_backend_clears() {
+        use crate::test_utils::TestBackend;
+        use crate::RetryConfig;
+
+        let backend = TestBackend::default();
+        let config = persistent_config()
+            .with_retry(RetryConfig::new(3, std::time::Duration::from_millis(1)));
+        let cache: Cache<String, String, (), TestBackend> =
+            Cache::new(config, backend.clone()).await.unwrap();
+        cache.put("k".to_string(), "v".to_string()).await.unwrap();
+        cache.flush().await.unwrap();
+        let snapshot = cache.export_snapshot().await.unwrap();
+
+        *backend.failures.write().await = 2;
+        cache.clear().await.unwrap();
+        assert!(backend.entries.read().await.is_empty());
+
+        *backend.failures.write().await = 2;
+        cache.import_snapshot(&snapshot, false).await.unwrap();
+        assert_eq!(*backend.failures.read().await, 0);
+        assert!(cache.contains(&"k".to_string()).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_circuit_breaker_serves_from_memory() {
+        use crate::test_utils::TestBackend;
+        use crate::CircuitBreakerConfig;
+
+        let backend = TestBackend::default();
+        *backend.failures.write().await = usize::MAX;
+        let config = persistent_config()
+            .with_mode(CacheMode::WriteThrough)
+            .with_circuit_breaker(CircuitBreakerConfig::new(
+                2,
+                std::time::Duration::from_millis(50),
+            ));
+        let cache: Cache<String, String, (), TestBackend> =
+            Cache::new(config, backend.clone()).await.unwrap();
+        assert!(cache.get_stats().await.backend_healthy);
+
+        cache
+            .put("a".to_string(), "1".to_string())
+            .await
+            .unwrap_err();
+        cache
+            .put("b".to_string(), "2".to_string())
+            .await
+            .unwrap_err();
+        assert!(!cache.get_stats().await.backend_healthy);
+
+        // The backend is left alone while the breaker is open
+        cache.put("c".to_string(), "3".to_string()).await.unwrap();
+        assert_eq!(backend.saved_keys.read().await.len(), 2);
+        assert_eq!(
+            cache.get(&"a".to_string()).await.unwrap().as_deref(),
+            Some("1")
+        );
+        assert_eq!(
+            cache.remove(&"a".to_string()).await.unwrap().as_deref(),
+            Some("1")
+        );
+        assert!(cache.flush().await.is_err());
+
+        *backend.failures.write().await = 0;
+        tokio::time::sleep(std::time::Duration::from_millis(60)).await;
+        cache.put("d".to_string(), "4".to_string()).await.unwrap();
+        assert!(cache.get_stats().await.backend_healthy);
+        let mut saved: Vec<_> = backend.entries.read().await.keys().cloned().collect();
+        saved.sort();
+        assert_eq!(saved, ["b", "c", "d"]);
+
+        // A clear while the breaker is open reaches the backend with the
+        // next save
+        *backend.failures.write().await = usize::MAX;
+        for key in ["e", "f"] {
+            cache
+                .put(key.to_string(), "5".to_string())
+                .await
+                .unwrap_err();
+        }
+        cache.clear().await.unwrap();
+        *backend.failures.write().await = 0;
+        tokio::time::sleep(std::time::Duration::from_millis(60)).await;
+        cache.save_dirty().await.unwrap();
+        assert!(backend.entries.read().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_namespaced_caches_share_backend_in_isolation() {
+        let backend: MemoryBackend<String, String> = MemoryBackend::new();
+        let mut config = persistent_config();
+        config.persistence.load_on_startup = true;
+
+        let tenant_a: Cache<String, String> =
+            Cache::new(config.clone().with_namespace("a"), backend.clone())
+                .await
+                .unwrap();
+        let tenant_b: Cache<String, String> =
+            Cache::new(config.clone().with_namespace("b"), backend.clone())
+                .await
+                .unwrap();
+
+        tenant_a
+            .put("k".to_string(), "a".to_string())
+            .await
+            .unwrap();
+        tenant_b
+            .put("k".to_string(), "b".to_string())
+            .await
+            .unwrap();
+        tenant_a.flush().await.unwrap();
+        tenant_b.flush().await.unwrap();
+        tenant_b.clear().await.unwrap();
+
+        let reloaded: Cache<String, String> = Cache::new(config.with_namespace("a"), backend)
+            .await
+            .unwrap();
+        assert_eq!(
+            reloaded.get(&"k".to_string()).await.unwrap().as_deref(),
+            Some("a")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_duration_sync_saves_pending_writes() {
+        use crate::config::SyncStrategy;
+        use crate::test_utils::TestBackend;
+
+        let period = std::time::Duration::from_millis(20);
+        let mut config = persistent_config();
+        config.persistence.save_on_drop = false;
+        config.persistence = config
+            .persistence
+            .with_sync_strategy(SyncStrategy::EveryDuration(period));
+
+        let backend = TestBackend::default();
+        let cache: Cache<String, String, (), TestBackend> =
+            Cache::new(config, backend.clone()).await.unwrap();
+
+        // Nothing to save yet
+        tokio::time::sleep(period * 4).await;
+        assert_eq!(*backend.save_calls.read().await, 0);
+
+        cache.put("k".to_string(), "v".to_string()).await.unwrap();
+        tokio::time::sleep(period * 4).await;
+        assert_eq!(*backend.save_calls.read().await, 1);
+        assert!(backend.entries.read().await.contains_key("k"));
+
+        // The timer stops with the cache and releases the backend
+        drop(cache);
+        tokio::time::sleep(period * 4).await;
+        assert_eq!(Arc::strong_count(&backend.save_calls), 1);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_drop_saves_while_the_sync_timer_is_saving() {
+        use crate::config::SyncStrategy;
+        use crate::test_utils::TestBackend;
+
+        let period = std::time::Duration::from_millis(20);
+        let mut config = persistent_config();
+        config.persistence = config
+            .persistence
+            .with_sync_strategy(SyncStrategy::EveryDuration(period));
+
+        let backend = TestBackend::default();
+        let cache: Cache<String, String, (), TestBackend> =
+            Cache::new(config, backend.clone()).await.unwrap();
+        cache.put("k".to_string(), "v".to_string()).await.unwrap();
+
+        // Stall the timer's save, then let it finish while the cache drops
+        let gate = Arc::clone(&backend.save_gate).lock_owned().await;
+        tokio::time::sleep(period * 4).await;
+        assert_eq!(*backend.save_calls.read().await, 1);
+        tokio::spawn(async move {
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+            drop(gate);
+        });
+        drop(cache);
+        assert_eq!(*backend.save_calls.read().await, 2);
+    }
+
+    #[tokio::test]
+    async fn test_load_error_counted_or_propagated() {
+        use crate::test_utils::TestBackend;
+
+        let mut config = persistent_config();
+        config.persistence.load_on_startup = true;
+        config.persistence.save_on_drop = true;
+        let backend = TestBackend::default();
+        *backend.truncated_loads.write().await = Some(0);
+
+        let cache: Cache<String, String, (), TestBackend> =
+            Cache::new(config.clone(), backend.clone()).await.unwrap();
+        assert_eq!(cache.get_stats().await.load_errors, 1);
+        assert!(cache.is_empty().await.unwrap());
+        drop(cache);
+
+        let result: Result<Cache<String, String, (), TestBackend>> =
+            Cache::new(config.with_fail_on_load_error(true), backend.clone()).await;
+        assert!(matches!(result, Err(CacheError::StorageBackend(_))));
+        tokio::task::yield_now().await;
+        // Only the first cache's drop saved; the failed one left storage alone
+        assert_eq!(*backend.save_calls.read().await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_load_inserts_streamed_entries_until_error() {
+        use crate::test_utils::TestBackend;
+
+        let backend = TestBackend::default();
+        for key in ["a", "b"] {
+            let entry = CacheEntry::new(key.to_string(), "1".to_string());
+            backend
+                .entries
+                .write()
+                .await
+                .insert(key.to_string(), vec![entry]);
+        }
+        *backend.truncated_loads.write().await = Some(1);
+
+        let mut config = persistent_config();
+        config.persistence.load_on_startup = true;
+        let cache: Cache<String, String, (), TestBackend> =
+            Cache::new(config, backend).await.unwrap();
+
+        assert_eq!(cache.get_stats().await.load_errors, 1);
+        assert_eq!(
+            cache.peek(&"a".to_string()).await.unwrap().as_deref(),
+            Some("1")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sync_resets_operation_count() {
+        use crate::test_utils::TestBackend;
+
+        let mut config = persistent_config();
+        config.persistence.sync_interval = 2;
+        let backend = TestBackend::default();
+        let cache: Cache<String, String, (), TestBackend> =
+            Cache::new(config, backend.clone()).await.unwrap();
+
+        cache.put("a".to_string(), "v".to_string()).await.unwrap();
+        cache.sync().await.unwrap();
+        assert_eq!(*backend.save_calls.read().await, 1);
+
+        // The interval counts from the explicit sync
+        cache.put("b".to_string(), "v".to_string()).await.unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        assert_eq!(*backend.save_calls.read().await, 1);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_writes_trigger_one_save_per_interval() {
+        use crate::test_utils::TestBackend;
 
-    async fn create_cache() -> Cache<String, String> {
-        let config = CacheConfig::default();
-        let backend = MemoryBackend::new();
-        Cache::new(config, backend).await.unwrap()
+        let mut config = persistent_config();
+        config.persistence.sync_interval = 10;
+        let backend = TestBackend::default();
+        let cache: Cache<String, String, (), TestBackend> =
+            Cache::new(config, backend.clone()).await.unwrap();
+
+        let writers: Vec<_> = (0..8)
+            .map(|writer| {
+                let cache = cache.clone();
+                tokio::spawn(async move {
+                    for i in 0..25 {
+                        cache
+                            .put(format!("{writer}-{i}"), "v".to_string())
+                            .await
+                            .unwrap();
+                    }
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.await.unwrap();
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+
+        assert_eq!(*backend.save_calls.read().await, 20);
+        assert_eq!(cache.operation_count.load(Ordering::Acquire), 0);
     }
 
     #[tokio::test]
-    async fn test_cache_basic_operations() {
+    async fn test_get_version_and_versions_between() {
         let cache = create_cache().await;
+        let key = "k".to_string();
+        let start = chrono::Utc::now();
+        // Added out of order, so versions follow timestamps, not insertion
+        for (value, minutes) in [("v2", 2), ("v0", 0), ("v1", 1)] {
+            let mut entry = CacheEntry::new(key.clone(), value.to_string());
+            entry.timestamp = start + chrono::Duration::minutes(minutes);
+            cache.add_entry(entry).await.unwrap();
+        }
 
-        // Test put and get
+        assert_eq!(cache.get_version(&key, 0).await.unwrap().value, "v0");
+        assert_eq!(cache.get_version(&key, 2).await.unwrap().value, "v2");
+        assert!(cache.get_version(&key, 3).await.is_none());
+        assert!(cache.get_version(&"missing".to_string(), 0).await.is_none());
+
+        let between = cache
+            .get_versions_between(&key, start, start + chrono::Duration::minutes(2))
+            .await;
+        let values: Vec<_> = between.iter().map(|e| e.value.as_str()).collect();
+        assert_eq!(values, ["v0", "v1"]);
+        assert_eq!(between[1].access_count(), 1);
+        assert!(cache
+            .get_versions_between(&key, start - chrono::Duration::hours(1), start)
+            .await
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_eviction_low_watermark() {
+        let config = CacheConfig::default()
+            .with_max_total_entries(10)
+            .with_eviction_low_watermark(0.5);
+        let cache: Cache<String, String> = Cache::with_config(config).await.unwrap();
+        for i in 0..10 {
+            cache.put(format!("k{i}"), i.to_string()).await.unwrap();
+        }
+        assert_eq!(cache.len().await.unwrap(), 10);
+
+        // Crossing the limit evicts down to half of it
         cache
-            .put("key1".to_string(), "value1".to_string())
+            .put("k10".to_string(), "10".to_string())
             .await
             .unwrap();
-        let value = cache.get(&"key1".to_string()).await.unwrap();
-        assert_eq!(value, Some("value1".to_string()));
+        assert_eq!(cache.len().await.unwrap(), 5);
+        assert_eq!(cache.get_stats().await.evictions, 6);
+        assert!(cache.contains(&"k10".to_string()).await.unwrap());
+    }
 
-        // Test contains
-        assert!(cache.contains(&"key1".to_string()).await.unwrap());
-        assert!(!cache.contains(&"key2".to_string()).await.unwrap());
+    #[tokio::test]
+    async fn test_event_channel_publishes_operations() {
+        let (sender, _) = broadcast::channel(16);
+        let config = CacheConfig::default()
+            .with_max_total_entries(1)
+            .with_expire_on_access(true);
+        let cache: Cache<String, String> = Cache::with_config(config)
+            .await
+            .unwrap()
+            .with_event_channel(sender);
+        let mut events = cache.subscribe();
 
-        // Test len
-        assert_eq!(cache.len().await.unwrap(), 1);
+        let a = "a".to_string();
+        let b = "b".to_string();
+        cache.put(a.clone(), "1".to_string()).await.unwrap();
+        cache.get(&a).await.unwrap();
+        cache.get(&b).await.unwrap();
+        cache.put(b.clone(), "2".to_string()).await.unwrap();
+        cache
+            .put_with_ttl(b.clone(), "3".to_string(), chrono::Duration::seconds(-1))
+            .await
+            .unwrap();
+        cache.get(&b).await.unwrap();
 
-        // Test remove
-        let removed = cache.remove(&"key1".to_string()).await.unwrap();
-        assert_eq!(removed, Some("value1".to_string()));
-        assert_eq!(cache.len().await.unwrap(), 0);
+        let mut received = Vec::new();
+        while let Ok(event) = events.try_recv() {
+            received.push(event);
+        }
+        assert_eq!(
+            received,
+            [
+                CacheEvent::Put(a.clone()),
+                CacheEvent::Get(a.clone()),
+                CacheEvent::Miss(b.clone()),
+                CacheEvent::Put(b.clone()),
+                CacheEvent::Evict(a),
+                CacheEvent::Put(b.clone()),
+                CacheEvent::Expire(b.clone()),
+                CacheEvent::Miss(b),
+            ]
+        );
     }
 
     #[tokio::test]
-    async fn test_cache_clear() {
+    async fn test_subscribe_without_channel_is_closed() {
         let cache = create_cache().await;
+        let mut events = cache.subscribe();
+        cache.put("k".to_string(), "v".to_string()).await.unwrap();
+        assert!(matches!(
+            events.try_recv(),
+            Err(broadcast::error::TryRecvError::Closed)
+        ));
+    }
 
+    #[tokio::test]
+    async fn test_sharded_cache_spans_every_shard() {
+        use crate::test_utils::TestBackend;
+
+        let backend = TestBackend::default();
+        let config = persistent_config().with_shard_count(4);
+        let cache: Cache<String, String, (), TestBackend> =
+            Cache::new(config, backend.clone()).await.unwrap();
+        assert_eq!(cache.entries.count(), 4);
+
+        let items: Vec<_> = (0..32).map(|i| (format!("k{i}"), i.to_string())).collect();
+        cache.put_many(items).await.unwrap();
         cache
-            .put("key1".to_string(), "value1".to_string())
-            .await
-            .unwrap();
-        cache
-            .put("key2".to_string(), "value2".to_string())
+            .put("solo".to_string(), "x".to_string())
             .await
             .unwrap();
 
-        assert_eq!(cache.len().await.unwrap(), 2);
+        assert_eq!(
+            cache.get(&"k7".to_string()).await.unwrap().as_deref(),
+            Some("7")
+        );
+        assert_eq!(cache.len().await.unwrap(), 33);
+        assert_eq!(cache.get_stats().await.total_keys, 33);
+        assert_eq!(cache.keys().await.len(), 33);
+        assert_eq!(cache.search(&SearchQuery::new()).await.len(), 33);
+
+        cache.sync().await.unwrap();
+        assert_eq!(backend.entries.read().await.len(), 33);
 
         cache.clear().await.unwrap();
-        assert_eq!(cache.len().await.unwrap(), 0);
-        assert!(!cache.contains(&"key1".to_string()).await.unwrap());
+        assert!(cache.is_empty().await.unwrap());
     }
 
     #[tokio::test]
-    async fn test_entry_limits_and_eviction() {
-        let config = CacheConfig {
-            max_entries_per_key: 2,
-            max_total_entries: 3,
-            ..CacheConfig::default()
-        };
-        let backend: MemoryBackend<String, String> = MemoryBackend::new();
-        let cache: Cache<String, String> = Cache::new(config, backend).await.unwrap();
+    async fn test_sharded_save_dirty_hands_over_only_dirty_entries() {
+        use crate::test_utils::TestBackend;
 
-        cache
-            .add_entry(CacheEntry::new("k1".to_string(), "v1".to_string()))
-            .await
-            .unwrap();
-        cache
-            .add_entry(CacheEntry::new("k1".to_string(), "v2".to_string()))
+        let backend = TestBackend::default();
+        let config = persistent_config().with_shard_count(4);
+        let cache: Cache<String, String, (), TestBackend> =
+            Cache::new(config, backend.clone()).await.unwrap();
+        let items: Vec<_> = (0..32).map(|i| (format!("k{i}"), i.to_string())).collect();
+        cache.put_many(items).await.unwrap();
+        cache.flush().await.unwrap();
+
+        cache.put("k3".to_string(), "x".to_string()).await.unwrap();
+        cache.put("k9".to_string(), "y".to_string()).await.unwrap();
+        cache.save_dirty().await.unwrap();
+        assert_eq!(*backend.saved_keys.read().await, [["k3", "k9"]]);
+        assert_eq!(*backend.saved_entry_counts.read().await, [2]);
+
+        let stored = backend.entries.read().await;
+        assert_eq!(stored.len(), 32);
+        assert_eq!(stored["k3"][0].value, "x");
+    }
+
+    #[tokio::test]
+    async fn test_shards_split_the_capacity() {
+        let config = CacheConfig::default()
+            .with_shard_count(2)
+            .with_max_total_entries(10);
+        let cache: Cache<String, String> = Cache::with_config(config).await.unwrap();
+        for i in 0..100 {
+            cache.put(format!("k{i}"), i.to_string()).await.unwrap();
+        }
+
+        // Each shard holds at most half the total
+        assert_eq!(cache.len().await.unwrap(), 10);
+        for index in 0..2 {
+            assert_eq!(cache.entries.shard(index).read().await.len(), 5);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_uneven_shard_shares_add_up_to_the_total() {
+        let config = CacheConfig::default()
+            .with_shard_count(4)
+            .with_max_total_entries(10);
+        let cache: Cache<String, String> = Cache::with_config(config).await.unwrap();
+        for i in 0..100 {
+            cache.put(format!("k{i}"), i.to_string()).await.unwrap();
+        }
+
+        assert_eq!(cache.len().await.unwrap(), 10);
+        let mut sizes = Vec::new();
+        for index in 0..4 {
+            sizes.push(cache.entries.shard(index).read().await.len());
+        }
+        assert_eq!(sizes, [3, 3, 2, 2]);
+    }
+
+    #[tokio::test]
+    async fn test_per_shard_strategies_are_separate() {
+        let config = CacheConfig::default()
+            .with_shard_count(2)
+            .with_max_total_entries(10);
+        let cache: Cache<String, String> = Cache::with_config(config)
             .await
-            .unwrap();
-        cache
-            .add_entry(CacheEntry::new("k1".to_string(), "v3".to_string()))
+            .unwrap()
+            .with_eviction_strategy_per_shard(|| Box::new(crate::eviction::ArcEviction::new()));
+        assert!(!Arc::ptr_eq(
+            &cache.eviction_strategies[0],
+            &cache.eviction_strategies[1]
+        ));
+
+        for i in 0..100 {
+            cache.put(format!("k{i}"), i.to_string()).await.unwrap();
+        }
+        assert_eq!(cache.len().await.unwrap(), 10);
+    }
+
+    #[cfg(feature = "tracing")]
+    #[tokio::test]
+    async fn test_sharded_tracing_spans_name_the_shard() {
+        use crate::test_utils::RecordingSubscriber;
+
+        let subscriber = Arc::new(RecordingSubscriber::default());
+        let _guard = tracing::subscriber::set_default(Arc::clone(&subscriber));
+
+        let config = CacheConfig::default().with_shard_count(2);
+        let cache: Cache<String, String> = Cache::with_config(config).await.unwrap();
+        let key = "a".to_string();
+        cache.put(key.clone(), "1".to_string()).await.unwrap();
+
+        let puts = subscriber.spans_named("cache.put");
+        let shard = cache.entries.index_of(&key).to_string();
+        assert_eq!(puts[0].fields["shard"], shard);
+        assert_eq!(puts[0].fields["keys"], "1");
+        assert_eq!(
+            subscriber.spans_named("cache.evict")[0].fields["shard"],
+            shard
+        );
+    }
+
+    #[cfg(feature = "json-serialization")]
+    #[tokio::test]
+    async fn test_snapshot_round_trip() {
+        let source = create_cache().await;
+        source.put("a".to_string(), "1".to_string()).await.unwrap();
+        source
+            .add_entry(CacheEntry::new("a".to_string(), "2".to_string()))
             .await
             .unwrap();
+        source.put("b".to_string(), "3".to_string()).await.unwrap();
+        let snapshot = source.export_snapshot().await.unwrap();
 
-        let k1_entries = cache.get_entries(&"k1".to_string()).await.unwrap();
-        assert_eq!(k1_entries.len(), 2);
-        assert_eq!(k1_entries[0].value, "v2");
-        assert_eq!(k1_entries[1].value, "v3");
-
-        cache
-            .add_entry(CacheEntry::new("k2".to_string(), "v".to_string()))
+        let target = create_cache().await;
+        target
+            .put("b".to_string(), "old".to_string())
             .await
             .unwrap();
-        cache
-            .add_entry(CacheEntry::new("k3".to_string(), "v".to_string()))
+        target
+            .put("c".to_string(), "kept".to_string())
             .await
             .unwrap();
+        target.import_snapshot(&snapshot, true).await.unwrap();
+        assert_eq!(target.version_count(&"a".to_string()).await, 2);
+        // The target's value for "b" was written last, so it wins
+        assert_eq!(
+            target.peek(&"b".to_string()).await.unwrap().as_deref(),
+            Some("old")
+        );
+        assert!(target.contains(&"c".to_string()).await.unwrap());
 
-        assert!(cache.len().await.unwrap() <= 3);
+        target.import_snapshot(&snapshot, false).await.unwrap();
+        assert!(!target.contains(&"c".to_string()).await.unwrap());
+        assert_eq!(target.len().await.unwrap(), 3);
+
+        // A corrupt snapshot is rejected without touching the cache
+        assert!(target.import_snapshot(b"garbage", false).await.is_err());
+        assert_eq!(target.len().await.unwrap(), 3);
     }
 
+    #[cfg(feature = "json-serialization")]
     #[tokio::test]
-    async fn test_cache_entries_search_stats() {
-        let cache = create_cache().await;
+    async fn test_merge_snapshot_policies() {
+        let entry = |value: &str, age: i64| {
+            let mut entry = CacheEntry::new("k".to_string(), value.to_string());
+            entry.timestamp = chrono::Utc::now() - chrono::Duration::seconds(age);
+            entry
+        };
+
+        let source = create_cache().await;
+        source.add_entry(entry("imported", 10)).await.unwrap();
+        let snapshot = source.export_snapshot().await.unwrap();
+
+        let merged = |policy: MergePolicy, existing_age: i64| {
+            let snapshot = snapshot.clone();
+            let existing = entry("existing", existing_age);
+            async move {
+                let config = CacheConfig::default().with_max_entries_per_key(1);
+                let cache: Cache<String, String> =
+                    Cache::new(config, MemoryBackend::new()).await.unwrap();
+                cache.add_entry(existing).await.unwrap();
+                cache.merge_snapshot(&snapshot, policy).await.unwrap();
+                cache
+                    .get_entries(&"k".to_string())
+                    .await
+                    .unwrap()
+                    .into_iter()
+                    .map(|e| e.value)
+                    .collect::<Vec<_>>()
+            }
+        };
+
+        assert_eq!(merged(MergePolicy::KeepExisting, 20).await, ["existing"]);
+        assert_eq!(merged(MergePolicy::Overwrite, 0).await, ["imported"]);
+        assert_eq!(
+            merged(MergePolicy::KeepNewestByTimestamp, 20).await,
+            ["imported"]
+        );
+        assert_eq!(
+            merged(MergePolicy::KeepNewestByTimestamp, 0).await,
+            ["existing"]
+        );
+        // Concat trims back to one entry per key, dropping the oldest
+        assert_eq!(merged(MergePolicy::Concat, 20).await, ["imported"]);
+    }
+
+    #[tokio::test]
+    async fn test_backend_accessor_and_compact() {
+        use crate::test_utils::TestBackend;
+
+        let cache: Cache<String, String, (), TestBackend> =
+            Cache::new(persistent_config(), TestBackend::default())
+                .await
+                .unwrap();
+        cache.put("k".to_string(), "v".to_string()).await.unwrap();
+        cache.sync().await.unwrap();
+
+        assert!(cache.backend().entries.read().await.contains_key("k"));
+        cache.compact().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_load_keys_warms_only_requested_keys() {
+        use crate::backends::memory::MemoryBackend;
+
+        let backend = MemoryBackend::new();
+        let stored: crate::storage::EntryMap<String, String, ()> = ["a", "b", "c"]
+            .into_iter()
+            .map(|key| {
+                (
+                    key.to_string(),
+                    vec![CacheEntry::new(key.to_string(), format!("{key}-stored"))],
+                )
+            })
+            .collect();
+        backend.save(&stored).await.unwrap();
 
+        let cache: Cache<String, String> = Cache::new(persistent_config(), backend).await.unwrap();
         cache
-            .add_entry(CacheEntry::new("key".to_string(), "v1".to_string()))
+            .put("a".to_string(), "a-local".to_string())
             .await
             .unwrap();
-        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
         cache
-            .add_entry(CacheEntry::new("key".to_string(), "v2".to_string()))
+            .load_keys(&["a".to_string(), "c".to_string(), "missing".to_string()])
             .await
             .unwrap();
 
-        let entries = cache.get_entries(&"key".to_string()).await.unwrap();
-        assert_eq!(entries.len(), 2);
-        let latest = cache.get_latest(&"key".to_string()).await.unwrap();
-        assert_eq!(latest.value, "v2");
+        assert_eq!(cache.len().await.unwrap(), 2);
+        assert_eq!(
+            cache.get(&"a".to_string()).await.unwrap().as_deref(),
+            Some("a-stored")
+        );
+        assert_eq!(
+            cache.get(&"c".to_string()).await.unwrap().as_deref(),
+            Some("c-stored")
+        );
+        assert!(!cache.contains(&"b".to_string()).await.unwrap());
+    }
 
-        let results = cache.search(&SearchQuery::new().with_pattern("key")).await;
-        assert_eq!(results.len(), 2);
+    #[tokio::test]
+    async fn test_load_keys_past_capacity_evicts_to_the_limit() {
+        let backend = MemoryBackend::new();
+        let keys: Vec<String> = (0..5).map(|i| format!("k{i}")).collect();
+        let stored: crate::storage::EntryMap<String, String, ()> = keys
+            .iter()
+            .map(|key| {
+                (
+                    key.clone(),
+                    vec![CacheEntry::new(key.clone(), "v".to_string())],
+                )
+            })
+            .collect();
+        backend.save(&stored).await.unwrap();
 
-        // Add expired entry for stats
-        let expired = CacheEntry::new("expired".to_string(), "v".to_string())
-            .with_ttl(chrono::Duration::seconds(-1));
-        cache.add_entry(expired).await.unwrap();
+        let config = persistent_config().with_max_total_entries(3);
+        let cache: Cache<String, String> = Cache::new(config, backend).await.unwrap();
+        cache.load_keys(&keys).await.unwrap();
 
-        let stats = cache.get_stats().await;
-        assert_eq!(stats.total_entries, 3);
-        assert_eq!(stats.expired_count, 1);
-        assert_eq!(stats.total_access_count, 3); // accesses from get_entries/get_latest
+        assert_eq!(cache.len().await.unwrap(), 3);
     }
 
     #[tokio::test]
-    async fn test_empty_cache_stats() {
-        let cache = create_cache().await;
-        let stats = cache.get_stats().await;
-        assert_eq!(stats.total_entries, 0);
-        assert_eq!(stats.total_keys, 0);
-        assert_eq!(stats.total_access_count, 0);
-        assert_eq!(stats.expired_count, 0);
+    async fn test_write_through_saves_every_write() {
+        use crate::test_utils::TestBackend;
+
+        let backend = TestBackend::default();
+        let config = persistent_config().with_mode(CacheMode::WriteThrough);
+        let cache: Cache<String, String, (), TestBackend> =
+            Cache::new(config, backend.clone()).await.unwrap();
+
+        cache.put("a".to_string(), "v".to_string()).await.unwrap();
+        assert!(backend.entries.read().await.contains_key("a"));
+        cache.put("b".to_string(), "v".to_string()).await.unwrap();
+        cache.remove(&"a".to_string()).await.unwrap();
+        assert!(!backend.entries.read().await.contains_key("a"));
+
+        // Each write saves only the key it changed
+        assert_eq!(*backend.save_calls.read().await, 0);
+        assert_eq!(*backend.saved_keys.read().await, [["a"], ["b"]]);
+    }
+
+    #[tokio::test]
+    async fn test_read_through_consults_backend_on_miss() {
+        use crate::test_utils::TestBackend;
+
+        let backend = TestBackend::default();
+        backend.entries.write().await.insert(
+            "a".to_string(),
+            vec![CacheEntry::new("a".to_string(), "stored".to_string())],
+        );
+
+        let config = persistent_config().with_mode(CacheMode::ReadThrough);
+        let cache: Cache<String, String, (), TestBackend> =
+            Cache::new(config, backend.clone()).await.unwrap();
+        assert!(!cache.contains(&"a".to_string()).await.unwrap());
+
+        assert_eq!(
+            cache.get(&"a".to_string()).await.unwrap().as_deref(),
+            Some("stored")
+        );
+        assert!(cache.contains(&"a".to_string()).await.unwrap());
+        assert!(cache.get(&"b".to_string()).await.unwrap().is_none());
+
+        // Write-behind leaves misses alone
+        let cache: Cache<String, String, (), TestBackend> =
+            Cache::new(persistent_config(), backend).await.unwrap();
+        assert!(cache.get(&"a".to_string()).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_shared_save_limit_serializes_caches() {
+        use crate::test_utils::TestBackend;
+
+        let backend = TestBackend::default();
+        let limit = Arc::new(Semaphore::new(1));
+        let mut config = persistent_config();
+        config.persistence = config.persistence.with_save_limit(limit);
+        config.persistence.save_on_drop = false;
+        let first: Cache<String, String, (), TestBackend> =
+            Cache::new(config.clone(), backend.clone()).await.unwrap();
+        let second: Cache<String, String, (), TestBackend> =
+            Cache::new(config, backend.clone()).await.unwrap();
+
+        let gate = backend.save_gate.lock().await;
+        let syncs = tokio::spawn(async move {
+            let (a, b) = tokio::join!(first.sync(), second.sync());
+            a.and(b)
+        });
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+
+        // The second save waits for the first one's permit
+        assert_eq!(*backend.save_calls.read().await, 1);
+        drop(gate);
+        syncs.await.unwrap().unwrap();
+        assert_eq!(*backend.save_calls.read().await, 2);
+    }
+
+    #[tokio::test]
+    async fn test_sync_without_persistence_is_noop() {
+        use crate::test_utils::TestBackend;
+
+        let backend = TestBackend::default();
+        let cache: Cache<String, String, (), TestBackend> =
+            Cache::new(CacheConfig::default(), backend.clone())
+                .await
+                .unwrap();
+        cache.put("k".to_string(), "v".to_string()).await.unwrap();
+        cache.sync().await.unwrap();
+        assert_eq!(*backend.save_calls.read().await, 0);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_drop_saves_synchronously_on_multi_thread_runtime() {
+        use crate::test_utils::TestBackend;
+
+        let backend = TestBackend::default();
+        let cache: Cache<String, String, (), TestBackend> =
+            Cache::new(persistent_config(), backend.clone())
+                .await
+                .unwrap();
+        cache.put("k".to_string(), "v".to_string()).await.unwrap();
+
+        // Dropping a clone leaves the cache open, so nothing is saved
+        drop(cache.clone());
+        assert_eq!(*backend.save_calls.read().await, 0);
+
+        drop(cache);
+        assert_eq!(*backend.save_calls.read().await, 1);
+        assert!(backend.entries.read().await.contains_key("k"));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_drop_flush_timeout_bounds_final_save() {
+        use crate::test_utils::TestBackend;
+
+        let mut config = persistent_config();
+        config.persistence = config
+            .persistence
+            .with_drop_flush_timeout(std::time::Duration::from_millis(50));
+        let backend = TestBackend::default();
+        let cache: Cache<String, String, (), TestBackend> =
+            Cache::new(config, backend.clone()).await.unwrap();
+
+        // A stalled save is given up on once the timeout passes
+        let _gate = backend.save_gate.lock().await;
+        let started = std::time::Instant::now();
+        drop(cache);
+        assert!(started.elapsed() < std::time::Duration::from_secs(5));
+        assert_eq!(*backend.save_calls.read().await, 1);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_drop_save_waits_for_save_permits() {
+        use crate::test_utils::TestBackend;
+
+        let limit = Arc::new(Semaphore::new(1));
+        let mut config = persistent_config();
+        config.persistence = config
+            .persistence
+            .with_save_limit(Arc::clone(&limit))
+            .with_drop_flush_timeout(std::time::Duration::from_millis(50));
+        let backend = TestBackend::default();
+
+        // A save already holding the permit keeps the drop from saving
+        let held = limit.acquire().await.unwrap();
+        let cache: Cache<String, String, (), TestBackend> =
+            Cache::new(config.clone(), backend.clone()).await.unwrap();
+        cache.put("a".to_string(), "v".to_string()).await.unwrap();
+        drop(cache);
+        assert_eq!(*backend.save_calls.read().await, 0);
+
+        drop(held);
+        let cache: Cache<String, String, (), TestBackend> =
+            Cache::new(config, backend.clone()).await.unwrap();
+        cache.put("a".to_string(), "v".to_string()).await.unwrap();
+        drop(cache);
+        assert_eq!(*backend.save_calls.read().await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_drop_falls_back_to_spawn_on_current_thread_runtime() {
+        use crate::test_utils::TestBackend;
+
+        let backend = TestBackend::default();
+        let cache: Cache<String, String, (), TestBackend> =
+            Cache::new(persistent_config(), backend.clone())
+                .await
+                .unwrap();
+        cache.put("k".to_string(), "v".to_string()).await.unwrap();
+
+        drop(cache);
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        assert_eq!(*backend.save_calls.read().await, 1);
     }
 
     #[tokio::test]
@@ -0,0 +1,81 @@
+//! Sources of the current time for expiry and eviction
+
+use chrono::{DateTime, Utc};
+use std::sync::Mutex;
+
+/// Where a cache gets "now" from
+///
+/// A cache asks its clock whenever it stamps, expires or ages entries. The
+/// default is [`SystemClock`]; tests can install a [`ManualClock`] with
+/// [`Cache::with_clock`](crate::Cache::with_clock) to move time forward
+/// without sleeping.
+pub trait Clock: Send + Sync {
+    /// The current time
+    fn now(&self) -> DateTime<Utc>;
+}
+
+/// Clock reading the system time
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// Clock that only moves when told to
+#[derive(Debug)]
+pub struct ManualClock {
+    now: Mutex<DateTime<Utc>>,
+}
+
+impl ManualClock {
+    /// Create a clock stopped at `start`
+    pub fn new(start: DateTime<Utc>) -> Self {
+        Self {
+            now: Mutex::new(start),
+        }
+    }
+
+    /// Set the time
+    pub fn set(&self, now: DateTime<Utc>) {
+        *self.now.lock().unwrap() = now;
+    }
+
+    /// Move the time forward by `duration`, or back if it is negative
+    pub fn advance(&self, duration: chrono::Duration) {
+        *self.now.lock().unwrap() += duration;
+    }
+}
+
+impl Default for ManualClock {
+    /// A clock stopped at the current system time
+    fn default() -> Self {
+        Self::new(Utc::now())
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> DateTime<Utc> {
+        *self.now.lock().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_manual_clock_moves_only_when_told() {
+        let start = Utc::now();
+        let clock = ManualClock::new(start);
+        assert_eq!(clock.now(), start);
+
+        clock.advance(chrono::Duration::seconds(5));
+        assert_eq!(clock.now(), start + chrono::Duration::seconds(5));
+
+        clock.set(start);
+        assert_eq!(clock.now(), start);
+    }
+}
@@ -0,0 +1,192 @@
+//! Compression helpers for persisted cache data
+
+use std::io::{Read, Write};
+
+use flate2::read::{DeflateDecoder, GzDecoder, ZlibDecoder};
+use flate2::write::{DeflateEncoder, GzEncoder, ZlibEncoder};
+use flate2::Compression;
+
+use crate::config::CompressionAlgorithm;
+use crate::error::{CacheError, Result};
+
+impl CompressionAlgorithm {
+    /// Get the file extension appended to compressed files
+    pub fn extension(&self) -> &'static str {
+        match self {
+            CompressionAlgorithm::Gzip => "gz",
+            CompressionAlgorithm::Zlib => "zz",
+            CompressionAlgorithm::Deflate => "deflate",
+            #[cfg(feature = "zstd-compression")]
+            CompressionAlgorithm::Zstd => "zst",
+        }
+    }
+
+    /// Look up the algorithm for a compressed file extension
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        Self::all()
+            .iter()
+            .copied()
+            .find(|algorithm| algorithm.extension() == extension)
+    }
+
+    /// All algorithms available with the enabled features
+    pub fn all() -> &'static [CompressionAlgorithm] {
+        &[
+            CompressionAlgorithm::Gzip,
+            CompressionAlgorithm::Zlib,
+            CompressionAlgorithm::Deflate,
+            #[cfg(feature = "zstd-compression")]
+            CompressionAlgorithm::Zstd,
+        ]
+    }
+}
+
+/// Compress bytes with the given algorithm and level
+pub fn compress(algorithm: CompressionAlgorithm, level: u32, data: &[u8]) -> Result<Vec<u8>> {
+    let compressed = match algorithm {
+        CompressionAlgorithm::Gzip => {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::new(level));
+            encoder.write_all(data).and_then(|_| encoder.finish())
+        }
+        CompressionAlgorithm::Zlib => {
+            let mut encoder = ZlibEncoder::new(Vec::new(), Compression::new(level));
+            encoder.write_all(data).and_then(|_| encoder.finish())
+        }
+        CompressionAlgorithm::Deflate => {
+            let mut encoder = DeflateEncoder::new(Vec::new(), Compression::new(level));
+            encoder.write_all(data).and_then(|_| encoder.finish())
+        }
+        #[cfg(feature = "zstd-compression")]
+        CompressionAlgorithm::Zstd => zstd::encode_all(data, level as i32),
+    };
+    compressed.map_err(|e| CacheError::Compression(e.to_string()))
+}
+
+/// Decompress bytes produced by `compress` with the same algorithm
+pub fn decompress(algorithm: CompressionAlgorithm, data: &[u8]) -> Result<Vec<u8>> {
+    let decompressed = match algorithm {
+        CompressionAlgorithm::Gzip => read_all(GzDecoder::new(data)),
+        CompressionAlgorithm::Zlib => read_all(ZlibDecoder::new(data)),
+        CompressionAlgorithm::Deflate => read_all(DeflateDecoder::new(data)),
+        #[cfg(feature = "zstd-compression")]
+        CompressionAlgorithm::Zstd => zstd::decode_all(data),
+    };
+    decompressed.map_err(|e| CacheError::Compression(e.to_string()))
+}
+
+fn read_all<R: Read>(mut reader: R) -> std::io::Result<Vec<u8>> {
+    let mut buffer = Vec::new();
+    reader.read_to_end(&mut buffer)?;
+    Ok(buffer)
+}
+
+/// Characters of the standard base64 alphabet, by value
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Encode bytes as standard, padded base64, for compressed data stored in
+/// text formats
+pub(crate) fn encode_base64(data: &[u8]) -> String {
+    let mut encoded = String::with_capacity((data.len() + 2) / 3 * 4);
+    for chunk in data.chunks(3) {
+        let byte = |index: usize| u32::from(chunk.get(index).copied().unwrap_or(0));
+        let group = (byte(0) << 16) | (byte(1) << 8) | byte(2);
+        for index in 0..4 {
+            if index <= chunk.len() {
+                let value = (group >> (18 - 6 * index)) & 63;
+                encoded.push(char::from(BASE64_ALPHABET[value as usize]));
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+/// Decode standard, padded base64, or `None` if `encoded` is not valid
+pub(crate) fn decode_base64(encoded: &str) -> Option<Vec<u8>> {
+    fn value(c: u8) -> Option<u32> {
+        let value = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => return None,
+        };
+        Some(u32::from(value))
+    }
+
+    let encoded = encoded.as_bytes();
+    if encoded.len() % 4 != 0 {
+        return None;
+    }
+    let groups = encoded.len() / 4;
+    let mut data = Vec::with_capacity(groups * 3);
+    for (index, chunk) in encoded.chunks(4).enumerate() {
+        // Only the last group may be padded, and by two characters at most
+        let padding = chunk.iter().rev().take_while(|&&c| c == b'=').count();
+        if padding > 2 || (padding > 0 && index + 1 < groups) {
+            return None;
+        }
+        let mut group = 0;
+        for &c in &chunk[..4 - padding] {
+            group = (group << 6) | value(c)?;
+        }
+        group <<= 6 * padding;
+        data.extend_from_slice(&group.to_be_bytes()[1..4 - padding]);
+    }
+    Some(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip_all_algorithms() {
+        let data = b"threatflux ".repeat(100);
+        for &algorithm in CompressionAlgorithm::all() {
+            let compressed = compress(algorithm, 6, &data).unwrap();
+            assert!(
+                compressed.len() < data.len(),
+                "{algorithm:?} did not shrink"
+            );
+            assert_eq!(decompress(algorithm, &compressed).unwrap(), data);
+        }
+    }
+
+    #[test]
+    fn test_extension_lookup() {
+        for &algorithm in CompressionAlgorithm::all() {
+            assert_eq!(
+                CompressionAlgorithm::from_extension(algorithm.extension()),
+                Some(algorithm)
+            );
+        }
+        assert_eq!(CompressionAlgorithm::from_extension("json"), None);
+    }
+
+    #[test]
+    fn test_base64_round_trip() {
+        assert_eq!(encode_base64(&[1, 2, 255]), "AQL/");
+        assert_eq!(encode_base64(b"ab"), "YWI=");
+        assert_eq!(encode_base64(b"a"), "YQ==");
+        let data: Vec<u8> = (0..=255).collect();
+        for len in 0..8 {
+            let encoded = encode_base64(&data[..len]);
+            assert_eq!(decode_base64(&encoded).unwrap(), data[..len]);
+        }
+        assert_eq!(decode_base64(&encode_base64(&data)).unwrap(), data);
+
+        for invalid in ["YQ=", "Y===", "YQ==YQ==", "YQ!="] {
+            assert_eq!(decode_base64(invalid), None, "{invalid}");
+        }
+    }
+
+    #[test]
+    fn test_decompress_invalid_data() {
+        let result = decompress(CompressionAlgorithm::Gzip, b"not compressed");
+        assert!(matches!(result, Err(CacheError::Compression(_))));
+    }
+}
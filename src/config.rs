@@ -2,41 +2,144 @@
 
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
+use std::sync::Arc;
 use std::time::Duration;
+use tokio::sync::Semaphore;
+
+use crate::storage::SerializationFormat;
+use crate::{CacheError, Result};
 
 /// Cache configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CacheConfig {
-    /// Maximum number of entries per key
+    /// Maximum number of entries per key; must be at least 1
     pub max_entries_per_key: usize,
-    /// Maximum total number of entries
+    /// Which entry to drop when a key exceeds `max_entries_per_key`
+    pub per_key_eviction: PerKeyEviction,
+    /// Keep each key's entries in timestamp order, oldest first, instead of
+    /// insertion order
+    ///
+    /// Finding a key's latest entry then takes the last one instead of
+    /// scanning them all. Entries stamped out of order are inserted at their
+    /// place, and entries loaded or imported are sorted.
+    #[serde(default)]
+    pub keep_buckets_sorted: bool,
+    /// Maximum total number of entries; must be at least 1
     pub max_total_entries: usize,
+    /// Maximum total size in bytes, measured with `EntryMetadata::size_bytes`
+    pub max_total_bytes: Option<u64>,
+    /// Fraction of `max_total_entries` that eviction brings the cache down
+    /// to once it is over the limit; `None` stops just below the limit
+    #[serde(default)]
+    pub eviction_low_watermark: Option<f32>,
     /// Eviction policy to use
     pub eviction_policy: EvictionPolicy,
     /// Persistence configuration
     pub persistence: PersistenceConfig,
+    /// How reads and writes reach the backend while persistence is enabled
+    #[serde(default)]
+    pub mode: CacheMode,
     /// Enable compression for stored values
     #[cfg(feature = "compression")]
     pub compression: Option<CompressionConfig>,
+    /// Serialized size in bytes above which a value is stored compressed
+    ///
+    /// Only the persisted copy is compressed, with the algorithm and level
+    /// of `compression` (or its defaults); values in memory stay as they
+    /// are. Only `FilesystemBackend` honors it, on its own or as a tier of a
+    /// `TieredBackend`; every other backend ignores it.
+    #[cfg(feature = "compression")]
+    #[serde(default)]
+    pub value_compression_threshold: Option<usize>,
+    /// Fill in `size_bytes` for entries added without one, as
+    /// `Cache::with_measured_sizes` does
+    #[cfg(feature = "json-serialization")]
+    #[serde(default)]
+    pub measure_sizes: bool,
     /// Default TTL for entries (if not specified per-entry)
     pub default_ttl: Option<Duration>,
+    /// Most the default TTL is lengthened or shortened by, chosen at random
+    /// for each entry, so entries added together do not all expire together
+    #[serde(default)]
+    pub ttl_jitter: Option<Duration>,
+    /// Treat expired entries as misses on read and drop them from the cache
+    pub expire_on_access: bool,
+    /// Fail `Cache::new` when loading persisted entries fails, instead of
+    /// starting empty
+    #[serde(default)]
+    pub fail_on_load_error: bool,
     /// Enable metrics collection
     #[cfg(feature = "metrics")]
     pub enable_metrics: bool,
+    /// Refuse every write with `CacheError::ReadOnly` once the cache is built
+    ///
+    /// Entries loaded on startup, or passed to `Cache::from_entries`, are
+    /// kept. Reads leave access counts and times, the eviction strategy and
+    /// the backend alone; expired entries are hidden rather than dropped, and
+    /// misses filled by a loader or read-through are returned without being
+    /// stored.
+    #[serde(default)]
+    pub read_only: bool,
+    /// Namespace the backend is scoped to, isolating this cache from others
+    /// sharing the same storage
+    pub namespace: Option<String>,
+    /// Format of `Cache::export_snapshot`; `None` uses the first enabled of
+    /// JSON, Bincode, MessagePack and CBOR
+    #[serde(default)]
+    pub snapshot_format: Option<SerializationFormat>,
+    /// Number of independently locked shards the entries are split across
+    ///
+    /// Capacity limits are divided between shards as evenly as they go, and
+    /// each shard evicts on its own, so with more than one shard the cache
+    /// never exceeds a limit but can start evicting before it is full
+    /// overall. Zero is treated as one, and more shards than
+    /// `max_total_entries` are rejected.
+    #[serde(default = "default_shard_count")]
+    pub shard_count: usize,
+    /// How backend saves, loads and removes are retried after a storage
+    /// backend error; `None` fails on the first error
+    #[serde(default)]
+    pub retry: Option<RetryConfig>,
+    /// When to stop calling a failing backend for a while; `None` keeps
+    /// calling it however often it fails
+    #[serde(default)]
+    pub circuit_breaker: Option<CircuitBreakerConfig>,
+}
+
+fn default_shard_count() -> usize {
+    1
 }
 
 impl Default for CacheConfig {
     fn default() -> Self {
         Self {
             max_entries_per_key: 100,
+            per_key_eviction: PerKeyEviction::Oldest,
+            keep_buckets_sorted: false,
             max_total_entries: 10_000,
+            max_total_bytes: None,
+            eviction_low_watermark: None,
             eviction_policy: EvictionPolicy::Lru,
             persistence: PersistenceConfig::default(),
+            mode: CacheMode::default(),
             #[cfg(feature = "compression")]
             compression: None,
+            #[cfg(feature = "compression")]
+            value_compression_threshold: None,
+            #[cfg(feature = "json-serialization")]
+            measure_sizes: false,
             default_ttl: None,
+            ttl_jitter: None,
+            expire_on_access: false,
+            fail_on_load_error: false,
             #[cfg(feature = "metrics")]
             enable_metrics: false,
+            read_only: false,
+            namespace: None,
+            snapshot_format: None,
+            shard_count: default_shard_count(),
+            retry: None,
+            circuit_breaker: None,
         }
     }
 }
@@ -53,12 +156,36 @@ impl CacheConfig {
         self
     }
 
+    /// Set which entry is dropped when a key exceeds its entry limit
+    pub fn with_per_key_eviction(mut self, eviction: PerKeyEviction) -> Self {
+        self.per_key_eviction = eviction;
+        self
+    }
+
     /// Set maximum total entries
     pub fn with_max_total_entries(mut self, max: usize) -> Self {
         self.max_total_entries = max;
         self
     }
 
+    /// Set a byte budget for all entries
+    ///
+    /// Entries whose metadata reports no size count as zero bytes.
+    pub fn with_max_total_bytes(mut self, max: u64) -> Self {
+        self.max_total_bytes = Some(max);
+        self
+    }
+
+    /// Evict down to `fraction` of `max_total_entries` whenever the limit is
+    /// exceeded
+    ///
+    /// This frees room for several inserts at once instead of evicting again
+    /// on each of them. The fraction is clamped to `0.0..=1.0`.
+    pub fn with_eviction_low_watermark(mut self, fraction: f32) -> Self {
+        self.eviction_low_watermark = Some(fraction);
+        self
+    }
+
     /// Set eviction policy
     pub fn with_eviction_policy(mut self, policy: EvictionPolicy) -> Self {
         self.eviction_policy = policy;
@@ -71,12 +198,78 @@ impl CacheConfig {
         self
     }
 
+    /// Set how reads and writes reach the backend
+    pub fn with_mode(mut self, mode: CacheMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Split the entries across `count` shards to reduce lock contention
+    pub fn with_shard_count(mut self, count: usize) -> Self {
+        self.shard_count = count;
+        self
+    }
+
+    /// Retry backend calls that fail with a storage backend error
+    pub fn with_retry(mut self, retry: RetryConfig) -> Self {
+        self.retry = Some(retry);
+        self
+    }
+
+    /// Stop calling the backend for a while after repeated failures
+    pub fn with_circuit_breaker(mut self, breaker: CircuitBreakerConfig) -> Self {
+        self.circuit_breaker = Some(breaker);
+        self
+    }
+
     /// Set default TTL for entries
     pub fn with_default_ttl(mut self, ttl: Duration) -> Self {
         self.default_ttl = Some(ttl);
         self
     }
 
+    /// Randomize the default TTL of each entry by up to `jitter` either way
+    pub fn with_ttl_jitter(mut self, jitter: Duration) -> Self {
+        self.ttl_jitter = Some(jitter);
+        self
+    }
+
+    /// The default TTL, with `random` in `0.0..1.0` choosing where it falls
+    /// within the jitter
+    ///
+    /// A jitter larger than the TTL can shorten it to zero, never below.
+    pub fn jittered_default_ttl(&self, random: f64) -> Option<Duration> {
+        let ttl = self.default_ttl?;
+        let Some(jitter) = self.ttl_jitter else {
+            return Some(ttl);
+        };
+        let offset = 2.0 * random.clamp(0.0, 1.0) - 1.0;
+        let shift = jitter.mul_f64(offset.abs());
+        Some(if offset < 0.0 {
+            ttl.saturating_sub(shift)
+        } else {
+            ttl.saturating_add(shift)
+        })
+    }
+
+    /// Keep each key's entries sorted by timestamp
+    pub fn with_sorted_buckets(mut self, enable: bool) -> Self {
+        self.keep_buckets_sorted = enable;
+        self
+    }
+
+    /// Drop expired entries when they are read instead of returning them
+    pub fn with_expire_on_access(mut self, enable: bool) -> Self {
+        self.expire_on_access = enable;
+        self
+    }
+
+    /// Fail cache construction when persisted entries cannot be loaded
+    pub fn with_fail_on_load_error(mut self, enable: bool) -> Self {
+        self.fail_on_load_error = enable;
+        self
+    }
+
     /// Enable compression with given configuration
     #[cfg(feature = "compression")]
     pub fn with_compression(mut self, compression: CompressionConfig) -> Self {
@@ -84,12 +277,113 @@ impl CacheConfig {
         self
     }
 
+    /// Store values whose serialized form is larger than `bytes` compressed
+    #[cfg(feature = "compression")]
+    pub fn with_value_compression_threshold(mut self, bytes: usize) -> Self {
+        self.value_compression_threshold = Some(bytes);
+        self
+    }
+
+    /// How the backend should compress large values, if at all
+    #[cfg(feature = "compression")]
+    pub fn value_compression(&self) -> Option<ValueCompression> {
+        let threshold = self.value_compression_threshold?;
+        let compression = self.compression.clone().unwrap_or_default();
+        Some(ValueCompression {
+            threshold,
+            algorithm: compression.algorithm,
+            level: compression.level,
+        })
+    }
+
+    /// Measure the size of entries added without one
+    #[cfg(feature = "json-serialization")]
+    pub fn with_measured_sizes(mut self, enable: bool) -> Self {
+        self.measure_sizes = enable;
+        self
+    }
+
     /// Enable metrics collection
     #[cfg(feature = "metrics")]
     pub fn with_metrics(mut self, enable: bool) -> Self {
         self.enable_metrics = enable;
         self
     }
+
+    /// Make the cache refuse writes after it is built
+    pub fn with_read_only(mut self, enable: bool) -> Self {
+        self.read_only = enable;
+        self
+    }
+
+    /// Scope the cache's storage to a namespace
+    pub fn with_namespace<S: Into<String>>(mut self, namespace: S) -> Self {
+        self.namespace = Some(namespace.into());
+        self
+    }
+
+    /// Set the format of exported snapshots
+    pub fn with_snapshot_format(mut self, format: SerializationFormat) -> Self {
+        self.snapshot_format = Some(format);
+        self
+    }
+
+    /// Check that the limits leave room for at least one entry
+    ///
+    /// `Cache::new` calls this and refuses configurations that fail it. A
+    /// limit of zero is rejected rather than read as unbounded: the entry
+    /// limits have no unbounded setting, and a cache that stores nothing
+    /// usually means a limit was left unset by mistake. For the same reason
+    /// there may be no more shards than the limits can give a share each. A
+    /// per-key limit above `max_total_entries` is allowed, as the default one
+    /// often is. A retry jitter outside `0.0..=1.0` is rejected as well.
+    pub fn validate(&self) -> Result<()> {
+        let invalid = |message: &str| Err(CacheError::InvalidConfiguration(message.to_string()));
+        if self.max_entries_per_key == 0 {
+            return invalid("max_entries_per_key must be at least 1");
+        }
+        if self.max_total_entries == 0 {
+            return invalid("max_total_entries must be at least 1");
+        }
+        if self.max_total_bytes == Some(0) {
+            return invalid("max_total_bytes must be at least 1 when set");
+        }
+        // Each shard gets a share of every limit, which must not be zero
+        let shards = self.shard_count.max(1);
+        if shards > self.max_total_entries {
+            return invalid("shard_count must not exceed max_total_entries");
+        }
+        if self
+            .max_total_bytes
+            .is_some_and(|bytes| bytes < shards as u64)
+        {
+            return invalid("max_total_bytes must be at least shard_count when set");
+        }
+        if self.eviction_low_watermark.is_some_and(f32::is_nan) {
+            return invalid("eviction_low_watermark must be a number");
+        }
+        if self
+            .circuit_breaker
+            .is_some_and(|breaker| breaker.failure_threshold == 0)
+        {
+            return invalid("circuit_breaker.failure_threshold must be at least 1");
+        }
+        if self
+            .persistence
+            .sync_strategy
+            .and_then(|strategy| strategy.period())
+            .is_some_and(|period| period.is_zero())
+        {
+            return invalid("persistence.sync_strategy period must be above zero");
+        }
+        if self
+            .retry
+            .is_some_and(|retry| !(0.0..=1.0).contains(&retry.jitter))
+        {
+            return invalid("retry.jitter must be between 0.0 and 1.0");
+        }
+        Ok(())
+    }
 }
 
 /// Eviction policy for cache entries
@@ -103,10 +397,64 @@ pub enum EvictionPolicy {
     Fifo,
     /// Time To Live based
     Ttl,
+    /// Adaptive Replacement Cache, balancing recency and frequency
+    Arc,
+    /// LRU eviction behind a TinyLFU admission filter, which turns away
+    /// newcomers that are used less often than the key they would evict
+    TinyLfu,
     /// No eviction (manual only)
     None,
 }
 
+/// Entry dropped from a key that exceeds `max_entries_per_key`
+///
+/// The entry being added is never the one dropped.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PerKeyEviction {
+    /// The oldest stored entry
+    #[default]
+    Oldest,
+    /// The newest stored entry
+    Newest,
+    /// The entry that was accessed least recently
+    LeastAccessed,
+}
+
+/// How a persistent cache moves data between memory and its backend
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CacheMode {
+    /// Writes stay in memory until the sync strategy saves them, and the
+    /// backend is only read on startup
+    #[default]
+    WriteBehind,
+    /// Every write waits for a save before returning, so the backend never
+    /// lags behind memory
+    ///
+    /// Each write saves the keys it changed, including any it evicted,
+    /// through [`StorageBackend::save_keys`](crate::StorageBackend::save_keys);
+    /// backends without per-key saves write the whole cache instead.
+    WriteThrough,
+    /// Misses look the key up in the backend before being reported, keeping
+    /// whatever is found in memory; writes behave as in `WriteBehind`
+    ReadThrough,
+}
+
+/// How a merging snapshot import resolves keys the cache already holds
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MergePolicy {
+    /// Keep the cache's entries and ignore the imported ones
+    KeepExisting,
+    /// Replace the cache's entries with the imported ones
+    Overwrite,
+    /// Keep whichever side has the most recent `CacheEntry::timestamp`,
+    /// preferring the cache's entries on a tie
+    #[default]
+    KeepNewestByTimestamp,
+    /// Append the imported entries to the cache's, then trim the key to
+    /// `max_entries_per_key` following `per_key_eviction`
+    Concat,
+}
+
 /// Persistence configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PersistenceConfig {
@@ -116,8 +464,25 @@ pub struct PersistenceConfig {
     pub path: Option<PathBuf>,
     /// Sync to disk after every N operations
     pub sync_interval: usize,
+    /// When to sync; `None` syncs every `sync_interval` operations
+    #[serde(default)]
+    pub sync_strategy: Option<SyncStrategy>,
     /// Automatically save on drop
     pub save_on_drop: bool,
+    /// Semaphore every save also takes a permit from, to limit how many
+    /// caches sharing it save at once
+    ///
+    /// Each cache still runs one save at a time on its own. This is not
+    /// serialized, so it has to be set again after deserializing a config.
+    #[serde(skip)]
+    pub save_limit: Option<Arc<Semaphore>>,
+    /// Longest time dropping the cache blocks on its final save; `None`
+    /// waits for the save to finish
+    ///
+    /// Only multi-threaded runtimes can block in `Drop`. On a current-thread
+    /// runtime the save is spawned as a task either way.
+    #[serde(default)]
+    pub drop_flush_timeout: Option<Duration>,
     /// Load existing cache on startup
     pub load_on_startup: bool,
 }
@@ -128,7 +493,10 @@ impl Default for PersistenceConfig {
             enabled: false,
             path: None,
             sync_interval: 100,
+            sync_strategy: None,
             save_on_drop: true,
+            save_limit: None,
+            drop_flush_timeout: None,
             load_on_startup: true,
         }
     }
@@ -151,6 +519,153 @@ impl PersistenceConfig {
             ..Default::default()
         }
     }
+
+    /// Set when the cache syncs to storage
+    pub fn with_sync_strategy(mut self, strategy: SyncStrategy) -> Self {
+        self.sync_strategy = Some(strategy);
+        self
+    }
+
+    /// Share `limit` as a save-concurrency limit with other caches
+    pub fn with_save_limit(mut self, limit: Arc<Semaphore>) -> Self {
+        self.save_limit = Some(limit);
+        self
+    }
+
+    /// Give up on the save made when the cache is dropped after `timeout`
+    pub fn with_drop_flush_timeout(mut self, timeout: Duration) -> Self {
+        self.drop_flush_timeout = Some(timeout);
+        self
+    }
+
+    /// Get the sync strategy in effect, falling back to `sync_interval`
+    pub fn effective_sync_strategy(&self) -> SyncStrategy {
+        self.sync_strategy
+            .unwrap_or(SyncStrategy::EveryNOps(self.sync_interval))
+    }
+}
+
+/// When a persistent cache syncs its entries to storage
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SyncStrategy {
+    /// Sync after every N write operations
+    EveryNOps(usize),
+    /// Sync periodically while there are unsaved writes
+    EveryDuration(Duration),
+    /// Sync after N write operations or once the period has passed with
+    /// unsaved writes, whichever comes first
+    Both {
+        /// Write operations between syncs
+        ops: usize,
+        /// Longest time unsaved writes wait for a sync
+        every: Duration,
+    },
+}
+
+impl SyncStrategy {
+    /// Number of write operations that triggers a sync, if any
+    pub fn ops(&self) -> Option<usize> {
+        match *self {
+            SyncStrategy::EveryNOps(ops) | SyncStrategy::Both { ops, .. } => Some(ops),
+            SyncStrategy::EveryDuration(_) => None,
+        }
+    }
+
+    /// Period of the background sync timer, if any
+    pub fn period(&self) -> Option<Duration> {
+        match *self {
+            SyncStrategy::EveryDuration(every) | SyncStrategy::Both { every, .. } => Some(every),
+            SyncStrategy::EveryNOps(_) => None,
+        }
+    }
+}
+
+/// Retry policy for backend calls
+///
+/// Only `CacheError::StorageBackend` errors are retried, as those are the
+/// ones a remote backend reports for timeouts and dropped connections. Other
+/// errors, such as a value that fails to serialize, would fail again and are
+/// returned at once.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RetryConfig {
+    /// Attempts per call, the first one included; one disables retrying
+    pub max_attempts: u32,
+    /// Delay before the first retry, doubled before each one after it
+    pub base_delay: Duration,
+    /// Fraction of each delay, from 0.0 to 1.0, randomly added or taken off
+    /// so that caches failing together do not retry in lockstep
+    pub jitter: f32,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(100),
+            jitter: 0.2,
+        }
+    }
+}
+
+impl RetryConfig {
+    /// Create a retry policy without jitter
+    pub fn new(max_attempts: u32, base_delay: Duration) -> Self {
+        Self {
+            max_attempts,
+            base_delay,
+            jitter: 0.0,
+        }
+    }
+
+    /// Set the fraction of each delay that is randomized
+    pub fn with_jitter(mut self, jitter: f32) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Delay before retry number `retry`, counting from one, with `random`
+    /// in `0.0..1.0` choosing where it falls within the jitter
+    pub fn delay(&self, retry: u32, random: f64) -> Duration {
+        let backoff = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(retry.saturating_sub(1)));
+        let jitter = f64::from(self.jitter.clamp(0.0, 1.0));
+        backoff.mul_f64(1.0 + jitter * (2.0 * random.clamp(0.0, 1.0) - 1.0))
+    }
+}
+
+/// Circuit breaker policy for the backend
+///
+/// After `failure_threshold` backend calls in a row fail, the breaker opens
+/// and the cache works from memory alone for `cooldown`. Once the cooldown
+/// is over, the next call is let through: success closes the breaker, and
+/// failure keeps it open for another cooldown. A call that exhausts its
+/// retries counts as one failure.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CircuitBreakerConfig {
+    /// Consecutive failed backend calls that open the breaker
+    pub failure_threshold: u32,
+    /// How long the breaker stays open before the backend is tried again
+    pub cooldown: Duration,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 5,
+            cooldown: Duration::from_secs(30),
+        }
+    }
+}
+
+impl CircuitBreakerConfig {
+    /// Create a circuit breaker policy
+    pub fn new(failure_threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            failure_threshold,
+            cooldown,
+        }
+    }
 }
 
 /// Compression configuration
@@ -176,6 +691,21 @@ impl Default for CompressionConfig {
     }
 }
 
+/// Compression of individual values in a backend's persisted representation
+///
+/// Passed to [`StorageBackend::with_value_compression`](crate::StorageBackend::with_value_compression)
+/// by `Cache::new`.
+#[cfg(feature = "compression")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ValueCompression {
+    /// Serialized size in bytes a value must exceed to be compressed
+    pub threshold: usize,
+    /// Algorithm compressing the values
+    pub algorithm: CompressionAlgorithm,
+    /// Compression level
+    pub level: u32,
+}
+
 /// Supported compression algorithms
 #[cfg(feature = "compression")]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -186,12 +716,85 @@ pub enum CompressionAlgorithm {
     Zlib,
     /// Raw DEFLATE compression
     Deflate,
+    /// Zstandard compression
+    #[cfg(feature = "zstd-compression")]
+    Zstd,
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_validate_rejects_limits_that_store_nothing() {
+        assert!(CacheConfig::default().validate().is_ok());
+        assert!(CacheConfig::default()
+            .with_max_entries_per_key(1)
+            .with_max_total_entries(1)
+            .with_max_total_bytes(1)
+            .with_eviction_low_watermark(0.0)
+            .validate()
+            .is_ok());
+        assert!(CacheConfig::default()
+            .with_max_total_entries(4)
+            .with_max_total_bytes(4)
+            .with_shard_count(4)
+            .with_retry(RetryConfig::default().with_jitter(1.0))
+            .validate()
+            .is_ok());
+
+        let rejected = [
+            CacheConfig::default().with_max_entries_per_key(0),
+            CacheConfig::default().with_max_total_entries(0),
+            CacheConfig::default().with_max_total_bytes(0),
+            CacheConfig::default().with_eviction_low_watermark(f32::NAN),
+            CacheConfig::default()
+                .with_circuit_breaker(CircuitBreakerConfig::new(0, Duration::from_secs(1))),
+            CacheConfig::default().with_retry(RetryConfig::default().with_jitter(f32::NAN)),
+            CacheConfig::default().with_retry(RetryConfig::default().with_jitter(f32::INFINITY)),
+            CacheConfig::default().with_retry(RetryConfig::default().with_jitter(1.5)),
+            CacheConfig::default().with_retry(RetryConfig::default().with_jitter(-0.1)),
+            CacheConfig::default()
+                .with_max_total_entries(3)
+                .with_shard_count(4),
+            CacheConfig::default()
+                .with_max_total_bytes(3)
+                .with_shard_count(4),
+        ];
+        for config in rejected {
+            assert!(matches!(
+                config.validate(),
+                Err(CacheError::InvalidConfiguration(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_validate_rejects_a_zero_sync_period() {
+        let with_strategy = |strategy| {
+            CacheConfig::default()
+                .with_persistence(PersistenceConfig::disabled().with_sync_strategy(strategy))
+        };
+        let every = Duration::from_millis(1);
+        assert!(with_strategy(SyncStrategy::EveryDuration(every))
+            .validate()
+            .is_ok());
+
+        let rejected = [
+            with_strategy(SyncStrategy::EveryDuration(Duration::ZERO)),
+            with_strategy(SyncStrategy::Both {
+                ops: 10,
+                every: Duration::ZERO,
+            }),
+        ];
+        for config in rejected {
+            assert!(matches!(
+                config.validate(),
+                Err(CacheError::InvalidConfiguration(_))
+            ));
+        }
+    }
+
     #[test]
     fn test_default_config() {
         let config = CacheConfig::default();
@@ -201,6 +804,16 @@ mod tests {
         assert!(!config.persistence.enabled);
     }
 
+    #[test]
+    fn test_default_config_leaves_optional_behavior_off() {
+        let config = CacheConfig::default();
+        assert!(!config.expire_on_access);
+        assert!(!config.fail_on_load_error);
+        assert_eq!(config.per_key_eviction, PerKeyEviction::Oldest);
+        assert_eq!(config.max_total_bytes, None);
+        assert_eq!(config.namespace, None);
+    }
+
     #[test]
     fn test_config_builder() {
         let config = CacheConfig::new()
@@ -215,6 +828,20 @@ mod tests {
         assert_eq!(config.default_ttl, Some(Duration::from_secs(300)));
     }
 
+    #[test]
+    fn test_config_builder_sets_optional_behavior() {
+        let config = CacheConfig::new()
+            .with_expire_on_access(true)
+            .with_per_key_eviction(PerKeyEviction::LeastAccessed)
+            .with_max_total_bytes(1 << 20)
+            .with_namespace("tenant-a");
+
+        assert!(config.expire_on_access);
+        assert_eq!(config.per_key_eviction, PerKeyEviction::LeastAccessed);
+        assert_eq!(config.max_total_bytes, Some(1 << 20));
+        assert_eq!(config.namespace.as_deref(), Some("tenant-a"));
+    }
+
     #[test]
     fn test_persistence_config() {
         let persistence = PersistenceConfig::with_path("/tmp/cache");
@@ -232,6 +859,26 @@ mod tests {
         assert_eq!(persistence.path, None);
     }
 
+    #[test]
+    fn test_sync_strategy_defaults_to_sync_interval() {
+        let persistence = PersistenceConfig {
+            sync_interval: 7,
+            ..Default::default()
+        };
+        assert_eq!(
+            persistence.effective_sync_strategy(),
+            SyncStrategy::EveryNOps(7)
+        );
+
+        let every = Duration::from_secs(5);
+        let persistence = persistence.with_sync_strategy(SyncStrategy::Both { ops: 3, every });
+        let strategy = persistence.effective_sync_strategy();
+        assert_eq!(strategy.ops(), Some(3));
+        assert_eq!(strategy.period(), Some(every));
+        assert_eq!(SyncStrategy::EveryDuration(every).ops(), None);
+        assert_eq!(SyncStrategy::EveryNOps(3).period(), None);
+    }
+
     #[test]
     fn test_with_persistence_builder() {
         let p = PersistenceConfig::with_path("/tmp/data");
@@ -240,6 +887,50 @@ mod tests {
         assert_eq!(config.persistence.path, p.path);
     }
 
+    #[test]
+    fn test_retry_delay_doubles_within_jitter() {
+        let retry = RetryConfig::new(4, Duration::from_millis(100));
+        assert_eq!(retry.delay(1, 0.9), Duration::from_millis(100));
+        assert_eq!(retry.delay(2, 0.1), Duration::from_millis(200));
+        assert_eq!(retry.delay(3, 0.5), Duration::from_millis(400));
+
+        let retry = retry.with_jitter(0.5);
+        assert_eq!(retry.delay(2, 0.0), Duration::from_millis(100));
+        assert_eq!(retry.delay(2, 0.5), Duration::from_millis(200));
+        assert_eq!(retry.delay(2, 1.0), Duration::from_millis(300));
+
+        let config = CacheConfig::new().with_retry(retry);
+        assert_eq!(config.retry, Some(retry));
+        assert_eq!(CacheConfig::default().retry, None);
+    }
+
+    #[test]
+    fn test_jittered_default_ttl_stays_within_jitter() {
+        let config = CacheConfig::new().with_default_ttl(Duration::from_secs(60));
+        assert_eq!(
+            config.jittered_default_ttl(0.0),
+            Some(Duration::from_secs(60))
+        );
+
+        let config = config.with_ttl_jitter(Duration::from_secs(10));
+        assert_eq!(
+            config.jittered_default_ttl(0.0),
+            Some(Duration::from_secs(50))
+        );
+        assert_eq!(
+            config.jittered_default_ttl(0.5),
+            Some(Duration::from_secs(60))
+        );
+        assert_eq!(
+            config.jittered_default_ttl(1.0),
+            Some(Duration::from_secs(70))
+        );
+
+        let config = config.with_ttl_jitter(Duration::from_secs(600));
+        assert_eq!(config.jittered_default_ttl(0.0), Some(Duration::ZERO));
+        assert_eq!(CacheConfig::new().jittered_default_ttl(0.5), None);
+    }
+
     #[cfg(feature = "compression")]
     #[test]
     fn test_compression_config() {
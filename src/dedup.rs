@@ -0,0 +1,225 @@
+//! Sharing one copy of identical values across keys
+
+#[cfg(feature = "json-serialization")]
+use serde::Serialize;
+#[cfg(feature = "json-serialization")]
+use std::collections::HashMap;
+#[cfg(feature = "json-serialization")]
+use std::sync::{Arc, Mutex, Weak};
+
+/// Hands out an already stored copy of values seen before
+///
+/// The cache holds one as a trait object, so it can deduplicate without its
+/// value type being known to be an `Arc`.
+pub(crate) trait ValueDedup<V>: Send + Sync {
+    /// Return a stored value equal to `value` if there is one, otherwise
+    /// remember `value` and return it
+    fn intern(&self, value: V) -> V;
+
+    /// How much sharing there is among `values`
+    fn savings<'a>(&self, values: &mut dyn Iterator<Item = &'a V>) -> DedupSavings
+    where
+        V: 'a;
+}
+
+/// Sharing found among stored values
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct DedupSavings {
+    /// Values that are a second or later reference to a shared copy
+    pub(crate) shared_values: usize,
+    /// Serialized size of the copies those references avoided
+    pub(crate) bytes_saved: u64,
+}
+
+/// Interner for `Arc<T>` values, keyed by a hash of their JSON form
+///
+/// Only weak references are kept, so a value is freed as soon as the last
+/// key holding it is removed; stale references are dropped when their hash
+/// comes up again and in sweeps as the table grows. Values with the same
+/// hash are compared with `==`, so a collision never swaps in a different
+/// value. Each reference is kept with the length of the value's JSON form,
+/// so the savings are counted without serializing anything again.
+#[cfg(feature = "json-serialization")]
+pub(crate) struct ArcInterner<T> {
+    state: Mutex<InternState<T>>,
+}
+
+#[cfg(feature = "json-serialization")]
+struct InternState<T> {
+    /// Values by hash, each with its serialized length
+    slots: HashMap<u64, Vec<(Weak<T>, u64)>>,
+    /// Number of hashes at which stale references are next swept
+    sweep_at: usize,
+}
+
+#[cfg(feature = "json-serialization")]
+const MIN_SWEEP_AT: usize = 64;
+
+#[cfg(feature = "json-serialization")]
+impl<T> ArcInterner<T> {
+    pub(crate) fn new() -> Self {
+        Self {
+            state: Mutex::new(InternState {
+                slots: HashMap::new(),
+                sweep_at: MIN_SWEEP_AT,
+            }),
+        }
+    }
+}
+
+/// Hash and length of a value's JSON serialization, or `None` if it cannot
+/// be serialized
+///
+/// Like [`serialized_size`](crate::entry::serialized_size), this streams the
+/// output instead of buffering it.
+#[cfg(feature = "json-serialization")]
+fn fingerprint<T: Serialize + ?Sized>(value: &T) -> Option<(u64, u64)> {
+    use std::hash::Hasher;
+
+    struct HashWriter {
+        hasher: std::collections::hash_map::DefaultHasher,
+        len: u64,
+    }
+
+    impl std::io::Write for HashWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.hasher.write(buf);
+            self.len += buf.len() as u64;
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let mut writer = HashWriter {
+        hasher: Default::default(),
+        len: 0,
+    };
+    serde_json::to_writer(&mut writer, value).ok()?;
+    Some((writer.hasher.finish(), writer.len))
+}
+
+#[cfg(feature = "json-serialization")]
+impl<T> ValueDedup<Arc<T>> for ArcInterner<T>
+where
+    T: Serialize + PartialEq + Send + Sync,
+{
+    fn intern(&self, value: Arc<T>) -> Arc<T> {
+        let Some((hash, len)) = fingerprint(&*value) else {
+            return value;
+        };
+        let mut state = self.state.lock().unwrap();
+        let slot = state.slots.entry(hash).or_default();
+        slot.retain(|(weak, _)| weak.strong_count() > 0);
+        if let Some(shared) = slot
+            .iter()
+            .filter_map(|(weak, _)| weak.upgrade())
+            .find(|shared| **shared == *value)
+        {
+            return shared;
+        }
+        slot.push((Arc::downgrade(&value), len));
+
+        if state.slots.len() >= state.sweep_at {
+            state.slots.retain(|_, slot| {
+                slot.retain(|(weak, _)| weak.strong_count() > 0);
+                !slot.is_empty()
+            });
+            state.sweep_at = (state.slots.len() * 2).max(MIN_SWEEP_AT);
+        }
+        value
+    }
+
+    /// Values are weighed by the lengths recorded when they were interned;
+    /// only a shared value that never went through the interner is
+    /// serialized to find its length
+    fn savings<'a>(&self, values: &mut dyn Iterator<Item = &'a Arc<T>>) -> DedupSavings
+    where
+        Arc<T>: 'a,
+    {
+        // A weak reference keeps its allocation, so no other value can have
+        // the address of a recorded one while it is in the table
+        let lengths: HashMap<*const T, u64> = self
+            .state
+            .lock()
+            .unwrap()
+            .slots
+            .values()
+            .flatten()
+            .map(|(weak, len)| (weak.as_ptr(), *len))
+            .collect();
+        arc_savings(values, |value| {
+            lengths
+                .get(&Arc::as_ptr(value))
+                .copied()
+                .or_else(|| fingerprint(&**value).map(|(_, len)| len))
+                .unwrap_or(0)
+        })
+    }
+}
+
+/// Count references beyond the first to each distinct `Arc`, weighing each
+/// with `size`
+#[cfg(feature = "json-serialization")]
+fn arc_savings<'a, T: 'a>(
+    values: &mut dyn Iterator<Item = &'a Arc<T>>,
+    size: impl Fn(&Arc<T>) -> u64,
+) -> DedupSavings {
+    let mut references: HashMap<*const T, (usize, &Arc<T>)> = HashMap::new();
+    for value in values {
+        references.entry(Arc::as_ptr(value)).or_insert((0, value)).0 += 1;
+    }
+    references.values().filter(|(count, _)| *count > 1).fold(
+        DedupSavings::default(),
+        |savings, (count, value)| {
+            let extra = count - 1;
+            DedupSavings {
+                shared_values: savings.shared_values + extra,
+                bytes_saved: savings.bytes_saved + extra as u64 * size(value),
+            }
+        },
+    )
+}
+
+#[cfg(all(test, feature = "json-serialization"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interner_shares_equal_values_and_forgets_dropped_ones() {
+        let interner = ArcInterner::new();
+        let first = interner.intern(Arc::new("report".to_string()));
+        let second = interner.intern(Arc::new("report".to_string()));
+        let other = interner.intern(Arc::new("other".to_string()));
+        assert!(Arc::ptr_eq(&first, &second));
+        assert!(!Arc::ptr_eq(&first, &other));
+
+        let savings = interner.savings(&mut [&first, &second, &other].into_iter());
+        assert_eq!(
+            savings,
+            DedupSavings {
+                shared_values: 1,
+                bytes_saved: "\"report\"".len() as u64,
+            }
+        );
+
+        // A shared value the interner never saw is measured on the spot
+        let loose = Arc::new("loose".to_string());
+        let savings = interner.savings(&mut [&loose, &loose].into_iter());
+        assert_eq!(savings.bytes_saved, "\"loose\"".len() as u64);
+
+        // Once every holder is gone the next equal value is stored afresh
+        drop(other);
+        let again = interner.intern(Arc::new("other".to_string()));
+        assert_eq!(Arc::strong_count(&again), 1);
+        let state = interner.state.lock().unwrap();
+        assert!(state.slots.values().all(|slot| slot.len() == 1));
+        assert!(state
+            .slots
+            .values()
+            .flatten()
+            .any(|(weak, len)| weak.as_ptr() == Arc::as_ptr(&first) && *len == 8));
+    }
+}
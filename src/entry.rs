@@ -1,9 +1,10 @@
 //! Cache entry types and metadata traits
 
 use chrono::{DateTime, Utc};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::fmt::Debug;
 use std::hash::Hash;
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
 
 /// A cache entry containing a key-value pair with metadata
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -24,9 +25,80 @@ where
     /// Optional expiry time for TTL-based eviction
     pub expiry: Option<DateTime<Utc>>,
     /// Number of times this entry has been accessed
-    pub access_count: u64,
+    access_count: AccessCount,
     /// Last access timestamp
-    pub last_accessed: DateTime<Utc>,
+    last_accessed: AccessTime,
+}
+
+/// Access counter that can be bumped through a shared reference
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+struct AccessCount(AtomicU64);
+
+impl AccessCount {
+    fn get(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+impl Clone for AccessCount {
+    fn clone(&self) -> Self {
+        Self(AtomicU64::new(self.get()))
+    }
+}
+
+/// Access timestamp stored as nanoseconds since the Unix epoch so it can be
+/// updated through a shared reference
+///
+/// It serializes as a `DateTime<Utc>`, matching the previous plain field.
+struct AccessTime(AtomicI64);
+
+impl AccessTime {
+    fn new(time: DateTime<Utc>) -> Self {
+        Self(AtomicI64::new(Self::nanos(time)))
+    }
+
+    /// Nanoseconds since the epoch, saturating outside the years 1677-2262
+    fn nanos(time: DateTime<Utc>) -> i64 {
+        time.timestamp_nanos_opt()
+            .unwrap_or(if time.timestamp() < 0 {
+                i64::MIN
+            } else {
+                i64::MAX
+            })
+    }
+
+    fn get(&self) -> DateTime<Utc> {
+        DateTime::from_timestamp_nanos(self.0.load(Ordering::Relaxed))
+    }
+
+    fn set(&self, time: DateTime<Utc>) {
+        self.0.store(Self::nanos(time), Ordering::Relaxed);
+    }
+}
+
+impl Clone for AccessTime {
+    fn clone(&self) -> Self {
+        Self::new(self.get())
+    }
+}
+
+impl Debug for AccessTime {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Debug::fmt(&self.get(), f)
+    }
+}
+
+impl Serialize for AccessTime {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        self.get().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for AccessTime {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        DateTime::<Utc>::deserialize(deserializer).map(Self::new)
+    }
 }
 
 impl<K, V, M> CacheEntry<K, V, M>
@@ -56,8 +128,8 @@ where
             metadata,
             timestamp: now,
             expiry: None,
-            access_count: 0,
-            last_accessed: now,
+            access_count: AccessCount::default(),
+            last_accessed: AccessTime::new(now),
         }
     }
 
@@ -74,22 +146,85 @@ where
 
     /// Check if the entry has expired
     pub fn is_expired(&self) -> bool {
-        if let Some(expiry) = self.expiry {
-            Utc::now() > expiry
-        } else {
-            false
-        }
+        self.is_expired_at(Utc::now())
+    }
+
+    /// Check if the entry has expired as of `now`
+    pub fn is_expired_at(&self, now: DateTime<Utc>) -> bool {
+        self.expiry.is_some_and(|expiry| now > expiry)
     }
 
     /// Update access statistics
-    pub fn record_access(&mut self) {
-        self.access_count += 1;
-        self.last_accessed = Utc::now();
+    ///
+    /// This only needs a shared reference, so reads can record access while
+    /// holding the cache's read lock.
+    pub fn record_access(&self) {
+        self.record_access_at(Utc::now());
+    }
+
+    /// Update access statistics for an access made at `now`
+    pub fn record_access_at(&self, now: DateTime<Utc>) {
+        self.access_count.0.fetch_add(1, Ordering::Relaxed);
+        self.last_accessed.set(now);
+    }
+
+    /// Restamp the entry as created at `now`, keeping its time to live
+    ///
+    /// The access time moves to `now` as well.
+    pub fn created_at(mut self, now: DateTime<Utc>) -> Self {
+        if let Some(expiry) = &mut self.expiry {
+            *expiry = now + (*expiry - self.timestamp);
+        }
+        self.timestamp = now;
+        self.last_accessed.set(now);
+        self
+    }
+
+    /// Number of times this entry has been accessed
+    pub fn access_count(&self) -> u64 {
+        self.access_count.get()
+    }
+
+    /// Last access timestamp
+    pub fn last_accessed(&self) -> DateTime<Utc> {
+        self.last_accessed.get()
+    }
+
+    /// Overwrite the access count
+    pub fn set_access_count(&mut self, count: u64) {
+        *self.access_count.0.get_mut() = count;
+    }
+
+    /// Overwrite the last access timestamp
+    pub fn set_last_accessed(&mut self, time: DateTime<Utc>) {
+        self.last_accessed.set(time);
     }
 
     /// Get the age of the entry
     pub fn age(&self) -> chrono::Duration {
-        Utc::now() - self.timestamp
+        self.age_at(Utc::now())
+    }
+
+    /// Get the age of the entry as of `now`
+    pub fn age_at(&self, now: DateTime<Utc>) -> chrono::Duration {
+        now - self.timestamp
+    }
+}
+
+impl<K, V, M> CacheEntry<K, V, M>
+where
+    K: Clone + Hash + Eq,
+    V: Clone,
+    M: Clone + EntryMetadata,
+{
+    /// Estimate the memory used by this entry in bytes
+    ///
+    /// This is the inline size of the entry (key, value and bookkeeping
+    /// fields) plus the data size reported by `EntryMetadata::size_bytes`,
+    /// which lets heap-allocated payloads be accounted for.
+    pub fn estimated_size_bytes(&self) -> usize {
+        let payload = self.metadata.size_bytes().unwrap_or(0);
+        std::mem::size_of::<Self>() + usize::try_from(payload).unwrap_or(usize::MAX)
     }
 }
 
@@ -107,15 +242,39 @@ pub trait EntryMetadata:
         None
     }
 
+    /// Record the size of the cached data, for metadata able to hold it
+    ///
+    /// Caches built with `Cache::with_measured_sizes` call this for entries
+    /// whose `size_bytes` is unset. The default ignores the size.
+    fn set_size_bytes(&mut self, _size: u64) {}
+
     /// Get a category or type identifier
     fn category(&self) -> Option<&str> {
         None
     }
+
+    /// Get the tags attached to the entry
+    fn tags(&self) -> &[String] {
+        &[]
+    }
 }
 
 /// Empty metadata implementation
 impl EntryMetadata for () {}
 
+/// Metadata whose category is a typed value rather than a string
+///
+/// Implement this alongside [`EntryMetadata`] to filter searches with
+/// [`SearchQuery::with_category_filter`](crate::SearchQuery::with_category_filter)
+/// instead of stringifying the category.
+pub trait TypedCategory: EntryMetadata {
+    /// Category type, usually an enum
+    type Category: PartialEq + Send + Sync + 'static;
+
+    /// Get the typed category of the entry
+    fn typed_category(&self) -> Option<&Self::Category>;
+}
+
 /// Simple metadata implementation with common fields
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct BasicMetadata {
@@ -138,9 +297,96 @@ impl EntryMetadata for BasicMetadata {
         self.size_bytes
     }
 
+    fn set_size_bytes(&mut self, size: u64) {
+        self.size_bytes = Some(size);
+    }
+
     fn category(&self) -> Option<&str> {
         self.category.as_deref()
     }
+
+    fn tags(&self) -> &[String] {
+        &self.tags
+    }
+}
+
+/// Metadata that adds a size to another metadata type
+///
+/// `()` has nowhere to keep a size, so byte limits ignore entries using it.
+/// `MeasuredMetadata<()>` has the same lack of fields plus a size, which
+/// `Cache::with_measured_sizes` fills in; every other method is answered by
+/// `inner`.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct MeasuredMetadata<M = ()> {
+    /// The wrapped metadata
+    pub inner: M,
+    /// Size in bytes, taking precedence over the size `inner` reports
+    pub size_bytes: Option<u64>,
+}
+
+impl<M> MeasuredMetadata<M> {
+    /// Wrap `inner` without a size
+    pub fn new(inner: M) -> Self {
+        Self {
+            inner,
+            size_bytes: None,
+        }
+    }
+}
+
+impl<M: EntryMetadata> EntryMetadata for MeasuredMetadata<M> {
+    fn execution_time_ms(&self) -> Option<u64> {
+        self.inner.execution_time_ms()
+    }
+
+    fn size_bytes(&self) -> Option<u64> {
+        self.size_bytes.or_else(|| self.inner.size_bytes())
+    }
+
+    fn set_size_bytes(&mut self, size: u64) {
+        self.size_bytes = Some(size);
+    }
+
+    fn category(&self) -> Option<&str> {
+        self.inner.category()
+    }
+
+    fn tags(&self) -> &[String] {
+        self.inner.tags()
+    }
+}
+
+impl<M: TypedCategory> TypedCategory for MeasuredMetadata<M> {
+    type Category = M::Category;
+
+    fn typed_category(&self) -> Option<&Self::Category> {
+        self.inner.typed_category()
+    }
+}
+
+/// Number of bytes `value` takes when serialized as JSON, or `None` if it
+/// cannot be serialized
+///
+/// The value is streamed into a counter rather than a buffer, so nothing is
+/// allocated for the output.
+#[cfg(feature = "json-serialization")]
+pub fn serialized_size<T: Serialize + ?Sized>(value: &T) -> Option<u64> {
+    struct Counter(u64);
+
+    impl std::io::Write for Counter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0 += buf.len() as u64;
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let mut counter = Counter(0);
+    serde_json::to_writer(&mut counter, value).ok()?;
+    Some(counter.0)
 }
 
 /// Statistics for a group of cache entries
@@ -173,7 +419,7 @@ mod tests {
         let entry = sample_entry();
         assert_eq!(entry.key, "key1");
         assert_eq!(entry.value, "value1");
-        assert_eq!(entry.access_count, 0);
+        assert_eq!(entry.access_count(), 0);
         assert!(!entry.is_expired());
     }
 
@@ -202,18 +448,98 @@ mod tests {
 
     #[test]
     fn test_entry_access_tracking() {
-        let mut entry = sample_entry();
-        let initial_time = entry.last_accessed;
+        let entry = sample_entry();
+        let initial_time = entry.last_accessed();
 
         // Sleep a tiny bit to ensure time difference
         std::thread::sleep(std::time::Duration::from_millis(10));
 
         entry.record_access();
-        assert_eq!(entry.access_count, 1);
-        assert!(entry.last_accessed > initial_time);
+        assert_eq!(entry.access_count(), 1);
+        assert!(entry.last_accessed() > initial_time);
+
+        entry.record_access();
+        assert_eq!(entry.access_count(), 2);
+    }
 
+    #[cfg(feature = "json-serialization")]
+    #[test]
+    fn test_access_stats_serialization() {
+        let entry = sample_entry();
         entry.record_access();
-        assert_eq!(entry.access_count, 2);
+
+        let json = serde_json::to_value(&entry).unwrap();
+        assert_eq!(json["access_count"], 1);
+        assert!(json["last_accessed"].is_string());
+
+        let decoded: CacheEntry<String, String, ()> = serde_json::from_value(json).unwrap();
+        assert_eq!(decoded.access_count(), 1);
+        assert_eq!(decoded.last_accessed(), entry.last_accessed());
+
+        // Clones snapshot the counters rather than sharing them
+        let clone = decoded.clone();
+        decoded.record_access();
+        assert_eq!(clone.access_count(), 1);
+    }
+
+    #[test]
+    fn test_estimated_size_bytes() {
+        let base = std::mem::size_of::<CacheEntry<String, String, BasicMetadata>>();
+        let entry: CacheEntry<String, String, BasicMetadata> =
+            CacheEntry::new("k".to_string(), "v".to_string());
+        assert_eq!(entry.estimated_size_bytes(), base);
+
+        let metadata = BasicMetadata {
+            size_bytes: Some(4096),
+            ..Default::default()
+        };
+        let entry = CacheEntry::with_metadata("k".to_string(), "v".to_string(), metadata);
+        assert_eq!(entry.estimated_size_bytes(), base + 4096);
+    }
+
+    #[cfg(feature = "json-serialization")]
+    #[test]
+    fn test_serialized_size_matches_json_length() {
+        assert_eq!(serialized_size("abc"), Some(5));
+        assert_eq!(serialized_size(&vec![1, 2, 3]), Some(7));
+    }
+
+    #[test]
+    fn test_measured_metadata_delegates_to_inner() {
+        let mut metadata = MeasuredMetadata::new(BasicMetadata {
+            size_bytes: Some(10),
+            category: Some("c".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(metadata.size_bytes(), Some(10));
+        assert_eq!(metadata.category(), Some("c"));
+
+        metadata.set_size_bytes(20);
+        assert_eq!(metadata.size_bytes(), Some(20));
+
+        let mut unit = MeasuredMetadata::<()>::default();
+        assert_eq!(unit.size_bytes(), None);
+        unit.set_size_bytes(3);
+        assert_eq!(unit.size_bytes(), Some(3));
+    }
+
+    #[test]
+    fn test_expiry_and_age_at_a_given_time() {
+        let entry = sample_entry().with_ttl(chrono::Duration::seconds(10));
+        let later = entry.timestamp + chrono::Duration::seconds(11);
+        assert!(!entry.is_expired_at(entry.timestamp));
+        assert!(entry.is_expired_at(later));
+        assert_eq!(entry.age_at(later), chrono::Duration::seconds(11));
+
+        entry.record_access_at(later);
+        assert_eq!(entry.last_accessed(), later);
+
+        let restamped = entry.created_at(later);
+        assert_eq!(restamped.timestamp, later);
+        assert_eq!(
+            restamped.expiry,
+            Some(later + chrono::Duration::seconds(10))
+        );
     }
 
     #[test]
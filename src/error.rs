@@ -29,14 +29,22 @@ pub enum CacheError {
     #[error("Storage backend error: {0}")]
     StorageBackend(String),
 
-    /// Entry not found
-    #[error("Entry not found for key")]
-    NotFound,
+    /// A save wrote some keys but failed on others
+    #[error("Failed to save {}: {}", count_keys(.failures.len()), describe_failures(.failures))]
+    PartialSave {
+        /// Each key that was not saved, as rendered by the backend, with the
+        /// error that stopped it
+        failures: Vec<(String, CacheError)>,
+    },
 
     /// Invalid configuration
     #[error("Invalid configuration: {0}")]
     InvalidConfiguration(String),
 
+    /// A write was attempted on a cache configured as read-only
+    #[error("Cache is read-only")]
+    ReadOnly,
+
     /// Compression error
     #[cfg(feature = "compression")]
     #[error("Compression error: {0}")]
@@ -47,6 +55,65 @@ pub enum CacheError {
     Custom(String),
 }
 
+impl CacheError {
+    /// Name the key a failed operation was for in the error's message
+    ///
+    /// Keys are often not `Display`, so callers pass whatever rendering they
+    /// have. The variant stays the same, and variants without a message, or
+    /// that already carry a key, are returned unchanged. An I/O error keeps
+    /// its kind and stays reachable as the new error's source.
+    pub fn with_key(self, key: impl std::fmt::Display) -> Self {
+        match self {
+            CacheError::Io(e) => CacheError::Io(io::Error::new(
+                e.kind(),
+                WithKey {
+                    key: key.to_string(),
+                    source: e,
+                },
+            )),
+            CacheError::Serialization(message) => {
+                CacheError::Serialization(format!("key {key}: {message}"))
+            }
+            CacheError::Deserialization(message) => {
+                CacheError::Deserialization(format!("key {key}: {message}"))
+            }
+            CacheError::StorageBackend(message) => {
+                CacheError::StorageBackend(format!("key {key}: {message}"))
+            }
+            #[cfg(feature = "compression")]
+            CacheError::Compression(message) => {
+                CacheError::Compression(format!("key {key}: {message}"))
+            }
+            other => other,
+        }
+    }
+}
+
+/// An I/O error annotated with the key it arose for
+#[derive(Error, Debug)]
+#[error("key {key}: {source}")]
+struct WithKey {
+    key: String,
+    source: io::Error,
+}
+
+/// Render a number of keys, such as "1 key" or "2 keys"
+fn count_keys(count: usize) -> String {
+    match count {
+        1 => "1 key".to_string(),
+        count => format!("{count} keys"),
+    }
+}
+
+/// List the failed keys of a partial save with their errors
+fn describe_failures(failures: &[(String, CacheError)]) -> String {
+    failures
+        .iter()
+        .map(|(key, error)| format!("{key} ({error})"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
 /// Result type alias for cache operations
 pub type Result<T> = std::result::Result<T, CacheError>;
 
@@ -69,6 +136,74 @@ impl From<bincode::Error> for CacheError {
     }
 }
 
+#[cfg(feature = "msgpack-serialization")]
+impl From<rmp_serde::encode::Error> for CacheError {
+    fn from(err: rmp_serde::encode::Error) -> Self {
+        CacheError::Serialization(err.to_string())
+    }
+}
+
+#[cfg(feature = "msgpack-serialization")]
+impl From<rmp_serde::decode::Error> for CacheError {
+    fn from(err: rmp_serde::decode::Error) -> Self {
+        CacheError::Deserialization(err.to_string())
+    }
+}
+
+#[cfg(feature = "cbor-serialization")]
+impl From<ciborium::ser::Error<io::Error>> for CacheError {
+    fn from(err: ciborium::ser::Error<io::Error>) -> Self {
+        CacheError::Serialization(err.to_string())
+    }
+}
+
+#[cfg(feature = "cbor-serialization")]
+impl From<ciborium::de::Error<io::Error>> for CacheError {
+    fn from(err: ciborium::de::Error<io::Error>) -> Self {
+        CacheError::Deserialization(err.to_string())
+    }
+}
+
+#[cfg(feature = "redis-backend")]
+impl From<redis::RedisError> for CacheError {
+    fn from(err: redis::RedisError) -> Self {
+        CacheError::StorageBackend(err.to_string())
+    }
+}
+
+#[cfg(feature = "sqlite-backend")]
+impl From<rusqlite::Error> for CacheError {
+    fn from(err: rusqlite::Error) -> Self {
+        CacheError::StorageBackend(err.to_string())
+    }
+}
+
+#[cfg(feature = "s3-backend")]
+impl<E, R> From<aws_sdk_s3::error::SdkError<E, R>> for CacheError
+where
+    E: std::error::Error + 'static,
+    R: std::fmt::Debug,
+{
+    fn from(err: aws_sdk_s3::error::SdkError<E, R>) -> Self {
+        // The plain Display of an SdkError omits the service's message
+        CacheError::StorageBackend(aws_sdk_s3::error::DisplayErrorContext(err).to_string())
+    }
+}
+
+#[cfg(feature = "s3-backend")]
+impl From<aws_sdk_s3::primitives::ByteStreamError> for CacheError {
+    fn from(err: aws_sdk_s3::primitives::ByteStreamError) -> Self {
+        CacheError::StorageBackend(err.to_string())
+    }
+}
+
+#[cfg(feature = "s3-backend")]
+impl From<aws_sdk_s3::error::BuildError> for CacheError {
+    fn from(err: aws_sdk_s3::error::BuildError) -> Self {
+        CacheError::StorageBackend(err.to_string())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -92,13 +227,58 @@ mod tests {
         let backend_err = CacheError::StorageBackend("be".into());
         assert!(matches!(backend_err, CacheError::StorageBackend(_)));
 
-        let not_found = CacheError::NotFound;
-        assert_eq!(format!("{not_found}"), "Entry not found for key");
-
         let custom = CacheError::Custom("c".into());
         assert_eq!(format!("{custom}"), "Custom error: c");
     }
 
+    #[test]
+    fn test_partial_save_message() {
+        let partial = CacheError::PartialSave {
+            failures: vec![
+                ("a".into(), CacheError::StorageBackend("x".into())),
+                ("b".into(), CacheError::Custom("y".into())),
+            ],
+        };
+        assert_eq!(
+            format!("{partial}"),
+            "Failed to save 2 keys: a (Storage backend error: x), b (Custom error: y)"
+        );
+
+        let partial = CacheError::PartialSave {
+            failures: vec![("a".into(), CacheError::Custom("y".into()))],
+        };
+        assert_eq!(
+            format!("{partial}"),
+            "Failed to save 1 key: a (Custom error: y)"
+        );
+    }
+
+    #[test]
+    fn test_read_only_message() {
+        assert_eq!(format!("{}", CacheError::ReadOnly), "Cache is read-only");
+    }
+
+    #[test]
+    fn test_with_key_names_the_key() {
+        let backend_err = CacheError::StorageBackend("timeout".into()).with_key("user:1");
+        assert_eq!(
+            format!("{backend_err}"),
+            "Storage backend error: key user:1: timeout"
+        );
+
+        let io_err = CacheError::from(io::Error::new(io::ErrorKind::NotFound, "gone")).with_key(7);
+        let CacheError::Io(io_err) = io_err else {
+            panic!("with_key changed the variant");
+        };
+        assert_eq!(io_err.kind(), io::ErrorKind::NotFound);
+        assert_eq!(io_err.to_string(), "key 7: gone");
+        let source = std::error::Error::source(io_err.get_ref().unwrap()).unwrap();
+        assert_eq!(source.to_string(), "gone");
+
+        let read_only = CacheError::ReadOnly.with_key("b");
+        assert_eq!(format!("{read_only}"), "Cache is read-only");
+    }
+
     #[cfg(feature = "json-serialization")]
     #[test]
     fn test_cache_error_from_json() {
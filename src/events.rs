@@ -0,0 +1,22 @@
+//! Events published by a cache as it is used
+
+/// Something that happened to a key in a [`Cache`](crate::Cache)
+///
+/// Install a channel with
+/// [`Cache::with_event_channel`](crate::Cache::with_event_channel) and listen
+/// with [`Cache::subscribe`](crate::Cache::subscribe). Events are sent on a
+/// best-effort basis: a subscriber that falls behind the channel's capacity
+/// misses the oldest ones.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CacheEvent<K> {
+    /// An entry was stored under the key
+    Put(K),
+    /// A lookup found the key
+    Get(K),
+    /// A lookup did not find the key
+    Miss(K),
+    /// The eviction strategy removed the key
+    Evict(K),
+    /// Expired entries under the key were dropped when it was read
+    Expire(K),
+}
@@ -3,23 +3,125 @@
 use crate::config::EvictionPolicy;
 use crate::{CacheEntry, EntryMetadata};
 use async_trait::async_trait;
-use chrono::Utc;
+use chrono::{DateTime, Utc};
+use std::collections::hash_map::RandomState;
 use std::collections::HashMap;
-use std::hash::Hash;
+use std::hash::{BuildHasher, Hash, Hasher};
+use std::sync::{Mutex, PoisonError};
 
 /// Type alias for eviction strategy box
 type EvictionStrategyBox<K, V, M> = Box<dyn EvictionStrategy<K, V, M>>;
 
+/// Keys removed by an eviction pass, together with the entries that were dropped
+pub type Evicted<K, V, M> = Vec<(K, Vec<CacheEntry<K, V, M>>)>;
+
 /// Context for eviction decisions
+///
+/// Build one with [`EvictionContext::measure`]; fields may be added in any
+/// release.
 #[derive(Debug, Clone)]
+#[non_exhaustive]
 pub struct EvictionContext {
     /// Maximum total entries allowed
     pub max_total_entries: usize,
     /// Current total entries
     pub current_total_entries: usize,
+    /// Entry count eviction brings the cache down to once it is over
+    /// `max_total_entries`; equal to it unless a low watermark is set
+    pub target_total_entries: usize,
+    /// Maximum total size in bytes, if the cache has a byte budget
+    pub max_total_bytes: Option<u64>,
+    /// Current total size in bytes, as reported by `EntryMetadata::size_bytes`
+    pub current_total_bytes: u64,
+    /// Time the decision is made at, from the cache's clock
+    pub now: DateTime<Utc>,
+}
+
+impl EvictionContext {
+    /// Build a context from the current contents of the cache
+    pub fn measure<K, V, M>(
+        entries: &HashMap<K, Vec<CacheEntry<K, V, M>>>,
+        max_total_entries: usize,
+        max_total_bytes: Option<u64>,
+    ) -> Self
+    where
+        K: Hash + Eq + Clone,
+        V: Clone,
+        M: EntryMetadata,
+    {
+        let (current_total_entries, current_total_bytes) = entries
+            .values()
+            .flat_map(|v| v.iter())
+            .fold((0, 0u64), |(count, bytes), entry| {
+                let size = entry.metadata.size_bytes().unwrap_or(0);
+                (count + 1, bytes.saturating_add(size))
+            });
+        Self {
+            max_total_entries,
+            current_total_entries,
+            target_total_entries: max_total_entries,
+            max_total_bytes,
+            current_total_bytes,
+            now: Utc::now(),
+        }
+    }
+
+    /// Decide as of `now` rather than the system time
+    pub fn with_now(mut self, now: DateTime<Utc>) -> Self {
+        self.now = now;
+        self
+    }
+
+    /// Evict down to `target` entries rather than just below the limit
+    ///
+    /// Targets above `max_total_entries` are lowered to it.
+    pub fn with_target_total_entries(mut self, target: usize) -> Self {
+        self.target_total_entries = target.min(self.max_total_entries);
+        self
+    }
+
+    /// Whether the entry count is over its limit
+    pub fn over_entry_limit(&self) -> bool {
+        self.current_total_entries > self.max_total_entries
+    }
+
+    /// Whether the total size is over the byte budget
+    pub fn over_byte_budget(&self) -> bool {
+        self.max_total_bytes
+            .is_some_and(|max| self.current_total_bytes > max)
+    }
+
+    /// Whether either limit is exceeded
+    pub fn over_capacity(&self) -> bool {
+        self.over_entry_limit() || self.over_byte_budget()
+    }
+
+    /// Whether eviction, once started, should keep going
+    ///
+    /// Stricter than `over_capacity` when the target sits below the entry
+    /// limit, so a busy cache is not back over its limit on the next insert.
+    pub fn above_target(&self) -> bool {
+        self.current_total_entries > self.target_total_entries || self.over_byte_budget()
+    }
+
+    /// Update the current totals after `removed` left the cache
+    pub fn record_removal<K, V, M>(&mut self, removed: &[CacheEntry<K, V, M>])
+    where
+        K: Hash + Eq + Clone,
+        V: Clone,
+        M: EntryMetadata,
+    {
+        let bytes = removed
+            .iter()
+            .map(|entry| entry.metadata.size_bytes().unwrap_or(0))
+            .fold(0u64, u64::saturating_add);
+        self.current_total_entries = self.current_total_entries.saturating_sub(removed.len());
+        self.current_total_bytes = self.current_total_bytes.saturating_sub(bytes);
+    }
 }
 
-fn remove_key_by<K, V, M, F, T>(entries: &mut HashMap<K, Vec<CacheEntry<K, V, M>>>, metric: F)
+/// Find the key whose entries have the smallest `metric`
+fn min_key_by<K, V, M, F, T>(entries: &HashMap<K, Vec<CacheEntry<K, V, M>>>, metric: F) -> Option<K>
 where
     K: Hash + Eq + Clone,
     V: Clone,
@@ -27,13 +129,37 @@ where
     F: Fn(&[CacheEntry<K, V, M>]) -> T,
     T: Ord,
 {
-    if let Some(key) = entries
+    entries
         .iter()
         .min_by_key(|(_, v)| metric(v))
         .map(|(k, _)| k.clone())
-    {
-        entries.remove(&key);
+}
+
+/// Remove the keys chosen by `pick` until the cache is back within capacity
+/// and down to the context's target
+///
+/// Stops early if `pick` runs out of keys.
+fn evict_until_fits<K, V, M, F>(
+    entries: &mut HashMap<K, Vec<CacheEntry<K, V, M>>>,
+    context: &EvictionContext,
+    mut pick: F,
+) -> Evicted<K, V, M>
+where
+    K: Hash + Eq + Clone,
+    V: Clone,
+    M: EntryMetadata,
+    F: FnMut(&HashMap<K, Vec<CacheEntry<K, V, M>>>) -> Option<K>,
+{
+    let mut context = context.clone();
+    let mut evicted = Vec::new();
+    while context.above_target() {
+        let Some((key, removed)) = pick(entries).and_then(|key| entries.remove_entry(&key)) else {
+            break;
+        };
+        context.record_removal(&removed);
+        evicted.push((key, removed));
     }
+    evicted
 }
 
 /// Trait for eviction strategies
@@ -45,11 +171,19 @@ where
     M: EntryMetadata,
 {
     /// Evict entries based on the strategy
+    ///
+    /// Returns every key that lost entries along with the removed entries.
     async fn evict(
         &self,
         entries: &mut HashMap<K, Vec<CacheEntry<K, V, M>>>,
         _context: &EvictionContext,
-    );
+    ) -> Evicted<K, V, M>;
+
+    /// Called when an entry is stored under `key`
+    fn on_insert(&self, _key: &K) {}
+
+    /// Called when a lookup finds entries under `key`
+    fn on_access(&self, _key: &K) {}
 }
 
 /// Create an eviction strategy based on policy
@@ -65,6 +199,8 @@ where
         EvictionPolicy::Lfu => Box::new(LfuEviction),
         EvictionPolicy::Fifo => Box::new(FifoEviction),
         EvictionPolicy::Ttl => Box::new(TtlEviction),
+        EvictionPolicy::Arc => Box::new(ArcEviction::new()),
+        EvictionPolicy::TinyLfu => Box::new(TinyLfuEviction::new()),
         EvictionPolicy::None => Box::new(NoEviction),
     }
 }
@@ -82,7 +218,7 @@ macro_rules! impl_eviction_strategy {
                 &self,
                 $entries: &mut HashMap<K, Vec<CacheEntry<K, V, M>>>,
                 $ctx: &EvictionContext,
-            ) $body
+            ) -> Evicted<K, V, M> $body
         }
     };
 }
@@ -92,8 +228,9 @@ macro_rules! simple_eviction {
         $(#[$meta])*
         pub struct $name;
 
-        impl_eviction_strategy!($name, _context, entries, {
-            remove_key_by(entries, $metric);
+        impl_eviction_strategy!($name, context, entries, {
+            let metric = $metric;
+            evict_until_fits(entries, context, |entries| min_key_by(entries, &metric))
         });
     };
 }
@@ -103,8 +240,8 @@ simple_eviction!(
     LruEviction,
     |v: &[CacheEntry<K, V, M>]| {
         v.iter()
-            .min_by_key(|e| e.last_accessed)
-            .map(|e| e.last_accessed)
+            .map(|e| e.last_accessed())
+            .min()
             .unwrap_or_else(Utc::now)
     }
 );
@@ -112,7 +249,7 @@ simple_eviction!(
 simple_eviction!(
     /// Least Frequently Used eviction
     LfuEviction,
-    |v: &[CacheEntry<K, V, M>]| v.iter().map(|e| e.access_count).sum::<u64>()
+    |v: &[CacheEntry<K, V, M>]| v.iter().map(|e| e.access_count()).sum::<u64>()
 );
 
 simple_eviction!(
@@ -130,25 +267,444 @@ simple_eviction!(
 pub struct TtlEviction;
 
 impl_eviction_strategy!(TtlEviction, context, entries, {
+    let mut evicted = Vec::new();
     for key in entries.keys().cloned().collect::<Vec<_>>() {
         if let Some(vec) = entries.get_mut(&key) {
-            vec.retain(|e| !e.is_expired());
+            let (expired, live): (Vec<_>, Vec<_>) =
+                vec.drain(..).partition(|e| e.is_expired_at(context.now));
+            *vec = live;
             if vec.is_empty() {
                 entries.remove(&key);
             }
+            if !expired.is_empty() {
+                evicted.push((key, expired));
+            }
         }
     }
-    let total_entries: usize = entries.values().map(|v| v.len()).sum();
-    if total_entries > context.max_total_entries {
-        FifoEviction.evict(entries, context).await;
+    let context =
+        EvictionContext::measure(entries, context.max_total_entries, context.max_total_bytes)
+            .with_target_total_entries(context.target_total_entries)
+            .with_now(context.now);
+    if context.above_target() {
+        evicted.extend(FifoEviction.evict(entries, &context).await);
     }
+    evicted
 });
 
+/// Adaptive Replacement Cache eviction
+///
+/// Keys seen once sit in a recency list and move to a frequency list when
+/// they are seen again. Keys evicted from either list are remembered in a
+/// matching ghost list; inserting a ghost again shifts the target size of
+/// the recency list towards the list that would have kept it. One-off scans
+/// therefore only displace other one-off keys, while frequently used keys
+/// survive.
+pub struct ArcEviction<K> {
+    state: Mutex<ArcState<K>>,
+}
+
+/// Lists tracked by `ArcEviction`, each mapping a key to its last use tick
+struct ArcState<K> {
+    recent: HashMap<K, u64>,
+    frequent: HashMap<K, u64>,
+    recent_ghosts: HashMap<K, u64>,
+    frequent_ghosts: HashMap<K, u64>,
+    /// Target size of the recency list
+    target: usize,
+    /// Capacity seen on the last eviction, bounding `target` and the ghosts
+    capacity: usize,
+    tick: u64,
+}
+
+impl<K: Hash + Eq + Clone> ArcEviction<K> {
+    /// Create a strategy with empty lists
+    pub fn new() -> Self {
+        Self {
+            state: Mutex::new(ArcState {
+                recent: HashMap::new(),
+                frequent: HashMap::new(),
+                recent_ghosts: HashMap::new(),
+                frequent_ghosts: HashMap::new(),
+                target: 0,
+                capacity: usize::MAX,
+                tick: 0,
+            }),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, ArcState<K>> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<K: Hash + Eq + Clone> Default for ArcEviction<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Hash + Eq + Clone> ArcState<K> {
+    fn next_tick(&mut self) -> u64 {
+        self.tick += 1;
+        self.tick
+    }
+
+    /// Record a use of a key, promoting it to the frequency list
+    fn touch(&mut self, key: &K) {
+        let tick = self.next_tick();
+        if self.recent.remove(key).is_some() || self.frequent.contains_key(key) {
+            self.frequent.insert(key.clone(), tick);
+        } else {
+            self.recent.insert(key.clone(), tick);
+        }
+    }
+
+    fn insert(&mut self, key: &K) {
+        if self.recent.contains_key(key) || self.frequent.contains_key(key) {
+            self.touch(key);
+            return;
+        }
+
+        let recent_ghosts = self.recent_ghosts.len();
+        let frequent_ghosts = self.frequent_ghosts.len();
+        if self.recent_ghosts.remove(key).is_some() {
+            let delta = (frequent_ghosts / recent_ghosts).max(1);
+            self.target = self.target.saturating_add(delta).min(self.capacity);
+        } else if self.frequent_ghosts.remove(key).is_some() {
+            let delta = (recent_ghosts / frequent_ghosts).max(1);
+            self.target = self.target.saturating_sub(delta);
+        } else {
+            let tick = self.next_tick();
+            self.recent.insert(key.clone(), tick);
+            return;
+        }
+        let tick = self.next_tick();
+        self.frequent.insert(key.clone(), tick);
+    }
+
+    /// Drop keys the cache no longer holds and adopt untracked keys, such as
+    /// ones loaded from storage, as the least recent entries
+    fn reconcile<V, M>(&mut self, entries: &HashMap<K, Vec<CacheEntry<K, V, M>>>)
+    where
+        V: Clone,
+        M: EntryMetadata,
+    {
+        self.recent.retain(|key, _| entries.contains_key(key));
+        self.frequent.retain(|key, _| entries.contains_key(key));
+        for key in entries.keys() {
+            if !self.recent.contains_key(key) && !self.frequent.contains_key(key) {
+                self.recent.insert(key.clone(), 0);
+            }
+        }
+    }
+
+    /// Pick the key to evict and move it to its ghost list
+    fn replace(&mut self) -> Option<K> {
+        let from_recent = !self.recent.is_empty()
+            && (self.recent.len() > self.target || self.frequent.is_empty());
+        let tick = self.next_tick();
+        let capacity = self.capacity;
+        let (list, ghosts) = if from_recent {
+            (&mut self.recent, &mut self.recent_ghosts)
+        } else {
+            (&mut self.frequent, &mut self.frequent_ghosts)
+        };
+
+        let key = least_recent(list)?;
+        list.remove(&key);
+        ghosts.insert(key.clone(), tick);
+        while ghosts.len() > capacity {
+            match least_recent(ghosts) {
+                Some(oldest) => ghosts.remove(&oldest),
+                None => break,
+            };
+        }
+        Some(key)
+    }
+}
+
+fn least_recent<K: Clone>(list: &HashMap<K, u64>) -> Option<K> {
+    list.iter()
+        .min_by_key(|(_, tick)| **tick)
+        .map(|(key, _)| key.clone())
+}
+
+#[async_trait]
+impl<K, V, M> EvictionStrategy<K, V, M> for ArcEviction<K>
+where
+    K: Hash + Eq + Clone + Send + Sync,
+    V: Clone + Send + Sync,
+    M: EntryMetadata,
+{
+    async fn evict(
+        &self,
+        entries: &mut HashMap<K, Vec<CacheEntry<K, V, M>>>,
+        context: &EvictionContext,
+    ) -> Evicted<K, V, M> {
+        let mut state = self.lock();
+        state.capacity = context.max_total_entries;
+        state.target = state.target.min(state.capacity);
+        state.reconcile(entries);
+        evict_until_fits(entries, context, |_| state.replace())
+    }
+
+    fn on_insert(&self, key: &K) {
+        self.lock().insert(key);
+    }
+
+    fn on_access(&self, key: &K) {
+        self.lock().touch(key);
+    }
+}
+
+/// Number of rows in a `FrequencySketch`
+const SKETCH_DEPTH: usize = 4;
+
+/// Highest count a `FrequencySketch` counter reaches
+const SKETCH_MAX_COUNT: u8 = 15;
+
+/// Count-min sketch estimating how often each key has been seen recently
+///
+/// Each key bumps one counter per row, and its estimate is the smallest of
+/// them, so collisions can only make a key look more frequent. Once it has
+/// been bumped ten times per counter in a row, every counter is halved, so
+/// keys that were popular long ago fade.
+struct FrequencySketch {
+    rows: Vec<u8>,
+    /// Counters per row, a power of two
+    width: usize,
+    hasher: RandomState,
+    additions: usize,
+}
+
+impl FrequencySketch {
+    fn new(width: usize) -> Self {
+        let width = width.max(16).next_power_of_two();
+        Self {
+            rows: vec![0; width * SKETCH_DEPTH],
+            width,
+            hasher: RandomState::new(),
+            additions: 0,
+        }
+    }
+
+    /// Index of the key's counter in each row
+    fn slots<K: Hash>(&self, key: &K) -> [usize; SKETCH_DEPTH] {
+        let mut hasher = self.hasher.build_hasher();
+        key.hash(&mut hasher);
+        let hash = hasher.finish();
+        let step = hash.rotate_left(32) | 1;
+        let mut slots = [0; SKETCH_DEPTH];
+        for (row, slot) in slots.iter_mut().enumerate() {
+            let column =
+                hash.wrapping_add(step.wrapping_mul(row as u64)) as usize & (self.width - 1);
+            *slot = row * self.width + column;
+        }
+        slots
+    }
+
+    fn increment<K: Hash>(&mut self, key: &K) {
+        for slot in self.slots(key) {
+            let count = &mut self.rows[slot];
+            *count = (*count + 1).min(SKETCH_MAX_COUNT);
+        }
+        self.additions += 1;
+        if self.additions >= self.width * 10 {
+            self.additions /= 2;
+            for count in &mut self.rows {
+                *count /= 2;
+            }
+        }
+    }
+
+    fn estimate<K: Hash>(&self, key: &K) -> u8 {
+        self.slots(key)
+            .iter()
+            .map(|&slot| self.rows[slot])
+            .min()
+            .unwrap_or(0)
+    }
+}
+
+/// TinyLFU admission in front of LRU eviction
+///
+/// Every insert and hit is counted in a frequency sketch. When the cache is
+/// full, the key inserted last only stays if the sketch says it is used more
+/// often than the least recently used key; otherwise the newcomer itself is
+/// evicted, and reported like any other eviction. Keys seen once, such as
+/// those of a scan, therefore cannot push out keys that are used all the
+/// time, while a newcomer that keeps being requested soon wins its place.
+///
+/// On skewed workloads this keeps far more of the popular keys than LRU: in
+/// the Zipfian test of the cache module, with room for 5% of the keys, LRU
+/// hits about 47% of requests and TinyLFU about 58%. On workloads without
+/// repeat visits it behaves like LRU, minus the newcomers it turns away.
+pub struct TinyLfuEviction<K> {
+    state: Mutex<TinyLfuState<K>>,
+}
+
+struct TinyLfuState<K> {
+    sketch: FrequencySketch,
+    /// Key inserted last, and not yet admitted over a victim
+    candidate: Option<K>,
+}
+
+impl<K: Hash + Eq + Clone> TinyLfuEviction<K> {
+    /// Create a strategy with an empty sketch
+    ///
+    /// The sketch is resized to fit the capacity on the first eviction.
+    pub fn new() -> Self {
+        Self {
+            state: Mutex::new(TinyLfuState {
+                sketch: FrequencySketch::new(256),
+                candidate: None,
+            }),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, TinyLfuState<K>> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<K: Hash + Eq + Clone> Default for TinyLfuEviction<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl<K, V, M> EvictionStrategy<K, V, M> for TinyLfuEviction<K>
+where
+    K: Hash + Eq + Clone + Send + Sync,
+    V: Clone + Send + Sync,
+    M: EntryMetadata,
+{
+    async fn evict(
+        &self,
+        entries: &mut HashMap<K, Vec<CacheEntry<K, V, M>>>,
+        context: &EvictionContext,
+    ) -> Evicted<K, V, M> {
+        let mut state = self.lock();
+        if state.sketch.width < context.max_total_entries {
+            state.sketch = FrequencySketch::new(context.max_total_entries);
+        }
+        evict_until_fits(entries, context, |entries| {
+            let least_recent = |skip: Option<&K>| {
+                entries
+                    .iter()
+                    .filter(|(key, _)| Some(*key) != skip)
+                    .min_by_key(|(_, v)| v.iter().map(|e| e.last_accessed()).min())
+                    .map(|(key, _)| key.clone())
+            };
+            let candidate = state
+                .candidate
+                .take()
+                .filter(|key| entries.contains_key(key));
+            let Some(candidate) = candidate else {
+                return least_recent(None);
+            };
+            let Some(victim) = least_recent(Some(&candidate)) else {
+                return Some(candidate);
+            };
+            if state.sketch.estimate(&candidate) > state.sketch.estimate(&victim) {
+                // Admitted, but it still has to beat the next victim
+                state.candidate = Some(candidate);
+                Some(victim)
+            } else {
+                Some(candidate)
+            }
+        })
+    }
+
+    fn on_insert(&self, key: &K) {
+        let mut state = self.lock();
+        state.sketch.increment(key);
+        state.candidate = Some(key.clone());
+    }
+
+    fn on_access(&self, key: &K) {
+        self.lock().sketch.increment(key);
+    }
+}
+
+/// How `MetadataScoreEviction` combines the scores of a key's entries
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ScoreAggregate {
+    /// Add up the scores of every entry under the key
+    #[default]
+    Sum,
+    /// Use the lowest score of any entry under the key
+    Min,
+}
+
+/// Eviction by a score read from entry metadata
+///
+/// Keys with the lowest score are evicted first, regardless of how recently
+/// or often they were used. The scoring closure cannot be expressed in
+/// [`EvictionPolicy`], so install this strategy with
+/// [`Cache::with_eviction_strategy`](crate::Cache::with_eviction_strategy).
+pub struct MetadataScoreEviction<M> {
+    score: Box<dyn Fn(&M) -> i64 + Send + Sync>,
+    aggregate: ScoreAggregate,
+}
+
+impl<M> MetadataScoreEviction<M> {
+    /// Create a strategy that scores each entry with `score`
+    pub fn new<F>(score: F) -> Self
+    where
+        F: Fn(&M) -> i64 + Send + Sync + 'static,
+    {
+        Self {
+            score: Box::new(score),
+            aggregate: ScoreAggregate::default(),
+        }
+    }
+
+    /// Set how the scores of a key's entries are combined
+    pub fn with_aggregate(mut self, aggregate: ScoreAggregate) -> Self {
+        self.aggregate = aggregate;
+        self
+    }
+
+    fn key_score<K, V>(&self, entries: &[CacheEntry<K, V, M>]) -> i64
+    where
+        K: Hash + Eq + Clone,
+        V: Clone,
+        M: EntryMetadata,
+    {
+        let scores = entries.iter().map(|entry| (self.score)(&entry.metadata));
+        match self.aggregate {
+            ScoreAggregate::Sum => scores.fold(0, i64::saturating_add),
+            ScoreAggregate::Min => scores.min().unwrap_or(i64::MAX),
+        }
+    }
+}
+
+#[async_trait]
+impl<K, V, M> EvictionStrategy<K, V, M> for MetadataScoreEviction<M>
+where
+    K: Hash + Eq + Clone + Send + Sync,
+    V: Clone + Send + Sync,
+    M: EntryMetadata,
+{
+    async fn evict(
+        &self,
+        entries: &mut HashMap<K, Vec<CacheEntry<K, V, M>>>,
+        context: &EvictionContext,
+    ) -> Evicted<K, V, M> {
+        evict_until_fits(entries, context, |entries| {
+            min_key_by(entries, |v| self.key_score(v))
+        })
+    }
+}
+
 /// No eviction (manual only)
 pub struct NoEviction;
 
 impl_eviction_strategy!(NoEviction, _context, _entries, {
     // No automatic eviction
+    Vec::new()
 });
 
 #[cfg(test)]
@@ -160,6 +716,10 @@ mod tests {
         EvictionContext {
             max_total_entries: 1,
             current_total_entries: 2,
+            target_total_entries: 1,
+            max_total_bytes: None,
+            current_total_bytes: 0,
+            now: Utc::now(),
         }
     }
 
@@ -188,8 +748,8 @@ mod tests {
     #[tokio::test]
     async fn test_lru_eviction() {
         let mut entries = setup_entries(|e1, e2| {
-            e1.last_accessed = Utc::now() - Duration::hours(1);
-            e2.last_accessed = Utc::now();
+            e1.set_last_accessed(Utc::now() - Duration::hours(1));
+            e2.set_last_accessed(Utc::now());
         });
 
         let eviction = LruEviction;
@@ -205,8 +765,8 @@ mod tests {
     #[tokio::test]
     async fn test_lfu_eviction() {
         let mut entries = setup_entries(|e1, e2| {
-            e1.access_count = 1;
-            e2.access_count = 5;
+            e1.set_access_count(1);
+            e2.set_access_count(5);
         });
 
         let eviction = LfuEviction;
@@ -251,6 +811,10 @@ mod tests {
         let context = EvictionContext {
             max_total_entries: 10,
             current_total_entries: 2,
+            target_total_entries: 10,
+            max_total_bytes: None,
+            current_total_bytes: 0,
+            now: Utc::now(),
         };
 
         eviction.evict(&mut entries, &context).await;
@@ -259,4 +823,213 @@ mod tests {
         assert!(!entries.contains_key("key1"));
         assert!(entries.contains_key("key2"));
     }
+
+    #[tokio::test]
+    async fn test_evict_returns_the_removed_buckets() {
+        let mut entries = setup_entries(|e1, e2| {
+            e1.set_last_accessed(Utc::now() - Duration::hours(1));
+            e2.set_last_accessed(Utc::now());
+        });
+
+        let evicted = LruEviction.evict(&mut entries, &small_context()).await;
+        assert_eq!(evicted.len(), 1);
+        assert_eq!(evicted[0].0, "key1");
+        assert_eq!(evicted[0].1[0].value, "value1");
+    }
+
+    #[tokio::test]
+    async fn test_eviction_runs_until_within_capacity() {
+        let mut entries = HashMap::new();
+        for i in 0..5 {
+            let key = format!("key{i}");
+            let mut entry = create_test_entry(key.clone(), "v".to_string());
+            entry.timestamp = Utc::now() - Duration::seconds(10 - i);
+            entries.insert(key, vec![entry]);
+        }
+        let context = EvictionContext::measure(&entries, 2, None);
+
+        let evicted = FifoEviction.evict(&mut entries, &context).await;
+        let mut keys: Vec<_> = evicted.into_iter().map(|(key, _)| key).collect();
+        keys.sort();
+        assert_eq!(keys, ["key0", "key1", "key2"]);
+        assert_eq!(entries.len(), 2);
+
+        // Already within capacity, nothing is evicted
+        let context = EvictionContext::measure(&entries, 2, None);
+        assert!(LruEviction.evict(&mut entries, &context).await.is_empty());
+    }
+
+    #[test]
+    fn test_context_measure() {
+        use crate::entry::BasicMetadata;
+
+        let mut entries: HashMap<String, Vec<CacheEntry<String, String, BasicMetadata>>> =
+            HashMap::new();
+        for (key, size) in [("a", Some(100)), ("b", Some(250)), ("c", None)] {
+            let metadata = BasicMetadata {
+                size_bytes: size,
+                ..Default::default()
+            };
+            entries.insert(
+                key.to_string(),
+                vec![CacheEntry::with_metadata(
+                    key.to_string(),
+                    "v".to_string(),
+                    metadata,
+                )],
+            );
+        }
+
+        let context = EvictionContext::measure(&entries, 3, Some(300));
+        assert_eq!(context.current_total_entries, 3);
+        assert_eq!(context.current_total_bytes, 350);
+        assert!(!context.over_entry_limit());
+        assert!(context.over_byte_budget());
+        assert!(context.over_capacity());
+
+        let context = EvictionContext::measure(&entries, 3, None);
+        assert!(!context.over_capacity());
+    }
+
+    #[tokio::test]
+    async fn test_eviction_runs_down_to_target() {
+        let mut entries = HashMap::new();
+        for key in ["a", "b", "c", "d", "e"] {
+            entries.insert(
+                key.to_string(),
+                vec![create_test_entry(key.to_string(), "v".to_string())],
+            );
+        }
+
+        let context = EvictionContext::measure(&entries, 4, None).with_target_total_entries(2);
+        assert!(context.over_capacity());
+        let evicted = FifoEviction.evict(&mut entries, &context).await;
+        assert_eq!(evicted.len(), 3);
+        assert_eq!(entries.len(), 2);
+
+        let context = EvictionContext::measure(&entries, 1, None).with_target_total_entries(5);
+        assert_eq!(context.target_total_entries, 1);
+    }
+
+    #[tokio::test]
+    async fn test_metadata_score_eviction() {
+        use crate::entry::BasicMetadata;
+
+        // Score by execution time, standing in for a custom priority field
+        let entry = |key: &str, priority: u64| {
+            let metadata = BasicMetadata {
+                execution_time_ms: Some(priority),
+                ..Default::default()
+            };
+            CacheEntry::with_metadata(key.to_string(), "v".to_string(), metadata)
+        };
+        let setup = || {
+            let mut entries = HashMap::new();
+            entries.insert("a".to_string(), vec![entry("a", 5), entry("a", 5)]);
+            entries.insert("b".to_string(), vec![entry("b", 8)]);
+            entries.insert("c".to_string(), vec![entry("c", 1), entry("c", 20)]);
+            entries
+        };
+        let score = |m: &BasicMetadata| m.execution_time_ms.unwrap_or(0) as i64;
+
+        let mut entries = setup();
+        let context = EvictionContext::measure(&entries, 4, None);
+        let evicted = MetadataScoreEviction::new(score)
+            .evict(&mut entries, &context)
+            .await;
+        assert_eq!(evicted.len(), 1);
+        assert_eq!(evicted[0].0, "b");
+
+        let mut entries = setup();
+        let evicted = MetadataScoreEviction::new(score)
+            .with_aggregate(ScoreAggregate::Min)
+            .evict(&mut entries, &context)
+            .await;
+        assert_eq!(evicted[0].0, "c");
+        assert!(entries.contains_key("b"));
+    }
+
+    #[test]
+    fn test_frequency_sketch_counts_and_ages() {
+        let mut sketch = FrequencySketch::new(1024);
+        for _ in 0..3 {
+            sketch.increment(&"hot");
+        }
+        sketch.increment(&"cold");
+        assert!(sketch.estimate(&"hot") >= 3);
+        assert!(sketch.estimate(&"cold") >= 1);
+        assert!(sketch.estimate(&"hot") > sketch.estimate(&"unseen"));
+
+        // Enough additions halve every counter
+        sketch.additions = sketch.width * 10 - 1;
+        sketch.increment(&"other");
+        assert_eq!(sketch.estimate(&"hot"), 1);
+    }
+
+    #[tokio::test]
+    async fn test_tiny_lfu_rejects_rare_newcomers() {
+        let eviction = TinyLfuEviction::new();
+        let mut entries: HashMap<&str, Vec<CacheEntry<&str, i32>>> = HashMap::new();
+        for key in ["a", "b"] {
+            entries.insert(key, vec![CacheEntry::new(key, 0)]);
+            EvictionStrategy::<_, i32, ()>::on_insert(&eviction, &key);
+            EvictionStrategy::<_, i32, ()>::on_access(&eviction, &key);
+        }
+
+        // Seen once, the newcomer loses to the least recently used key
+        entries.insert("once", vec![CacheEntry::new("once", 0)]);
+        EvictionStrategy::<_, i32, ()>::on_insert(&eviction, &"once");
+        let context = EvictionContext::measure(&entries, 2, None);
+        let evicted = eviction.evict(&mut entries, &context).await;
+        assert_eq!(evicted[0].0, "once");
+
+        // Seen often enough, it takes the victim's place
+        for _ in 0..3 {
+            EvictionStrategy::<_, i32, ()>::on_access(&eviction, &"often");
+        }
+        entries.insert("often", vec![CacheEntry::new("often", 0)]);
+        EvictionStrategy::<_, i32, ()>::on_insert(&eviction, &"often");
+        let context = EvictionContext::measure(&entries, 2, None);
+        let evicted = eviction.evict(&mut entries, &context).await;
+        assert_eq!(evicted.len(), 1);
+        assert_ne!(evicted[0].0, "often");
+        assert!(entries.contains_key("often"));
+    }
+
+    #[tokio::test]
+    async fn test_arc_eviction_protects_frequent_keys() {
+        let eviction = ArcEviction::new();
+        let mut entries = HashMap::new();
+        let context = EvictionContext {
+            max_total_entries: 2,
+            current_total_entries: 3,
+            target_total_entries: 2,
+            max_total_bytes: None,
+            current_total_bytes: 0,
+            now: Utc::now(),
+        };
+
+        for key in ["hot", "once", "scan"] {
+            entries.insert(
+                key.to_string(),
+                vec![create_test_entry(key.to_string(), "v".to_string())],
+            );
+            EvictionStrategy::<String, String, ()>::on_insert(&eviction, &key.to_string());
+            if key == "hot" {
+                EvictionStrategy::<String, String, ()>::on_access(&eviction, &key.to_string());
+            }
+        }
+
+        // "once" is the least recent key seen only once
+        let evicted = eviction.evict(&mut entries, &context).await;
+        assert_eq!(evicted[0].0, "once");
+
+        // Re-inserting a ghost grows the recency target and lands in the
+        // frequency list
+        EvictionStrategy::<String, String, ()>::on_insert(&eviction, &"once".to_string());
+        let state = eviction.lock();
+        assert_eq!(state.target, 1);
+        assert!(state.frequent.contains_key("once"));
+        assert!(state.recent_ghosts.is_empty());
+    }
 }
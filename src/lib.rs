@@ -6,7 +6,7 @@
 //!
 //! - **Async-first**: Built on tokio for high-performance async operations
 //! - **Generic**: Works with any serializable key-value types
-//! - **Pluggable backends**: Filesystem, memory, or custom implementations
+//! - **Pluggable backends**: Filesystem, memory, Redis, SQLite, S3, or custom implementations
 //! - **Flexible serialization**: JSON, bincode, or custom formats
 //! - **Eviction policies**: LRU, LFU, FIFO, TTL-based eviction
 //! - **Compression**: Optional compression for stored values
@@ -46,41 +46,87 @@
 #![warn(rustdoc::missing_crate_level_docs)]
 
 pub mod backends;
+mod breaker;
+pub mod builder;
 pub mod cache;
+pub mod clock;
+#[cfg(feature = "compression")]
+pub mod compression;
 pub mod config;
+mod dedup;
 pub mod entry;
 pub mod error;
+pub mod events;
 pub mod eviction;
+pub mod loader;
+mod random;
+mod retry;
 pub mod search;
+mod shards;
 pub mod storage;
 
 #[cfg(feature = "metrics")]
 pub mod metrics;
 
+#[cfg(feature = "native-async")]
+pub mod native;
+
 #[cfg(test)]
 pub(crate) mod test_utils;
 
 // Re-export main types
-pub use cache::{AsyncCache, Cache};
-pub use config::{CacheConfig, EvictionPolicy, PersistenceConfig};
-pub use entry::{CacheEntry, EntryMetadata};
+pub use builder::CacheBuilder;
+pub use cache::{ArcCache, AsyncCache, Cache};
+pub use clock::{Clock, ManualClock, SystemClock};
+pub use config::{
+    CacheConfig, CacheMode, CircuitBreakerConfig, EvictionPolicy, MergePolicy, PerKeyEviction,
+    PersistenceConfig, RetryConfig, SyncStrategy,
+};
+pub use entry::{CacheEntry, EntryMetadata, MeasuredMetadata, TypedCategory};
 pub use error::{CacheError, Result};
+pub use events::CacheEvent;
+pub use loader::Loader;
+#[cfg(feature = "native-async")]
+pub use native::NativeAsyncCache;
 pub use search::{SearchQuery, Searchable};
 pub use storage::StorageBackend;
 
 // Re-export backend implementations
+#[cfg(feature = "append-log-backend")]
+pub use backends::append_log::AppendLogBackend;
+#[cfg(feature = "dashmap-backend")]
+pub use backends::dashmap::ConcurrentMemoryBackend;
 #[cfg(feature = "filesystem-backend")]
-pub use backends::filesystem::FilesystemBackend;
+pub use backends::filesystem::{migrate_format, FilesystemBackend, SaveErrorPolicy};
 pub use backends::memory::MemoryBackend;
+#[cfg(feature = "redis-backend")]
+pub use backends::redis::RedisBackend;
+#[cfg(feature = "s3-backend")]
+pub use backends::s3::S3Backend;
+#[cfg(feature = "sqlite-backend")]
+pub use backends::sqlite::SqliteBackend;
+pub use backends::tiered::TieredBackend;
 
 /// Prelude module for convenient imports
 pub mod prelude {
     pub use crate::{
-        AsyncCache, Cache, CacheConfig, CacheEntry, CacheError, EntryMetadata, Result, Searchable,
-        StorageBackend,
+        AsyncCache, Cache, CacheBuilder, CacheConfig, CacheEntry, CacheError, EntryMetadata,
+        Loader, Result, Searchable, StorageBackend,
     };
 
     #[cfg(feature = "filesystem-backend")]
     pub use crate::FilesystemBackend;
     pub use crate::MemoryBackend;
+    #[cfg(feature = "redis-backend")]
+    pub use crate::RedisBackend;
+    #[cfg(feature = "s3-backend")]
+    pub use crate::S3Backend;
+    #[cfg(feature = "sqlite-backend")]
+    pub use crate::SqliteBackend;
+    pub use crate::TieredBackend;
+
+    #[cfg(feature = "append-log-backend")]
+    pub use crate::AppendLogBackend;
+    #[cfg(feature = "dashmap-backend")]
+    pub use crate::ConcurrentMemoryBackend;
 }
@@ -0,0 +1,40 @@
+//! Read-through loading of missing values
+
+use async_trait::async_trait;
+
+use crate::Result;
+
+/// Source the cache falls back to when a lookup misses
+///
+/// Register one with [`Cache::with_loader`](crate::Cache::with_loader) to
+/// use the cache-aside pattern without calling the source yourself: `get`
+/// loads missing keys, stores what the loader found and returns it.
+///
+/// ```rust
+/// use async_trait::async_trait;
+/// use std::sync::Arc;
+/// use threatflux_cache::{AsyncCache, Cache, Loader, Result};
+///
+/// struct Squares;
+///
+/// #[async_trait]
+/// impl Loader<u64, u64> for Squares {
+///     async fn load(&self, key: &u64) -> Result<Option<u64>> {
+///         Ok(Some(key * key))
+///     }
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<()> {
+/// let cache: Cache<u64, u64> = Cache::with_config(Default::default())
+///     .await?
+///     .with_loader(Arc::new(Squares));
+/// assert_eq!(cache.get(&12).await?, Some(144));
+/// # Ok(())
+/// # }
+/// ```
+#[async_trait]
+pub trait Loader<K, V>: Send + Sync {
+    /// Fetch the value for `key`, or `None` if the source has none
+    async fn load(&self, key: &K) -> Result<Option<V>>;
+}
@@ -0,0 +1,108 @@
+//! Cache trait with unboxed futures
+//!
+//! [`NativeAsyncCache`] mirrors the core of [`AsyncCache`](crate::AsyncCache)
+//! using `async fn` in traits, so each call returns a concrete future instead
+//! of a boxed one. It needs Rust 1.75 or newer, which is why it sits behind
+//! the `native-async` feature.
+//!
+//! [`Cache`] implements both traits. Import only one of them where methods
+//! are called, or the shared method names become ambiguous.
+
+use std::future::Future;
+
+use crate::cache::{CacheKey, CacheValue};
+use crate::{Cache, CacheError, EntryMetadata, StorageBackend};
+
+/// Async cache trait whose methods return unboxed futures
+pub trait NativeAsyncCache<K, V>: Send + Sync
+where
+    K: CacheKey,
+    V: CacheValue,
+{
+    /// Error type for cache operations
+    type Error;
+
+    /// Get a value from the cache
+    fn get(&self, key: &K) -> impl Future<Output = Result<Option<V>, Self::Error>> + Send;
+
+    /// Put a value into the cache
+    fn put(&self, key: K, value: V) -> impl Future<Output = Result<(), Self::Error>> + Send;
+
+    /// Remove a value from the cache
+    fn remove(&self, key: &K) -> impl Future<Output = Result<Option<V>, Self::Error>> + Send;
+
+    /// Clear all entries from the cache
+    fn clear(&self) -> impl Future<Output = Result<(), Self::Error>> + Send;
+
+    /// Check if the cache contains a key
+    fn contains(&self, key: &K) -> impl Future<Output = Result<bool, Self::Error>> + Send;
+
+    /// Get the number of entries in the cache
+    fn len(&self) -> impl Future<Output = Result<usize, Self::Error>> + Send;
+
+    /// Check if the cache is empty
+    fn is_empty(&self) -> impl Future<Output = Result<bool, Self::Error>> + Send {
+        async { Ok(self.len().await? == 0) }
+    }
+}
+
+impl<K, V, M, B> NativeAsyncCache<K, V> for Cache<K, V, M, B>
+where
+    K: CacheKey,
+    V: CacheValue,
+    M: EntryMetadata + Default,
+    B: StorageBackend<Key = K, Value = V, Metadata = M>,
+{
+    type Error = CacheError;
+
+    async fn get(&self, key: &K) -> Result<Option<V>, Self::Error> {
+        self.get_value(key).await
+    }
+
+    async fn put(&self, key: K, value: V) -> Result<(), Self::Error> {
+        self.put_value(key, value).await
+    }
+
+    async fn remove(&self, key: &K) -> Result<Option<V>, Self::Error> {
+        self.remove_key(key).await
+    }
+
+    async fn clear(&self) -> Result<(), Self::Error> {
+        self.clear_all().await
+    }
+
+    async fn contains(&self, key: &K) -> Result<bool, Self::Error> {
+        Ok(self.contains_key(key).await)
+    }
+
+    async fn len(&self) -> Result<usize, Self::Error> {
+        Ok(self.entry_count().await)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_native_trait_matches_boxed_trait() {
+        let cache: Cache<String, String> = Cache::with_config(Default::default()).await.unwrap();
+        assert!(NativeAsyncCache::is_empty(&cache).await.unwrap());
+
+        cache.put("k".to_string(), "v".to_string()).await.unwrap();
+        assert_eq!(
+            cache.get(&"k".to_string()).await.unwrap().as_deref(),
+            Some("v")
+        );
+        assert!(cache.contains(&"k".to_string()).await.unwrap());
+        assert_eq!(cache.len().await.unwrap(), 1);
+
+        assert_eq!(
+            cache.remove(&"k".to_string()).await.unwrap().as_deref(),
+            Some("v")
+        );
+        cache.put("k".to_string(), "v".to_string()).await.unwrap();
+        cache.clear().await.unwrap();
+        assert!(crate::AsyncCache::is_empty(&cache).await.unwrap());
+    }
+}
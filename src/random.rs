@@ -0,0 +1,40 @@
+//! Cheap random numbers for spreading out retries and TTLs
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// A number in `0.0..1.0` that differs from call to call
+///
+/// Each `RandomState` is seeded differently, which is random enough to
+/// spread out retries without pulling in a random number generator.
+pub(crate) fn random_fraction() -> f64 {
+    let bits = RandomState::new().build_hasher().finish();
+    (bits >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// The next number in `0.0..1.0` of the sequence whose position is `state`
+///
+/// This is SplitMix64, so a given starting state always yields the same
+/// numbers.
+pub(crate) fn seeded_fraction(state: &AtomicU64) -> f64 {
+    let mut z = state
+        .fetch_add(0x9E37_79B9_7F4A_7C15, Ordering::Relaxed)
+        .wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    ((z ^ (z >> 31)) >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_random_fraction_is_in_range() {
+        for _ in 0..100 {
+            let random = random_fraction();
+            assert!((0.0..1.0).contains(&random));
+        }
+    }
+}
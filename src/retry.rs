@@ -0,0 +1,79 @@
+//! Retrying backend calls after transient errors
+
+use std::future::Future;
+use std::time::Duration;
+
+use crate::random::random_fraction;
+use crate::{CacheError, Result, RetryConfig};
+
+/// Delay before retrying a call that failed with `error` on attempt number
+/// `attempt`, or `None` if it should not be retried
+pub(crate) fn retry_delay(
+    retry: Option<&RetryConfig>,
+    error: &CacheError,
+    attempt: u32,
+) -> Option<Duration> {
+    let retry = retry?;
+    if attempt >= retry.max_attempts || !matches!(error, CacheError::StorageBackend(_)) {
+        return None;
+    }
+    Some(retry.delay(attempt, random_fraction()))
+}
+
+/// Run `op` until it succeeds, fails with an error that is not retried, or
+/// runs out of attempts, sleeping between attempts
+pub(crate) async fn with_retry<T, F, Fut>(retry: Option<&RetryConfig>, mut op: F) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let mut attempt = 1;
+    loop {
+        match op().await {
+            Err(error) => match retry_delay(retry, &error, attempt) {
+                Some(delay) => {
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                None => return Err(error),
+            },
+            result => return result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_only_backend_errors_are_retried() {
+        let retry = RetryConfig::new(3, Duration::from_millis(1));
+        let mut calls = 0;
+        let result: Result<()> = with_retry(Some(&retry), || {
+            calls += 1;
+            async { Err(CacheError::StorageBackend("down".into())) }
+        })
+        .await;
+        assert!(result.is_err());
+        assert_eq!(calls, 3);
+
+        let mut calls = 0;
+        let result: Result<()> = with_retry(Some(&retry), || {
+            calls += 1;
+            async { Err(CacheError::Serialization("bad".into())) }
+        })
+        .await;
+        assert!(matches!(result, Err(CacheError::Serialization(_))));
+        assert_eq!(calls, 1);
+
+        let mut calls = 0;
+        let result: Result<()> = with_retry(None, || {
+            calls += 1;
+            async { Err(CacheError::StorageBackend("down".into())) }
+        })
+        .await;
+        assert!(result.is_err());
+        assert_eq!(calls, 1);
+    }
+}
@@ -1,8 +1,14 @@
 //! Search and query functionality for cache entries
 
+use std::any::Any;
+use std::fmt;
+use std::sync::Arc;
+
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
+use crate::entry::TypedCategory;
+
 /// Trait for searchable cache entries
 pub trait Searchable {
     /// Query type for searching
@@ -10,6 +16,16 @@ pub trait Searchable {
 
     /// Check if this entry matches the query
     fn matches(&self, query: &Self::Query) -> bool;
+
+    /// Number of matching results to skip
+    fn query_offset(_query: &Self::Query) -> usize {
+        0
+    }
+
+    /// Maximum number of matching results to return
+    fn query_limit(_query: &Self::Query) -> Option<usize> {
+        None
+    }
 }
 
 /// Basic search query for cache entries
@@ -21,6 +37,8 @@ pub struct SearchQuery {
     pub min_timestamp: Option<DateTime<Utc>>,
     /// Maximum timestamp
     pub max_timestamp: Option<DateTime<Utc>>,
+    /// Minimum last access timestamp
+    pub min_last_accessed: Option<DateTime<Utc>>,
     /// Minimum access count
     pub min_access_count: Option<u64>,
     /// Maximum access count
@@ -29,6 +47,28 @@ pub struct SearchQuery {
     pub include_expired: bool,
     /// Category filter
     pub category: Option<String>,
+    /// Regular expression matched against keys and values
+    ///
+    /// Serialized as its source pattern.
+    #[cfg(feature = "regex-search")]
+    #[serde(default, with = "regex_serde")]
+    pub regex: Option<regex::Regex>,
+    /// Number of results to skip
+    #[serde(default)]
+    pub offset: usize,
+    /// Maximum number of results to return
+    pub limit: Option<usize>,
+    /// Tags of which an entry must carry at least one
+    #[serde(default)]
+    pub tags_any: Vec<String>,
+    /// Tags an entry must carry all of
+    #[serde(default)]
+    pub tags_all: Vec<String>,
+    /// Predicates run against entry metadata
+    ///
+    /// Not serialized, since closures cannot be.
+    #[serde(skip)]
+    pub metadata_filters: Vec<MetadataFilter>,
     /// Custom predicates as JSON
     #[cfg(feature = "json-serialization")]
     pub custom_predicates: Option<serde_json::Value>,
@@ -60,6 +100,24 @@ impl SearchQuery {
         self
     }
 
+    /// Only match entries created at or after `time`
+    pub fn with_created_after(mut self, time: DateTime<Utc>) -> Self {
+        self.min_timestamp = Some(time);
+        self
+    }
+
+    /// Only match entries created at or before `time`
+    pub fn with_created_before(mut self, time: DateTime<Utc>) -> Self {
+        self.max_timestamp = Some(time);
+        self
+    }
+
+    /// Only match entries last accessed at or after `time`
+    pub fn with_accessed_after(mut self, time: DateTime<Utc>) -> Self {
+        self.min_last_accessed = Some(time);
+        self
+    }
+
     /// Set access count range
     pub fn with_access_count_range(mut self, min: Option<u64>, max: Option<u64>) -> Self {
         self.min_access_count = min;
@@ -78,6 +136,130 @@ impl SearchQuery {
         self.category = Some(category.into());
         self
     }
+
+    /// Match keys or values against a regular expression
+    ///
+    /// The pattern is tested against each key's `Display` output and each
+    /// value's `Debug` output. `with_pattern`, if also set, still applies.
+    #[cfg(feature = "regex-search")]
+    pub fn with_regex(mut self, pattern: &str) -> crate::Result<Self> {
+        let regex = regex::Regex::new(pattern)
+            .map_err(|e| crate::CacheError::InvalidConfiguration(e.to_string()))?;
+        self.regex = Some(regex);
+        Ok(self)
+    }
+
+    /// Skip the first `offset` results
+    pub fn with_offset(mut self, offset: usize) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Return at most `limit` results
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Require entries to carry `tag`
+    pub fn with_tag<S: Into<String>>(mut self, tag: S) -> Self {
+        self.tags_all.push(tag.into());
+        self
+    }
+
+    /// Require entries to carry at least one of `tags`
+    pub fn with_tags_any<I, S>(mut self, tags: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.tags_any.extend(tags.into_iter().map(Into::into));
+        self
+    }
+
+    /// Require entries to carry every one of `tags`
+    pub fn with_tags_all<I, S>(mut self, tags: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.tags_all.extend(tags.into_iter().map(Into::into));
+        self
+    }
+
+    /// Only match entries whose metadata satisfies `filter`
+    ///
+    /// Filters accumulate, and an entry must pass all of them. An entry whose
+    /// metadata is not of type `M` never matches.
+    pub fn with_metadata_filter<M, F>(mut self, filter: F) -> Self
+    where
+        M: 'static,
+        F: Fn(&M) -> bool + Send + Sync + 'static,
+    {
+        self.metadata_filters
+            .push(MetadataFilter(Arc::new(move |metadata: &dyn Any| {
+                metadata.downcast_ref::<M>().is_some_and(&filter)
+            })));
+        self
+    }
+
+    /// Only match entries whose typed category equals `category`
+    pub fn with_category_filter<M: TypedCategory>(self, category: M::Category) -> Self {
+        self.with_metadata_filter(move |metadata: &M| metadata.typed_category() == Some(&category))
+    }
+
+    /// Check metadata against every metadata filter
+    fn matches_metadata(&self, metadata: &dyn Any) -> bool {
+        self.metadata_filters
+            .iter()
+            .all(|filter| (filter.0)(metadata))
+    }
+
+    /// Check a tag list against the tag filters
+    ///
+    /// An empty filter places no restriction on entries, while an entry with
+    /// no tags fails any non-empty filter.
+    fn matches_tags(&self, tags: &[String]) -> bool {
+        (self.tags_any.is_empty() || self.tags_any.iter().any(|tag| tags.contains(tag)))
+            && self.tags_all.iter().all(|tag| tags.contains(tag))
+    }
+}
+
+/// Predicate over metadata whose concrete type has been erased
+type ErasedPredicate = dyn Fn(&dyn Any) -> bool + Send + Sync;
+
+/// Type-erased predicate over entry metadata
+///
+/// Built by [`SearchQuery::with_metadata_filter`].
+#[derive(Clone)]
+pub struct MetadataFilter(Arc<ErasedPredicate>);
+
+impl fmt::Debug for MetadataFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("MetadataFilter")
+    }
+}
+
+/// Serialize a compiled regex as its source pattern
+#[cfg(feature = "regex-search")]
+mod regex_serde {
+    use regex::Regex;
+    use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        regex: &Option<Regex>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        regex.as_ref().map(Regex::as_str).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Regex>, D::Error> {
+        Option::<String>::deserialize(deserializer)?
+            .map(|pattern| Regex::new(&pattern).map_err(D::Error::custom))
+            .transpose()
+    }
 }
 
 /// Extended search capabilities
@@ -142,6 +324,14 @@ where
 
     fn matches(&self, query: &Self::Query) -> bool {
         let key_str = self.key.to_string();
+        #[cfg(feature = "regex-search")]
+        let regex_matches = || {
+            query.regex.as_ref().map_or(true, |regex| {
+                regex.is_match(&key_str) || regex.is_match(&format!("{:?}", self.value))
+            })
+        };
+        #[cfg(not(feature = "regex-search"))]
+        let regex_matches = || true;
         (query.include_expired || !self.is_expired())
             && query.pattern.as_ref().map_or(true, |p| key_str.contains(p))
             && query
@@ -150,15 +340,29 @@ where
             && query
                 .max_timestamp
                 .map_or(true, |max| self.timestamp <= max)
+            && query
+                .min_last_accessed
+                .map_or(true, |min| self.last_accessed() >= min)
             && query
                 .min_access_count
-                .map_or(true, |min| self.access_count >= min)
+                .map_or(true, |min| self.access_count() >= min)
             && query
                 .max_access_count
-                .map_or(true, |max| self.access_count <= max)
+                .map_or(true, |max| self.access_count() <= max)
             && query.category.as_ref().map_or(true, |category| {
                 self.metadata.category().is_some_and(|c| c == category)
             })
+            && query.matches_tags(self.metadata.tags())
+            && query.matches_metadata(&self.metadata)
+            && regex_matches()
+    }
+
+    fn query_offset(query: &Self::Query) -> usize {
+        query.offset
+    }
+
+    fn query_limit(query: &Self::Query) -> Option<usize> {
+        query.limit
     }
 }
 
@@ -185,7 +389,7 @@ mod tests {
     fn test_cache_entry_search() {
         let mut entry: CacheEntry<String, String, ()> =
             CacheEntry::new("test_key".to_string(), "test_value".to_string());
-        entry.access_count = 7;
+        entry.set_access_count(7);
 
         // Test pattern matching
         let query1 = SearchQuery::new().with_pattern("test");
@@ -215,6 +419,136 @@ mod tests {
         assert_eq!(query.category, Some("api".to_string()));
     }
 
+    #[test]
+    fn test_cache_entry_tag_search() {
+        use crate::entry::BasicMetadata;
+        let metadata = BasicMetadata {
+            tags: vec!["rust".to_string(), "cache".to_string()],
+            ..Default::default()
+        };
+        let tagged = CacheEntry::with_metadata("k".to_string(), "v".to_string(), metadata);
+        let untagged: CacheEntry<String, String, BasicMetadata> =
+            CacheEntry::new("u".to_string(), "v".to_string());
+
+        let q = SearchQuery::new().with_tag("rust");
+        assert!(tagged.matches(&q));
+        assert!(!untagged.matches(&q));
+
+        let q = SearchQuery::new().with_tags_all(["rust", "cache"]);
+        assert!(tagged.matches(&q));
+        let q = SearchQuery::new().with_tags_all(["rust", "go"]);
+        assert!(!tagged.matches(&q));
+
+        let q = SearchQuery::new().with_tags_any(["go", "cache"]);
+        assert!(tagged.matches(&q));
+        let q = SearchQuery::new().with_tags_any(["go", "java"]);
+        assert!(!tagged.matches(&q));
+
+        // No tag filters match everything, including untagged entries
+        assert!(untagged.matches(&SearchQuery::new()));
+    }
+
+    #[test]
+    fn test_cache_entry_time_range_search() {
+        let entry: CacheEntry<String, String, ()> =
+            CacheEntry::new("key".to_string(), "v".to_string());
+        let past = entry.timestamp - chrono::Duration::seconds(10);
+        let future = entry.timestamp + chrono::Duration::seconds(10);
+
+        assert!(entry.matches(&SearchQuery::new().with_created_after(past)));
+        assert!(!entry.matches(&SearchQuery::new().with_created_after(future)));
+        assert!(entry.matches(&SearchQuery::new().with_created_before(future)));
+        assert!(!entry.matches(&SearchQuery::new().with_created_before(past)));
+
+        let window = SearchQuery::new()
+            .with_created_after(past)
+            .with_created_before(future);
+        assert!(entry.matches(&window));
+        assert!(!entry.matches(&window.clone().with_pattern("other")));
+
+        assert!(!entry.matches(&SearchQuery::new().with_accessed_after(future)));
+        entry.record_access();
+        assert!(entry.matches(&SearchQuery::new().with_accessed_after(entry.timestamp)));
+    }
+
+    #[cfg(feature = "regex-search")]
+    #[test]
+    fn test_regex_search() {
+        let entry: CacheEntry<String, String, ()> =
+            CacheEntry::new("hash:abc123".to_string(), "Emotet dropper".to_string());
+
+        let q = SearchQuery::new().with_regex(r"^hash:[0-9a-f]+$").unwrap();
+        assert!(entry.matches(&q));
+        let q = SearchQuery::new().with_regex(r"(?i)emotet").unwrap();
+        assert!(entry.matches(&q));
+        let q = SearchQuery::new().with_regex(r"^url:").unwrap();
+        assert!(!entry.matches(&q));
+
+        // Combined with with_pattern, both must match
+        let q = SearchQuery::new()
+            .with_pattern("xyz")
+            .with_regex("hash")
+            .unwrap();
+        assert!(!entry.matches(&q));
+
+        let err = SearchQuery::new().with_regex("(unclosed").unwrap_err();
+        assert!(matches!(err, crate::CacheError::InvalidConfiguration(_)));
+    }
+
+    #[cfg(all(feature = "regex-search", feature = "json-serialization"))]
+    #[test]
+    fn test_regex_query_serialization() {
+        let query = SearchQuery::new().with_regex("^a+$").unwrap();
+        let json = serde_json::to_string(&query).unwrap();
+        let decoded: SearchQuery = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded.regex.unwrap().as_str(), "^a+$");
+    }
+
+    #[test]
+    fn test_metadata_and_typed_category_filters() {
+        #[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+        enum Verdict {
+            Clean,
+            Malicious,
+        }
+
+        #[derive(Clone, Debug, Serialize, Deserialize)]
+        struct ScanMetadata {
+            verdict: Option<Verdict>,
+            engines: u32,
+        }
+
+        impl crate::EntryMetadata for ScanMetadata {}
+
+        impl TypedCategory for ScanMetadata {
+            type Category = Verdict;
+
+            fn typed_category(&self) -> Option<&Verdict> {
+                self.verdict.as_ref()
+            }
+        }
+
+        let metadata = ScanMetadata {
+            verdict: Some(Verdict::Malicious),
+            engines: 12,
+        };
+        let entry = CacheEntry::with_metadata("k".to_string(), "v".to_string(), metadata);
+
+        let q = SearchQuery::new().with_category_filter::<ScanMetadata>(Verdict::Malicious);
+        assert!(entry.matches(&q));
+        let q = SearchQuery::new().with_category_filter::<ScanMetadata>(Verdict::Clean);
+        assert!(!entry.matches(&q));
+
+        let q = SearchQuery::new()
+            .with_category_filter::<ScanMetadata>(Verdict::Malicious)
+            .with_metadata_filter(|m: &ScanMetadata| m.engines > 20);
+        assert!(!entry.matches(&q));
+
+        // A filter written for another metadata type never matches
+        let q = SearchQuery::new().with_metadata_filter(|_: &()| true);
+        assert!(!entry.matches(&q));
+    }
+
     #[test]
     fn test_search_result_details() {
         let result = SearchResult::new(1u32, 0.5).with_detail("match");
@@ -0,0 +1,103 @@
+//! Key-sharded storage for cache entries
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hash, Hasher};
+use tokio::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use crate::storage::EntryMap;
+
+/// Cache entries split by key hash across independently locked maps
+///
+/// Operations on one key lock only the shard holding it. Operations over
+/// the whole cache lock every shard in index order, so they never deadlock
+/// with each other.
+pub(crate) struct Shards<K, V, M>
+where
+    K: Hash + Eq + Clone,
+    V: Clone,
+    M: Clone,
+{
+    shards: Box<[RwLock<EntryMap<K, V, M>>]>,
+    hasher: RandomState,
+}
+
+impl<K, V, M> Shards<K, V, M>
+where
+    K: Hash + Eq + Clone,
+    V: Clone,
+    M: Clone,
+{
+    /// Create `count` empty shards, or one if `count` is zero
+    pub(crate) fn new(count: usize) -> Self {
+        Self {
+            shards: (0..count.max(1))
+                .map(|_| RwLock::new(EntryMap::new()))
+                .collect(),
+            hasher: RandomState::new(),
+        }
+    }
+
+    /// Number of shards
+    pub(crate) fn count(&self) -> usize {
+        self.shards.len()
+    }
+
+    /// Index of the shard holding `key`
+    pub(crate) fn index_of(&self, key: &K) -> usize {
+        if self.shards.len() == 1 {
+            return 0;
+        }
+        let mut hasher = self.hasher.build_hasher();
+        key.hash(&mut hasher);
+        (hasher.finish() % self.shards.len() as u64) as usize
+    }
+
+    /// The shard at `index`
+    pub(crate) fn shard(&self, index: usize) -> &RwLock<EntryMap<K, V, M>> {
+        &self.shards[index]
+    }
+
+    /// The shard holding `key`
+    pub(crate) fn for_key(&self, key: &K) -> &RwLock<EntryMap<K, V, M>> {
+        self.shard(self.index_of(key))
+    }
+
+    /// Lock every shard for reading, in index order
+    pub(crate) async fn read_all(&self) -> Vec<RwLockReadGuard<'_, EntryMap<K, V, M>>> {
+        let mut guards = Vec::with_capacity(self.shards.len());
+        for shard in self.shards.iter() {
+            guards.push(shard.read().await);
+        }
+        guards
+    }
+
+    /// Lock every shard for writing, in index order
+    pub(crate) async fn write_all(&self) -> Vec<RwLockWriteGuard<'_, EntryMap<K, V, M>>> {
+        let mut guards = Vec::with_capacity(self.shards.len());
+        for shard in self.shards.iter() {
+            guards.push(shard.write().await);
+        }
+        guards
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keys_map_to_a_stable_shard() {
+        let shards: Shards<String, (), ()> = Shards::new(8);
+        assert_eq!(shards.count(), 8);
+        for i in 0..100 {
+            let key = format!("key-{i}");
+            let index = shards.index_of(&key);
+            assert!(index < 8);
+            assert_eq!(shards.index_of(&key), index);
+        }
+
+        let single: Shards<String, (), ()> = Shards::new(0);
+        assert_eq!(single.count(), 1);
+        assert_eq!(single.index_of(&"anything".to_string()), 0);
+    }
+}
@@ -3,13 +3,18 @@
 use crate::entry::CacheEntry;
 use crate::error::Result;
 use async_trait::async_trait;
-use serde::{de::DeserializeOwned, Serialize};
+use futures::stream::{self, BoxStream, StreamExt, TryStreamExt};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use std::collections::HashMap;
 use std::hash::Hash;
 
 /// Convenience alias for the internal storage map
 pub type EntryMap<K, V, M> = HashMap<K, Vec<CacheEntry<K, V, M>>>;
 
+/// Stream of the keys of a backend with their entries, as yielded by
+/// [`StorageBackend::load_stream`]
+pub type EntryStream<'a, K, V, M> = BoxStream<'a, Result<(K, Vec<CacheEntry<K, V, M>>)>>;
+
 /// Trait for cache storage backends
 #[async_trait]
 pub trait StorageBackend: Send + Sync + 'static {
@@ -23,9 +28,57 @@ pub trait StorageBackend: Send + Sync + 'static {
     /// Save entries to storage
     async fn save(&self, entries: &EntryMap<Self::Key, Self::Value, Self::Metadata>) -> Result<()>;
 
+    /// Save only the entries under `keys`
+    ///
+    /// `entries` holds everything the cache has, or only the entries of
+    /// `keys` when [`supports_partial_save`](Self::supports_partial_save)
+    /// is true; a key in `keys` that is missing from it was dropped since it
+    /// was last saved, and should be removed from storage. The default saves
+    /// all of `entries` with `save`, which suits backends that cannot write
+    /// part of their data.
+    async fn save_keys(
+        &self,
+        entries: &EntryMap<Self::Key, Self::Value, Self::Metadata>,
+        _keys: &[Self::Key],
+    ) -> Result<()> {
+        self.save(entries).await
+    }
+
+    /// Whether `save_keys` reads nothing from `entries` but the keys it is
+    /// given, so the cache may hand it just their entries
+    ///
+    /// Defaults to false, for backends that fall back to saving everything.
+    fn supports_partial_save(&self) -> bool {
+        false
+    }
+
     /// Load entries from storage
     async fn load(&self) -> Result<EntryMap<Self::Key, Self::Value, Self::Metadata>>;
 
+    /// Load entries from storage one key at a time
+    ///
+    /// When a key is yielded more than once, the last occurrence wins, as it
+    /// would in `load`. The default loads everything up front and then yields
+    /// it; backends that can read keys individually override this so large
+    /// stores are never held in memory twice.
+    fn load_stream(&self) -> EntryStream<'_, Self::Key, Self::Value, Self::Metadata> {
+        stream::once(self.load())
+            .map_ok(|entries| stream::iter(entries.into_iter().map(Ok)))
+            .try_flatten()
+            .boxed()
+    }
+
+    /// Load the entries stored under a single key
+    ///
+    /// The default loads everything and keeps one key; backends that can
+    /// read a key directly override this.
+    async fn load_key(
+        &self,
+        key: &Self::Key,
+    ) -> Result<Option<Vec<CacheEntry<Self::Key, Self::Value, Self::Metadata>>>> {
+        Ok(self.load().await?.remove(key))
+    }
+
     /// Remove entries for a specific key
     async fn remove(&self, key: &Self::Key) -> Result<()>;
 
@@ -34,8 +87,7 @@ pub trait StorageBackend: Send + Sync + 'static {
 
     /// Check if storage contains a key
     async fn contains(&self, key: &Self::Key) -> Result<bool> {
-        let entries = self.load().await?;
-        Ok(entries.contains_key(key))
+        Ok(self.load_key(key).await?.is_some())
     }
 
     /// Get approximate size of storage in bytes
@@ -47,10 +99,41 @@ pub trait StorageBackend: Send + Sync + 'static {
     async fn compact(&self) -> Result<()> {
         Ok(()) // Default is no-op
     }
+
+    /// Scope this backend to `namespace`
+    ///
+    /// `Cache::new` calls this when `CacheConfig::namespace` is set. Every
+    /// operation of the returned backend must only see entries saved under
+    /// the same namespace. The default returns the backend unchanged, which
+    /// is only correct for backends whose storage is never shared.
+    async fn with_namespace(self, _namespace: &str) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        Ok(self)
+    }
+
+    /// Store values larger than `compression.threshold` compressed
+    ///
+    /// `Cache::new` calls this when `CacheConfig::value_compression_threshold`
+    /// is set. Loads must return the values as they were saved. The default
+    /// returns the backend unchanged, so values are stored as they are; of
+    /// the bundled backends only the filesystem one, and the tiered one
+    /// through its tiers, override it.
+    #[cfg(feature = "compression")]
+    async fn with_value_compression(
+        self,
+        _compression: crate::config::ValueCompression,
+    ) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        Ok(self)
+    }
 }
 
 /// Serialization format for storage backends
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum SerializationFormat {
     /// JSON format
     #[cfg(feature = "json-serialization")]
@@ -58,9 +141,90 @@ pub enum SerializationFormat {
     /// Bincode format
     #[cfg(feature = "bincode-serialization")]
     Bincode,
+    /// MessagePack format
+    #[cfg(feature = "msgpack-serialization")]
+    MessagePack,
+    /// CBOR format
+    #[cfg(feature = "cbor-serialization")]
+    Cbor,
 }
 
+/// Layout of serialized JSON
+///
+/// Deserializing accepts either, so the style can change without rewriting
+/// stored data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum JsonStyle {
+    /// No whitespace, the smallest output
+    #[default]
+    Compact,
+    /// Indented across lines, for reading files by hand
+    Pretty,
+}
+
+/// Format backends use until `with_format` picks another one: the first of
+/// JSON, Bincode, MessagePack and CBOR that is enabled
+#[cfg(feature = "json-serialization")]
+pub(crate) const DEFAULT_FORMAT: SerializationFormat = SerializationFormat::Json;
+#[cfg(all(not(feature = "json-serialization"), feature = "bincode-serialization"))]
+pub(crate) const DEFAULT_FORMAT: SerializationFormat = SerializationFormat::Bincode;
+#[cfg(all(
+    not(feature = "json-serialization"),
+    not(feature = "bincode-serialization"),
+    feature = "msgpack-serialization"
+))]
+pub(crate) const DEFAULT_FORMAT: SerializationFormat = SerializationFormat::MessagePack;
+#[cfg(all(
+    not(feature = "json-serialization"),
+    not(feature = "bincode-serialization"),
+    not(feature = "msgpack-serialization"),
+    feature = "cbor-serialization"
+))]
+pub(crate) const DEFAULT_FORMAT: SerializationFormat = SerializationFormat::Cbor;
+
 impl SerializationFormat {
+    /// Get [`DEFAULT_FORMAT`], or an error if no format is enabled
+    pub(crate) fn default_format() -> Result<Self> {
+        #[cfg(any(
+            feature = "json-serialization",
+            feature = "bincode-serialization",
+            feature = "msgpack-serialization",
+            feature = "cbor-serialization"
+        ))]
+        return Ok(DEFAULT_FORMAT);
+        #[cfg(not(any(
+            feature = "json-serialization",
+            feature = "bincode-serialization",
+            feature = "msgpack-serialization",
+            feature = "cbor-serialization"
+        )))]
+        Err(crate::error::CacheError::Serialization(
+            "No serialization features enabled".to_string(),
+        ))
+    }
+
+    /// Find the enabled format whose file extension is `extension`
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        Self::all()
+            .iter()
+            .copied()
+            .find(|format| format.extension() == extension)
+    }
+
+    /// All formats available with the enabled features
+    pub fn all() -> &'static [SerializationFormat] {
+        &[
+            #[cfg(feature = "json-serialization")]
+            SerializationFormat::Json,
+            #[cfg(feature = "bincode-serialization")]
+            SerializationFormat::Bincode,
+            #[cfg(feature = "msgpack-serialization")]
+            SerializationFormat::MessagePack,
+            #[cfg(feature = "cbor-serialization")]
+            SerializationFormat::Cbor,
+        ]
+    }
+
     /// Get file extension for this format
     pub fn extension(&self) -> &'static str {
         match self {
@@ -68,20 +232,55 @@ impl SerializationFormat {
             SerializationFormat::Json => "json",
             #[cfg(feature = "bincode-serialization")]
             SerializationFormat::Bincode => "bin",
-            #[cfg(not(any(feature = "json-serialization", feature = "bincode-serialization")))]
+            #[cfg(feature = "msgpack-serialization")]
+            SerializationFormat::MessagePack => "msgpack",
+            #[cfg(feature = "cbor-serialization")]
+            SerializationFormat::Cbor => "cbor",
+            #[cfg(not(any(
+                feature = "json-serialization",
+                feature = "bincode-serialization",
+                feature = "msgpack-serialization",
+                feature = "cbor-serialization"
+            )))]
             _ => "data",
         }
     }
 
     /// Serialize data to bytes
-    #[allow(unused_variables)]
+    ///
+    /// JSON is written compact; see [`SerializationFormat::serialize_with`].
     pub fn serialize<T: Serialize>(&self, value: &T) -> Result<Vec<u8>> {
+        self.serialize_with(value, JsonStyle::Compact)
+    }
+
+    /// Serialize data to bytes, laying out JSON in `style`
+    ///
+    /// The style has no effect on the binary formats.
+    #[allow(unused_variables)]
+    pub fn serialize_with<T: Serialize>(&self, value: &T, style: JsonStyle) -> Result<Vec<u8>> {
         match self {
             #[cfg(feature = "json-serialization")]
-            SerializationFormat::Json => serde_json::to_vec_pretty(value).map_err(Into::into),
+            SerializationFormat::Json => match style {
+                JsonStyle::Compact => serde_json::to_vec(value),
+                JsonStyle::Pretty => serde_json::to_vec_pretty(value),
+            }
+            .map_err(Into::into),
             #[cfg(feature = "bincode-serialization")]
             SerializationFormat::Bincode => bincode::serialize(value).map_err(Into::into),
-            #[cfg(not(any(feature = "json-serialization", feature = "bincode-serialization")))]
+            #[cfg(feature = "msgpack-serialization")]
+            SerializationFormat::MessagePack => rmp_serde::to_vec_named(value).map_err(Into::into),
+            #[cfg(feature = "cbor-serialization")]
+            SerializationFormat::Cbor => {
+                let mut buffer = Vec::new();
+                ciborium::into_writer(value, &mut buffer)?;
+                Ok(buffer)
+            }
+            #[cfg(not(any(
+                feature = "json-serialization",
+                feature = "bincode-serialization",
+                feature = "msgpack-serialization",
+                feature = "cbor-serialization"
+            )))]
             _ => Err(crate::error::CacheError::Serialization(
                 "No serialization features enabled".to_string(),
             )),
@@ -96,7 +295,16 @@ impl SerializationFormat {
             SerializationFormat::Json => serde_json::from_slice(data).map_err(Into::into),
             #[cfg(feature = "bincode-serialization")]
             SerializationFormat::Bincode => bincode::deserialize(data).map_err(Into::into),
-            #[cfg(not(any(feature = "json-serialization", feature = "bincode-serialization")))]
+            #[cfg(feature = "msgpack-serialization")]
+            SerializationFormat::MessagePack => rmp_serde::from_slice(data).map_err(Into::into),
+            #[cfg(feature = "cbor-serialization")]
+            SerializationFormat::Cbor => ciborium::from_reader(data).map_err(Into::into),
+            #[cfg(not(any(
+                feature = "json-serialization",
+                feature = "bincode-serialization",
+                feature = "msgpack-serialization",
+                feature = "cbor-serialization"
+            )))]
             _ => Err(crate::error::CacheError::Serialization(
                 "No serialization features enabled".to_string(),
             )),
@@ -120,6 +328,23 @@ pub struct StorageStats {
 #[cfg(test)]
 mod tests {
     use super::*;
+
+    #[tokio::test]
+    async fn test_default_load_key_falls_back_to_load() {
+        use crate::test_utils::TestBackend;
+
+        let backend = TestBackend::default();
+        backend.entries.write().await.insert(
+            "k".to_string(),
+            vec![CacheEntry::new("k".to_string(), "v".to_string())],
+        );
+
+        let entry_vec = backend.load_key(&"k".to_string()).await.unwrap().unwrap();
+        assert_eq!(entry_vec[0].value, "v");
+        assert!(backend.load_key(&"x".to_string()).await.unwrap().is_none());
+        assert!(backend.contains(&"k".to_string()).await.unwrap());
+        assert_eq!(*backend.load_calls.read().await, 3);
+    }
     #[cfg(any(feature = "json-serialization", feature = "bincode-serialization"))]
     #[test]
     fn test_serialization_format_extension() {
@@ -130,6 +355,29 @@ mod tests {
         assert_eq!(SerializationFormat::Bincode.extension(), "bin");
     }
 
+    #[test]
+    fn test_from_extension_finds_enabled_formats() {
+        for format in SerializationFormat::all() {
+            assert_eq!(
+                SerializationFormat::from_extension(format.extension()),
+                Some(*format)
+            );
+        }
+        assert_eq!(SerializationFormat::from_extension("txt"), None);
+    }
+
+    #[cfg(feature = "msgpack-serialization")]
+    #[test]
+    fn test_msgpack_serialization_format_extension() {
+        assert_eq!(SerializationFormat::MessagePack.extension(), "msgpack");
+    }
+
+    #[cfg(feature = "cbor-serialization")]
+    #[test]
+    fn test_cbor_serialization_format_extension() {
+        assert_eq!(SerializationFormat::Cbor.extension(), "cbor");
+    }
+
     #[cfg(feature = "json-serialization")]
     #[test]
     fn test_json_serialization() {
@@ -151,6 +399,68 @@ mod tests {
         assert_eq!(data, deserialized);
     }
 
+    #[cfg(all(
+        feature = "json-serialization",
+        any(feature = "msgpack-serialization", feature = "cbor-serialization")
+    ))]
+    fn assert_entry_round_trip_matches_json(format: SerializationFormat) {
+        use crate::entry::BasicMetadata;
+
+        let metadata = BasicMetadata {
+            execution_time_ms: Some(12),
+            size_bytes: None,
+            category: Some("scan".to_string()),
+            tags: vec!["a".to_string(), "b".to_string()],
+        };
+        let entries = vec![
+            CacheEntry::with_metadata("key".to_string(), "value".to_string(), metadata),
+            CacheEntry::new("key".to_string(), "other".to_string())
+                .with_ttl(chrono::Duration::seconds(60)),
+        ];
+
+        let bytes = format.serialize(&entries).unwrap();
+        let decoded: Vec<CacheEntry<String, String, BasicMetadata>> =
+            format.deserialize(&bytes).unwrap();
+
+        let json = SerializationFormat::Json;
+        assert_eq!(
+            json.serialize(&decoded).unwrap(),
+            json.serialize(&entries).unwrap()
+        );
+    }
+
+    #[cfg(all(feature = "json-serialization", feature = "msgpack-serialization"))]
+    #[test]
+    fn test_msgpack_entry_round_trip_matches_json() {
+        assert_entry_round_trip_matches_json(SerializationFormat::MessagePack);
+    }
+
+    #[cfg(all(feature = "json-serialization", feature = "cbor-serialization"))]
+    #[test]
+    fn test_cbor_entry_round_trip_matches_json() {
+        assert_entry_round_trip_matches_json(SerializationFormat::Cbor);
+    }
+
+    #[cfg(feature = "cbor-serialization")]
+    #[test]
+    fn test_cbor_invalid_data() {
+        let result: Result<Vec<String>> = SerializationFormat::Cbor.deserialize(b"\xff");
+        assert!(matches!(
+            result,
+            Err(crate::error::CacheError::Deserialization(_))
+        ));
+    }
+
+    #[cfg(feature = "msgpack-serialization")]
+    #[test]
+    fn test_msgpack_invalid_data() {
+        let result: Result<Vec<String>> = SerializationFormat::MessagePack.deserialize(b"\xc1");
+        assert!(matches!(
+            result,
+            Err(crate::error::CacheError::Deserialization(_))
+        ));
+    }
+
     #[tokio::test]
     async fn test_default_storage_methods() {
         use crate::test_utils::TestBackend;
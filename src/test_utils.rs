@@ -6,16 +6,45 @@ use std::sync::Arc;
 use tokio::sync::RwLock;
 
 #[cfg(test)]
-use crate::{CacheEntry, Result, StorageBackend};
+use crate::{storage::EntryStream, CacheEntry, CacheError, Result, StorageBackend};
+#[cfg(test)]
+use futures::stream::{self, StreamExt, TryStreamExt};
 
 /// Test backend storing entries in memory and tracking save/load calls.
+///
+/// While `failures` is nonzero, each save, load, remove and clear counts it
+/// down and fails with a storage backend error. While `truncated_loads` is
+/// set, loads stream that many keys, in key order, and then fail.
 #[cfg(test)]
 #[derive(Clone, Default)]
 #[allow(clippy::type_complexity)]
 pub(crate) struct TestBackend {
     pub entries: Arc<RwLock<HashMap<String, Vec<CacheEntry<String, String, ()>>>>>,
     pub save_calls: Arc<RwLock<usize>>,
+    /// Keys of each `save_keys` call, in order
+    pub saved_keys: Arc<RwLock<Vec<Vec<String>>>>,
+    /// Number of keys in the entries handed to each `save_keys` call
+    pub saved_entry_counts: Arc<RwLock<Vec<usize>>>,
     pub load_calls: Arc<RwLock<usize>>,
+    pub failures: Arc<RwLock<usize>>,
+    pub truncated_loads: Arc<RwLock<Option<usize>>>,
+    /// Held by each save while it runs, so a test can stall saves
+    pub save_gate: Arc<tokio::sync::Mutex<()>>,
+    /// Held by each remove and clear while it runs, so a test can stall them
+    pub remove_gate: Arc<tokio::sync::Mutex<()>>,
+}
+
+#[cfg(test)]
+impl TestBackend {
+    /// Fail if failures are still pending, using one up
+    async fn maybe_fail(&self) -> Result<()> {
+        let mut failures = self.failures.write().await;
+        if *failures == 0 {
+            return Ok(());
+        }
+        *failures -= 1;
+        Err(CacheError::StorageBackend("injected failure".to_string()))
+    }
 }
 
 #[cfg(test)]
@@ -30,28 +59,174 @@ impl StorageBackend for TestBackend {
         entries: &HashMap<Self::Key, Vec<CacheEntry<Self::Key, Self::Value, Self::Metadata>>>,
     ) -> Result<()> {
         *self.save_calls.write().await += 1;
+        let _gate = self.save_gate.lock().await;
+        self.maybe_fail().await?;
         *self.entries.write().await = entries.clone();
         Ok(())
     }
 
+    async fn save_keys(
+        &self,
+        entries: &HashMap<Self::Key, Vec<CacheEntry<Self::Key, Self::Value, Self::Metadata>>>,
+        keys: &[Self::Key],
+    ) -> Result<()> {
+        let mut sorted = keys.to_vec();
+        sorted.sort();
+        self.saved_keys.write().await.push(sorted);
+        self.saved_entry_counts.write().await.push(entries.len());
+        let _gate = self.save_gate.lock().await;
+        self.maybe_fail().await?;
+        let mut stored = self.entries.write().await;
+        for key in keys {
+            match entries.get(key) {
+                Some(bucket) => stored.insert(key.clone(), bucket.clone()),
+                None => stored.remove(key),
+            };
+        }
+        Ok(())
+    }
+
+    fn supports_partial_save(&self) -> bool {
+        true
+    }
+
     async fn load(
         &self,
     ) -> Result<HashMap<Self::Key, Vec<CacheEntry<Self::Key, Self::Value, Self::Metadata>>>> {
         *self.load_calls.write().await += 1;
+        self.maybe_fail().await?;
         Ok(self.entries.read().await.clone())
     }
 
+    fn load_stream(&self) -> EntryStream<'_, Self::Key, Self::Value, Self::Metadata> {
+        stream::once(async move {
+            let mut entries: Vec<_> = self.load().await?.into_iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(&b.0));
+            let items: Vec<Result<_>> = match *self.truncated_loads.read().await {
+                Some(keys) => entries
+                    .into_iter()
+                    .take(keys)
+                    .map(Ok)
+                    .chain([Err(CacheError::StorageBackend("truncated".to_string()))])
+                    .collect(),
+                None => entries.into_iter().map(Ok).collect(),
+            };
+            Result::Ok(stream::iter(items))
+        })
+        .try_flatten()
+        .boxed()
+    }
+
     async fn remove(&self, key: &Self::Key) -> Result<()> {
+        let _gate = self.remove_gate.lock().await;
+        self.maybe_fail().await?;
         self.entries.write().await.remove(key);
         Ok(())
     }
 
     async fn clear(&self) -> Result<()> {
+        let _gate = self.remove_gate.lock().await;
+        self.maybe_fail().await?;
         self.entries.write().await.clear();
         Ok(())
     }
 }
 
+/// Subscriber recording every span's name and fields, for asserting on the
+/// cache's tracing output
+#[cfg(all(test, feature = "tracing"))]
+#[derive(Default)]
+pub(crate) struct RecordingSubscriber {
+    spans: std::sync::Mutex<Vec<RecordedSpan>>,
+    stack: std::sync::Mutex<Vec<tracing::span::Id>>,
+}
+
+/// A span seen by [`RecordingSubscriber`]
+#[cfg(all(test, feature = "tracing"))]
+#[derive(Clone, Debug)]
+pub(crate) struct RecordedSpan {
+    pub metadata: &'static tracing::Metadata<'static>,
+    pub fields: HashMap<String, String>,
+}
+
+#[cfg(all(test, feature = "tracing"))]
+impl RecordingSubscriber {
+    /// Get the recorded spans named `name`
+    pub fn spans_named(&self, name: &str) -> Vec<RecordedSpan> {
+        self.spans
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|span| span.metadata.name() == name)
+            .cloned()
+            .collect()
+    }
+
+    fn index(id: &tracing::span::Id) -> usize {
+        id.into_u64() as usize - 1
+    }
+}
+
+#[cfg(all(test, feature = "tracing"))]
+struct FieldVisitor<'a>(&'a mut HashMap<String, String>);
+
+#[cfg(all(test, feature = "tracing"))]
+impl tracing::field::Visit for FieldVisitor<'_> {
+    fn record_str(&mut self, field: &tracing::field::Field, value: &str) {
+        self.0.insert(field.name().to_string(), value.to_string());
+    }
+
+    fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+        self.0
+            .insert(field.name().to_string(), format!("{value:?}"));
+    }
+}
+
+#[cfg(all(test, feature = "tracing"))]
+impl tracing::Subscriber for RecordingSubscriber {
+    fn enabled(&self, _metadata: &tracing::Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, attrs: &tracing::span::Attributes<'_>) -> tracing::span::Id {
+        let mut fields = HashMap::new();
+        attrs.record(&mut FieldVisitor(&mut fields));
+        let mut spans = self.spans.lock().unwrap();
+        spans.push(RecordedSpan {
+            metadata: attrs.metadata(),
+            fields,
+        });
+        tracing::span::Id::from_u64(spans.len() as u64)
+    }
+
+    fn record(&self, span: &tracing::span::Id, values: &tracing::span::Record<'_>) {
+        let mut spans = self.spans.lock().unwrap();
+        values.record(&mut FieldVisitor(&mut spans[Self::index(span)].fields));
+    }
+
+    fn record_follows_from(&self, _span: &tracing::span::Id, _follows: &tracing::span::Id) {}
+
+    fn event(&self, _event: &tracing::Event<'_>) {}
+
+    fn enter(&self, span: &tracing::span::Id) {
+        self.stack.lock().unwrap().push(span.clone());
+    }
+
+    fn exit(&self, _span: &tracing::span::Id) {
+        self.stack.lock().unwrap().pop();
+    }
+
+    fn current_span(&self) -> tracing_core::span::Current {
+        match self.stack.lock().unwrap().last() {
+            Some(id) => {
+                let metadata = self.spans.lock().unwrap()[Self::index(id)].metadata;
+                tracing_core::span::Current::new(id.clone(), metadata)
+            }
+            None => tracing_core::span::Current::none(),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
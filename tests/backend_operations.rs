@@ -1,12 +1,17 @@
 use std::collections::HashMap;
 
 use threatflux_cache::backends::memory::MemoryBackend;
+use threatflux_cache::backends::tiered::TieredBackend;
 use threatflux_cache::{CacheEntry, StorageBackend};
 
-#[cfg(feature = "filesystem-backend")]
+#[cfg(any(feature = "filesystem-backend", feature = "append-log-backend"))]
 use tempfile::TempDir;
+#[cfg(feature = "append-log-backend")]
+use threatflux_cache::backends::append_log::AppendLogBackend;
 #[cfg(feature = "filesystem-backend")]
 use threatflux_cache::backends::filesystem::FilesystemBackend;
+#[cfg(feature = "sqlite-backend")]
+use threatflux_cache::backends::sqlite::SqliteBackend;
 
 async fn run_basic_backend_tests<B>(backend: B)
 where
@@ -61,3 +66,71 @@ async fn filesystem_backend_operations() {
         FilesystemBackend::new(temp_dir.path()).await.unwrap();
     run_basic_backend_tests(backend).await;
 }
+
+#[cfg(feature = "append-log-backend")]
+#[tokio::test]
+async fn append_log_backend_operations() {
+    let temp_dir = TempDir::new().unwrap();
+    let backend: AppendLogBackend<String, String> =
+        AppendLogBackend::new(temp_dir.path()).await.unwrap();
+    run_basic_backend_tests(backend).await;
+}
+
+#[cfg(feature = "sqlite-backend")]
+#[tokio::test]
+async fn sqlite_backend_operations() {
+    let backend: SqliteBackend<String, String> = SqliteBackend::in_memory().await.unwrap();
+    run_basic_backend_tests(backend).await;
+}
+
+#[tokio::test]
+async fn tiered_memory_backend_operations() {
+    let backend = TieredBackend::new(
+        MemoryBackend::<String, String>::new(),
+        MemoryBackend::<String, String>::new(),
+    );
+    run_basic_backend_tests(backend).await;
+}
+
+#[cfg(feature = "filesystem-backend")]
+#[tokio::test]
+async fn tiered_filesystem_backend_operations() {
+    let temp_dir = TempDir::new().unwrap();
+    let slow: FilesystemBackend<String, String> =
+        FilesystemBackend::new(temp_dir.path()).await.unwrap();
+    let backend = TieredBackend::new(MemoryBackend::new(), slow);
+    run_basic_backend_tests(backend).await;
+}
+
+#[cfg(all(feature = "filesystem-backend", feature = "compression"))]
+#[tokio::test]
+async fn filesystem_backend_compresses_large_values_from_config() {
+    use threatflux_cache::{AsyncCache, Cache, CacheConfig};
+
+    let temp_dir = TempDir::new().unwrap();
+    let mut config = CacheConfig::default().with_value_compression_threshold(1024);
+    config.persistence.enabled = true;
+    config.persistence.load_on_startup = true;
+    let blob = "0123456789".repeat(10_000);
+
+    let backend: FilesystemBackend<String, String> =
+        FilesystemBackend::new(temp_dir.path()).await.unwrap();
+    let cache = Cache::new(config.clone(), backend).await.unwrap();
+    cache.put("blob".to_string(), blob.clone()).await.unwrap();
+    cache.flush().await.unwrap();
+    // The value in memory is left as it was
+    assert_eq!(
+        cache.get(&"blob".to_string()).await.unwrap(),
+        Some(blob.clone())
+    );
+
+    let file_len = std::fs::metadata(temp_dir.path().join("blob.json"))
+        .unwrap()
+        .len();
+    assert!(file_len < blob.len() as u64 / 10);
+
+    let backend: FilesystemBackend<String, String> =
+        FilesystemBackend::new(temp_dir.path()).await.unwrap();
+    let reloaded = Cache::new(config, backend).await.unwrap();
+    assert_eq!(reloaded.get(&"blob".to_string()).await.unwrap(), Some(blob));
+}